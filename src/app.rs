@@ -1,92 +1,1111 @@
+use crate::bulk::{BulkAction, BulkJob};
+use crate::config::{CardConfig, ViewConfig, ViewLayout};
+use crate::duplicates;
+use crate::group::{self, GroupBy};
+use crate::images::ImageCache;
+use crate::journal::Journal;
+use crate::keys::PendingKeys;
 use crate::models::Note;
-use ratatui_image::protocol::StatefulProtocol;
-use std::collections::HashMap;
+use crate::mouse::Click;
+use crate::search::{self, NoteHighlights};
+use crate::search::fulltext::FullTextIndex;
+use crate::search::query::Filter;
+use crate::search::related::{self, RelatedNote, RELATED_LIMIT};
+use crate::sort::{self, Sort};
+use crate::surprise::Surprise;
+use crate::tags::{self, TagNode};
+use crate::vault::VaultChange;
+use chrono::Local;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 
-// TODO: fix this, it affects the number of notes rendered in the UI
-pub const PAGE_SIZE: usize = 4;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SearchKind {
+    #[default]
+    Fuzzy,    // Titles, excerpts, tags and URLs
+    FullText, // Whole note bodies through the full-text index
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptKind {
+    Search(SearchKind),
+    Filter,
+    SurpriseBudget, // Minutes to pick a surprise note within
+    Command,        // A `:` command
+    AddTag,         // Tag to add to the marked notes
+    RemoveTag,
+    Export,         // File to write the marked notes to
+}
+
+/// A line of input being typed in the status bar.
+#[derive(Debug, Clone)]
+pub struct Prompt {
+    pub kind: PromptKind,
+    pub input: String,
+    pub completions: Vec<String>, // Inputs Tab cycles through, empty until Tab is pressed
+    pub completion: usize,
+    pub history_position: Option<usize>, // Index into `App.command_history` while browsing it
+}
+
+/// An active search: the ranked matches replace the browse order.
+#[derive(Debug, Clone, Default)]
+pub struct Search {
+    pub kind: SearchKind,
+    pub query: String,
+    pub results: Vec<usize>, // Indexes into `notes`, best match first
+    pub highlights: HashMap<usize, NoteHighlights>,
+    pub excerpts: HashMap<usize, String>, // Matching snippets shown instead of the excerpt
+}
+
+/// A filter query narrowing down which notes are shown, while browsing or searching.
+#[derive(Debug, Clone)]
+pub struct ActiveFilter {
+    pub query: String,
+    pub filter: Filter,
+}
+
+/// A line of the page: a note, or the header of a group in a grouped list.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Row {
+    Header { label: String, count: usize, collapsed: bool },
+    Note(usize), // Index into `notes`
+}
+
+/// A tab with its own filter, order and layout. Page, selection and collapsed
+/// groups are remembered while another view is active.
+#[derive(Debug, Clone)]
+pub struct View {
+    pub name: String,
+    pub filter: Filter,
+    pub filter_error: Option<String>, // A query that doesn't parse shows no notes
+    pub sort: Sort,
+    pub layout: ViewLayout,
+    pub group_by: Option<GroupBy>, // Only applies to the list layout
+    collapsed: HashSet<String>, // Labels of the folded groups
+    page: usize,
+    selected: usize,
+}
+
+impl Default for View {
+    fn default() -> Self {
+        View {
+            name: "All".to_string(),
+            filter: Filter::All,
+            filter_error: None,
+            sort: Sort::default(),
+            layout: ViewLayout::default(),
+            group_by: None,
+            collapsed: HashSet::new(),
+            page: 0,
+            selected: 0,
+        }
+    }
+}
+
+impl View {
+    pub fn from_config(config: &ViewConfig) -> Self {
+        let (filter, filter_error) = match Filter::parse(&config.filter) {
+            Ok(filter) => (filter, None),
+            Err(error) => (Filter::Not(Box::new(Filter::All)), Some(error.to_string())),
+        };
+        View {
+            name: config.name.clone(),
+            filter,
+            filter_error,
+            sort: config.sort,
+            layout: config.layout,
+            group_by: config.group_by,
+            ..View::default()
+        }
+    }
+}
+
+/// A card's place in the page's grid, counted from the top left.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    pub row: usize,
+    pub col: usize,
+}
+
+/// The notes most related to `source`, shown in a popup.
+#[derive(Debug, Clone)]
+pub struct RelatedPanel {
+    pub source: usize, // Index into `notes`
+    pub items: Vec<RelatedNote>,
+    pub selected: usize,
+}
+
+/// Keys and what they do, shown in a popup.
+#[derive(Debug, Clone)]
+pub struct HelpPanel {
+    pub entries: Vec<(String, String)>, // Keys, then the action
+    pub scroll: usize, // First entry shown
+}
+
+/// Groups of notes saved from the same canonical URL, shown in a popup.
+#[derive(Debug, Clone)]
+pub struct DuplicatesPanel {
+    pub groups: Vec<Vec<usize>>, // Indexes into `notes`
+    pub selected: usize, // Position across all groups
+}
+
+impl DuplicatesPanel {
+    pub fn len(&self) -> usize {
+        self.groups.iter().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// The selected note's group and its index into `notes`.
+    pub fn selected_note(&self) -> Option<(&[usize], usize)> {
+        let mut position = self.selected;
+        for group in &self.groups {
+            if position < group.len() {
+                return Some((group, group[position]));
+            }
+            position -= group.len();
+        }
+        None
+    }
+}
+
+/// Whether notes need every checked tag or just one of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TagMatch {
+    #[default]
+    All,
+    Any,
+}
+
+/// The tag browser on the left. Checked tags keep filtering while it is closed.
+#[derive(Debug, Clone, Default)]
+pub struct TagSidebar {
+    pub open: bool,
+    pub focused: bool, // Takes the keyboard, otherwise the cards do
+    pub tree: Vec<TagNode>,
+    pub selected: usize,
+    pub checked: Vec<String>, // Full lowercase tags, in the order they were checked
+    pub matching: TagMatch,
+}
+
+impl TagSidebar {
+    pub fn selected_tag(&self) -> Option<&TagNode> {
+        self.tree.get(self.selected)
+    }
+
+    pub fn is_checked(&self, tag: &str) -> bool {
+        self.checked.iter().any(|checked| checked == tag)
+    }
+
+    /// The checked tags as a filter, `tag:` matching nested tags too.
+    pub fn filter(&self) -> Option<Filter> {
+        let tags: Vec<Filter> = self.checked.iter().map(|tag| Filter::Tag(tag.clone())).collect();
+        match (tags.len(), self.matching) {
+            (0, _) => None,
+            (1, _) => tags.into_iter().next(),
+            (_, TagMatch::All) => Some(Filter::And(tags)),
+            (_, TagMatch::Any) => Some(Filter::Or(tags)),
+        }
+    }
+
+    /// "rust & go" or "rust | go", for the status bar.
+    pub fn label(&self) -> String {
+        let separator = match self.matching {
+            TagMatch::All => " & ",
+            TagMatch::Any => " | ",
+        };
+        self.checked.join(separator)
+    }
+}
 
 pub struct App {
     pub notes: Vec<Note>,
-    pub selected_note_index: usize, // Index on the current page
-    pub image_cache: HashMap<PathBuf, Box<dyn StatefulProtocol>>,
+    pub visible: Vec<usize>, // Indexes into `notes` the view shows, in display order, folded groups included
+    pub rows: Vec<Row>, // What gets paginated: `visible` with group headers, minus folded groups
+    pub selected_note_index: usize, // Row index on the current page
+    pub image_cache: ImageCache,
     pub current_page: usize,
+    pub hide_read: bool,
+    pub browse_limit: usize, // Most recent notes shown while browsing unfiltered by date, 0 for all
+    pub prompt: Option<Prompt>,
+    pub search: Option<Search>,
+    pub fulltext: FullTextIndex,
+    pub filter: Option<ActiveFilter>,
+    pub filter_error: Option<String>, // Why the query being typed doesn't parse
+    pub views: Vec<View>,
+    pub active_view: usize,
+    pub status_message: Option<String>, // Shown in the status bar until the next key
+    pub related: Option<RelatedPanel>,
+    pub duplicates: Option<DuplicatesPanel>,
+    pub card: CardConfig, // Which tags count when grouping and relating notes
+    pub tags: TagSidebar,
+    pub pending_keys: PendingKeys, // Count and sequence typed so far, shown in the status bar
+    pub command_history: Vec<String>, // `:` commands run this session, oldest first
+    pub last_click: Option<Click>, // For telling double clicks
+    pub marked: HashSet<PathBuf>, // Notes picked for a bulk action, by file so marks survive vault changes
+    pub bulk_menu: bool,
+    pub bulk: Option<BulkJob>,
+    pub journal: Journal, // Changes to the vault that `u` can undo
+    pub help: Option<HelpPanel>,
 }
 
 impl App {
     pub fn new(notes: Vec<Note>) -> Self {
-        App {
+        let mut app = App {
             notes,
+            visible: Vec::new(),
+            rows: Vec::new(),
             selected_note_index: 0,
-            image_cache: HashMap::new(),
+            image_cache: ImageCache::disabled(),
             current_page: 0,
+            hide_read: false,
+            browse_limit: 0,
+            prompt: None,
+            search: None,
+            fulltext: FullTextIndex::default(),
+            filter: None,
+            filter_error: None,
+            views: vec![View::default()],
+            active_view: 0,
+            status_message: None,
+            related: None,
+            duplicates: None,
+            card: CardConfig::default(),
+            tags: TagSidebar::default(),
+            pending_keys: PendingKeys::default(),
+            command_history: Vec::new(),
+            last_click: None,
+            marked: HashSet::new(),
+            bulk_menu: false,
+            bulk: None,
+            journal: Journal::default(),
+            help: None,
+        };
+        app.refresh_visible();
+        app
+    }
+
+    /// Whether a note passes the view filter, the active filter, the checked tags and the read toggle.
+    fn shown_filter(&self) -> impl Fn(&usize) -> bool + '_ {
+        let view = self.view();
+        let tag_filter = self.tags.filter();
+        move |i: &usize| {
+            let note = &self.notes[*i];
+            !(self.hide_read && note.is_done())
+                && view.filter.matches(note)
+                && self.filter.as_ref().is_none_or(|active| active.filter.matches(note))
+                && tag_filter.as_ref().is_none_or(|filter| filter.matches(note))
+        }
+    }
+
+    /// Recomputes which notes and group headers are paginated and keeps page and selection in range.
+    pub fn refresh_visible(&mut self) {
+        if self.tags.open {
+            self.refresh_tag_tree();
+        }
+        let view = self.view();
+        // The limit only trims the default newest-first browse, filters and other orders see every note
+        let limited = self.browse_limit > 0
+            && view.filter == Filter::All
+            && view.sort == Sort::default()
+            && self.filter.is_none()
+            && self.tags.checked.is_empty();
+        let is_shown = self.shown_filter();
+        self.visible = match &self.search {
+            Some(search) => search.results.iter().copied().filter(is_shown).collect(),
+            None => {
+                let mut visible: Vec<usize> = (0..self.notes.len()).filter(is_shown).collect();
+                view.sort.apply(&self.notes, &mut visible);
+                if limited {
+                    visible.truncate(self.browse_limit);
+                }
+                visible
+            }
+        };
+
+        self.rows = match self.grouping() {
+            Some(by) => {
+                let view = self.view();
+                let groups = group::group_notes(&self.notes, &self.visible, by, &self.card, Local::now().date_naive());
+                let mut rows = Vec::new();
+                let mut visible = Vec::new();
+                for group in groups {
+                    let collapsed = view.collapsed.contains(&group.label);
+                    rows.push(Row::Header { label: group.label, count: group.notes.len(), collapsed });
+                    if !collapsed {
+                        rows.extend(group.notes.iter().map(|&i| Row::Note(i)));
+                    }
+                    visible.extend(group.notes);
+                }
+                self.visible = visible;
+                rows
+            }
+            None => self.visible.iter().map(|&i| Row::Note(i)).collect(),
+        };
+
+        let total_pages = self.total_pages();
+        if self.current_page >= total_pages {
+            self.current_page = total_pages.saturating_sub(1);
+        }
+        let rows_on_page = self.rows_on_current_page().len();
+        if self.selected_note_index >= rows_on_page {
+            self.selected_note_index = rows_on_page.saturating_sub(1);
+        }
+    }
+
+    /// How the active view is grouped, if at all. Grids are never grouped.
+    pub fn grouping(&self) -> Option<GroupBy> {
+        let view = self.view();
+        view.group_by.filter(|_| matches!(view.layout, ViewLayout::List { .. }))
+    }
+
+    /// Regroups the active view, keeping the selected note selected.
+    pub fn set_group_by(&mut self, group_by: Option<GroupBy>) {
+        let selected = self.selected_absolute_index();
+        self.views[self.active_view].group_by = group_by;
+        self.refresh_visible();
+        match selected {
+            Some(selected) => {
+                self.select_note(selected);
+            }
+            None => self.select_position(0),
+        }
+    }
+
+    pub fn cycle_group_by(&mut self) {
+        if !matches!(self.view().layout, ViewLayout::List { .. }) {
+            self.status_message = Some("Only list views can be grouped".to_string());
+            return;
+        }
+        self.set_group_by(GroupBy::cycle(self.view().group_by));
+    }
+
+    /// Folds or unfolds the group of the selected row, then selects its header.
+    pub fn toggle_group(&mut self) {
+        let position = self.selected_position();
+        let Some(label) = self.rows.get(..=position.min(self.rows.len().saturating_sub(1)))
+            .and_then(|rows| rows.iter().rev().find_map(|row| match row {
+                Row::Header { label, .. } => Some(label.clone()),
+                Row::Note(_) => None,
+            }))
+        else {
+            return;
+        };
+        let collapsed = &mut self.views[self.active_view].collapsed;
+        if !collapsed.remove(&label) {
+            collapsed.insert(label.clone());
+        }
+        self.refresh_visible();
+        if let Some(header) = self.rows.iter().position(|row| matches!(row, Row::Header { label: other, .. } if *other == label)) {
+            self.select_position(header);
+        }
+    }
+
+    /// Selects a note the view shows, unfolding its group if needed. Returns
+    /// whether it is shown at all.
+    pub fn select_note(&mut self, note_index: usize) -> bool {
+        if !self.visible.contains(&note_index) {
+            return false;
+        }
+        if let Some(by) = self.grouping() {
+            let label = group::group_label(&self.notes[note_index], by, &self.card, Local::now().date_naive());
+            if self.views[self.active_view].collapsed.remove(&label) {
+                self.refresh_visible();
+            }
+        }
+        match self.rows.iter().position(|row| *row == Row::Note(note_index)) {
+            Some(position) => {
+                self.select_position(position);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Recounts the tags, keeping the same tag selected where it still exists.
+    fn refresh_tag_tree(&mut self) {
+        let selected = self.tags.selected_tag().map(|node| node.tag.clone());
+        self.tags.tree = tags::tag_tree(&self.notes, &self.card);
+        self.tags.selected = selected
+            .and_then(|selected| self.tags.tree.iter().position(|node| node.tag == selected))
+            .unwrap_or(self.tags.selected)
+            .min(self.tags.tree.len().saturating_sub(1));
+    }
+
+    /// Opens and focuses the tag sidebar, or closes it once it has the keyboard.
+    pub fn toggle_tag_sidebar(&mut self) {
+        if self.tags.open && self.tags.focused {
+            self.tags.open = false;
+            self.tags.focused = false;
+        } else {
+            self.tags.open = true;
+            self.tags.focused = true;
+            self.refresh_tag_tree();
+        }
+    }
+
+    /// Hands the keyboard back to the cards, leaving the sidebar open.
+    pub fn unfocus_tag_sidebar(&mut self) {
+        self.tags.focused = false;
+    }
+
+    pub fn next_tag(&mut self) {
+        if !self.tags.tree.is_empty() {
+            self.tags.selected = (self.tags.selected + 1) % self.tags.tree.len();
+        }
+    }
+
+    pub fn previous_tag(&mut self) {
+        let len = self.tags.tree.len();
+        if len > 0 {
+            self.tags.selected = (self.tags.selected + len - 1) % len;
+        }
+    }
+
+    /// Checks or unchecks the selected tag, combining it with the other checked ones.
+    pub fn toggle_tag_checked(&mut self) {
+        let Some(tag) = self.tags.selected_tag().map(|node| node.tag.clone()) else {
+            return;
+        };
+        if self.tags.is_checked(&tag) {
+            self.tags.checked.retain(|checked| *checked != tag);
+        } else {
+            self.tags.checked.push(tag);
+        }
+        self.refilter_tags();
+    }
+
+    /// Checks only the selected tag.
+    pub fn only_tag(&mut self) {
+        let Some(tag) = self.tags.selected_tag().map(|node| node.tag.clone()) else {
+            return;
+        };
+        self.tags.checked = vec![tag];
+        self.refilter_tags();
+    }
+
+    pub fn toggle_tag_match(&mut self) {
+        self.tags.matching = match self.tags.matching {
+            TagMatch::All => TagMatch::Any,
+            TagMatch::Any => TagMatch::All,
+        };
+        if self.tags.checked.len() > 1 {
+            self.refilter_tags();
+        }
+    }
+
+    pub fn clear_tags(&mut self) {
+        if !self.tags.checked.is_empty() {
+            self.tags.checked.clear();
+            self.refilter_tags();
+        }
+    }
+
+    fn refilter_tags(&mut self) {
+        self.current_page = 0;
+        self.selected_note_index = 0;
+        self.refresh_visible();
+    }
+
+    pub fn is_marked(&self, note_index: usize) -> bool {
+        self.marked.contains(&self.notes[note_index].path)
+    }
+
+    /// Marks or unmarks the selected note.
+    pub fn toggle_mark(&mut self) {
+        if let Some(path) = self.selected_note().map(|note| note.path.clone())
+            && !self.marked.remove(&path)
+        {
+            self.marked.insert(path);
+        }
+    }
+
+    /// Marks every note on the page, or unmarks them when they all are already.
+    pub fn mark_page(&mut self) {
+        let indexes: Vec<usize> = self.rows_on_current_page().iter()
+            .filter_map(|row| match row {
+                Row::Note(i) => Some(*i),
+                Row::Header { .. } => None,
+            })
+            .collect();
+        self.mark_all_of(indexes);
+    }
+
+    /// Marks every note the view shows on any page, or unmarks them when they all are already.
+    pub fn mark_all(&mut self) {
+        self.mark_all_of(self.visible.clone());
+    }
+
+    fn mark_all_of(&mut self, indexes: Vec<usize>) {
+        let paths: Vec<PathBuf> = indexes.into_iter().map(|i| self.notes[i].path.clone()).collect();
+        if paths.iter().all(|path| self.marked.contains(path)) {
+            for path in &paths {
+                self.marked.remove(path);
+            }
+        } else {
+            self.marked.extend(paths);
+        }
+    }
+
+    pub fn clear_marks(&mut self) {
+        self.marked.clear();
+    }
+
+    /// Files of the marked notes, in vault order.
+    pub fn marked_paths(&self) -> Vec<PathBuf> {
+        self.notes.iter()
+            .filter(|note| self.marked.contains(&note.path))
+            .map(|note| note.path.clone())
+            .collect()
+    }
+
+    /// Opens the bulk action menu, or says how to mark notes first.
+    pub fn open_bulk_menu(&mut self) {
+        if self.marked.is_empty() {
+            self.status_message = Some("No notes are marked, m marks the selected one".to_string());
+        } else {
+            self.bulk_menu = true;
         }
     }
 
-    pub fn notes_on_current_page(&self) -> &[Note] {
-        let start = self.current_page * PAGE_SIZE;
-        let end = (start + PAGE_SIZE).min(self.notes.len());
-        &self.notes[start..end]
+    pub fn close_bulk_menu(&mut self) {
+        self.bulk_menu = false;
+    }
+
+    pub fn open_help(&mut self, entries: Vec<(String, String)>) {
+        self.help = Some(HelpPanel { entries, scroll: 0 });
+    }
+
+    pub fn close_help(&mut self) {
+        self.help = None;
+    }
+
+    /// Scrolls the help by `lines`, up when negative, keeping the last entry in view.
+    pub fn scroll_help(&mut self, lines: isize) {
+        if let Some(panel) = &mut self.help {
+            let last = panel.entries.len().saturating_sub(1);
+            panel.scroll = panel.scroll.saturating_add_signed(lines).min(last);
+        }
+    }
+
+    /// Queues `action` for the notes at `paths`. `bulk::step` runs it a few notes at a time.
+    pub fn start_bulk(&mut self, action: BulkAction, paths: Vec<PathBuf>) {
+        self.bulk_menu = false;
+        if self.bulk.is_some() {
+            self.status_message = Some("Wait for the running bulk action to finish".to_string());
+        } else if !paths.is_empty() {
+            self.bulk = Some(BulkJob::new(action, paths));
+        }
+    }
+
+    /// Replaces the views with the configured ones and switches to the first.
+    pub fn set_views(&mut self, configs: &[ViewConfig]) {
+        self.views = configs.iter().map(View::from_config).collect();
+        if self.views.is_empty() {
+            self.views.push(View::default());
+        }
+        self.active_view = 0;
+        self.current_page = 0;
+        self.selected_note_index = 0;
+        self.refresh_visible();
+    }
+
+    pub fn view(&self) -> &View {
+        &self.views[self.active_view]
+    }
+
+    /// Switches tabs, restoring the page and selection the view had when it was left.
+    pub fn switch_view(&mut self, index: usize) {
+        if index >= self.views.len() || index == self.active_view {
+            return;
+        }
+        let current = &mut self.views[self.active_view];
+        current.page = self.current_page;
+        current.selected = self.selected_note_index;
+
+        self.active_view = index;
+        self.current_page = self.views[index].page;
+        self.selected_note_index = self.views[index].selected;
+        self.refresh_visible();
+    }
+
+    pub fn next_view(&mut self) {
+        self.switch_view((self.active_view + 1) % self.views.len());
+    }
+
+    pub fn previous_view(&mut self) {
+        self.switch_view((self.active_view + self.views.len() - 1) % self.views.len());
+    }
+
+    /// Re-sorts the active view, keeping the selected note selected.
+    pub fn set_sort(&mut self, sort: Sort) {
+        let selected = self.selected_absolute_index();
+        self.views[self.active_view].sort = sort;
+        self.refresh_visible();
+        if let Some(selected) = selected {
+            self.select_note(selected);
+        }
+    }
+
+    /// Changes the active view's layout, keeping the selected note selected.
+    pub fn set_layout(&mut self, layout: ViewLayout) {
+        let selected = self.selected_absolute_index();
+        self.views[self.active_view].layout = layout;
+        self.current_page = 0;
+        self.selected_note_index = 0;
+        self.refresh_visible();
+        if let Some(selected) = selected {
+            self.select_note(selected);
+        }
+    }
+
+    pub fn cycle_sort_key(&mut self) {
+        self.set_sort(self.view().sort.next_key());
+    }
+
+    pub fn reverse_sort(&mut self) {
+        self.set_sort(self.view().sort.reversed());
+    }
+
+    /// Selects a weighted random unread note among every one the view and its filters let through,
+    /// including the ones past the browse limit.
+    pub fn surprise_me(&mut self, surprise: &Surprise, max_minutes: Option<usize>) {
+        let candidates: Vec<usize> = (0..self.notes.len()).filter(self.shown_filter()).collect();
+        match surprise.pick(&self.notes, &candidates, max_minutes, sort::time_seed()) {
+            Some(note_index) => self.jump_to_note(note_index),
+            None => self.status_message = Some("Nothing unread to pick".to_string()),
+        }
+    }
+
+    /// Opens the related notes popup for the selected note.
+    pub fn open_related(&mut self) {
+        let Some(source) = self.selected_absolute_index() else {
+            return;
+        };
+        let items = related::related_notes(&self.notes, source, &self.fulltext, &self.card, RELATED_LIMIT);
+        if items.is_empty() {
+            self.status_message = Some("No related notes".to_string());
+            return;
+        }
+        self.related = Some(RelatedPanel { source, items, selected: 0 });
+    }
+
+    pub fn close_related(&mut self) {
+        self.related = None;
+    }
+
+    pub fn next_related(&mut self) {
+        if let Some(panel) = &mut self.related {
+            panel.selected = (panel.selected + 1) % panel.items.len();
+        }
+    }
+
+    pub fn previous_related(&mut self) {
+        if let Some(panel) = &mut self.related {
+            panel.selected = (panel.selected + panel.items.len() - 1) % panel.items.len();
+        }
+    }
+
+    pub fn selected_related(&self) -> Option<&Note> {
+        let panel = self.related.as_ref()?;
+        panel.items.get(panel.selected).map(|item| &self.notes[item.note_index])
+    }
+
+    /// Closes the popup and selects the chosen related note.
+    pub fn jump_to_related(&mut self) {
+        let Some(panel) = self.related.take() else {
+            return;
+        };
+        if let Some(item) = panel.items.get(panel.selected) {
+            self.jump_to_note(item.note_index);
+        }
+    }
+
+    /// Opens the duplicates popup, or says there are none.
+    pub fn open_duplicates(&mut self) {
+        let groups = duplicates::find_duplicates(&self.notes);
+        if groups.is_empty() {
+            self.status_message = Some("No duplicates".to_string());
+            return;
+        }
+        self.duplicates = Some(DuplicatesPanel { groups, selected: 0 });
+    }
+
+    pub fn close_duplicates(&mut self) {
+        self.duplicates = None;
+    }
+
+    pub fn next_duplicate(&mut self) {
+        if let Some(panel) = &mut self.duplicates {
+            panel.selected = (panel.selected + 1) % panel.len();
+        }
+    }
+
+    pub fn previous_duplicate(&mut self) {
+        if let Some(panel) = &mut self.duplicates {
+            panel.selected = (panel.selected + panel.len() - 1) % panel.len();
+        }
+    }
+
+    pub fn jump_to_duplicate(&mut self) {
+        let Some(panel) = self.duplicates.take() else {
+            return;
+        };
+        if let Some((_, note_index)) = panel.selected_note() {
+            self.jump_to_note(note_index);
+        }
+    }
+
+    /// Regroups after notes changed, closing the popup once no duplicates are left.
+    fn refresh_duplicates(&mut self) {
+        let Some(panel) = &self.duplicates else {
+            return;
+        };
+        let selected = panel.selected;
+        let groups = duplicates::find_duplicates(&self.notes);
+        self.duplicates = (!groups.is_empty()).then(|| {
+            let mut panel = DuplicatesPanel { groups, selected: 0 };
+            panel.selected = selected.min(panel.len() - 1);
+            panel
+        });
+    }
+
+    /// Selects a note, dropping the search, the filter, the checked tags and then the browse limit if they hide it.
+    pub fn jump_to_note(&mut self, note_index: usize) {
+        if !self.visible.contains(&note_index) {
+            self.clear_search();
+        }
+        if !self.visible.contains(&note_index) {
+            self.clear_filter();
+        }
+        if !self.visible.contains(&note_index) {
+            self.clear_tags();
+        }
+        if !self.visible.contains(&note_index) && self.browse_limit > 0 {
+            self.browse_limit = 0;
+            self.refresh_visible();
+        }
+        if !self.select_note(note_index) {
+            self.status_message = Some(format!("\"{}\" isn't shown in this view", self.notes[note_index].title));
+        }
+    }
+
+    /// Rows per page in the active view's layout, group headers included.
+    pub fn page_size(&self) -> usize {
+        self.view().layout.page_size()
+    }
+
+    pub fn toggle_hide_read(&mut self) {
+        self.hide_read = !self.hide_read;
+        self.refresh_visible();
+    }
+
+    pub fn open_prompt(&mut self, kind: PromptKind) {
+        let input = match kind {
+            PromptKind::Search(search_kind) => self.search.as_ref()
+                .filter(|search| search.kind == search_kind)
+                .map(|search| search.query.clone())
+                .unwrap_or_default(),
+            PromptKind::Filter => self.filter.as_ref().map(|active| active.query.clone()).unwrap_or_default(),
+            _ => String::new(),
+        };
+        self.prompt = Some(Prompt { kind, input, completions: Vec::new(), completion: 0, history_position: None });
+    }
+
+    pub fn close_prompt(&mut self) {
+        self.prompt = None;
+        self.filter_error = None;
+    }
+
+    /// Applies a filter query. A query that doesn't parse leaves the current
+    /// filter in place and sets `filter_error`; an empty one removes the filter.
+    pub fn set_filter_query(&mut self, query: &str) {
+        let filter = match Filter::parse(query) {
+            Ok(Filter::All) => None,
+            Ok(filter) => Some(ActiveFilter { query: query.to_string(), filter }),
+            Err(error) => {
+                self.filter_error = Some(error.to_string());
+                return;
+            }
+        };
+        self.filter_error = None;
+        self.filter = filter;
+        self.current_page = 0;
+        self.selected_note_index = 0;
+        self.refresh_visible();
+    }
+
+    pub fn clear_filter(&mut self) {
+        self.filter_error = None;
+        if self.filter.take().is_some() {
+            self.current_page = 0;
+            self.selected_note_index = 0;
+            self.refresh_visible();
+        }
+    }
+
+    /// Searches every loaded note and shows the best match first. An empty query ends the search.
+    pub fn set_search_query(&mut self, kind: SearchKind, query: &str) {
+        if query.trim().is_empty() {
+            self.clear_search();
+            return;
+        }
+
+        self.search = Some(self.run_search(kind, query));
+        self.current_page = 0;
+        self.selected_note_index = 0;
+        self.refresh_visible();
+    }
+
+    fn run_search(&self, kind: SearchKind, query: &str) -> Search {
+        let mut search = Search {
+            kind,
+            query: query.to_string(),
+            ..Search::default()
+        };
+        match kind {
+            SearchKind::Fuzzy => {
+                for result in search::fuzzy_search(&self.notes, query) {
+                    search.results.push(result.note_index);
+                    search.highlights.insert(result.note_index, result.highlights);
+                }
+            }
+            SearchKind::FullText => {
+                let note_indexes: HashMap<&PathBuf, usize> = self.notes.iter()
+                    .enumerate()
+                    .map(|(i, note)| (&note.path, i))
+                    .collect();
+                for result in self.fulltext.search(query) {
+                    let Some(&note_index) = note_indexes.get(&result.path) else {
+                        continue;
+                    };
+                    search.results.push(note_index);
+                    search.highlights.insert(note_index, NoteHighlights { title: Vec::new(), excerpt: result.highlights });
+                    search.excerpts.insert(note_index, result.snippet);
+                }
+            }
+        }
+        search
+    }
+
+    /// The text shown on a card: the matching snippet during a full-text search, otherwise the excerpt.
+    pub fn excerpt(&self, note_index: usize) -> &str {
+        self.search.as_ref()
+            .and_then(|search| search.excerpts.get(&note_index))
+            .map_or(self.notes[note_index].excerpt.as_str(), String::as_str)
+    }
+
+    /// Applies notes changed on disk, keeping the selected note and the active search in sync.
+    pub fn apply_vault_changes(&mut self, changes: Vec<VaultChange>) {
+        if changes.is_empty() {
+            return;
+        }
+        let selected_path = self.selected_note().map(|note| note.path.clone());
+        // Its indexes are about to go stale
+        self.related = None;
+
+        for change in changes {
+            match change {
+                VaultChange::Updated(note) => {
+                    self.fulltext.update_note(&note);
+                    match self.notes.iter().position(|existing| existing.path == note.path) {
+                        Some(i) => self.notes[i] = *note,
+                        None => self.notes.insert(0, *note),
+                    }
+                }
+                VaultChange::Removed(path) => {
+                    self.fulltext.remove(&path);
+                    self.notes.retain(|note| note.path != path);
+                }
+            }
+        }
+
+        if let Some(search) = self.search.take() {
+            self.search = Some(self.run_search(search.kind, &search.query));
+        }
+        self.refresh_duplicates();
+        self.refresh_visible();
+        if let Some(note_index) = selected_path.and_then(|path| self.notes.iter().position(|note| note.path == path)) {
+            self.select_note(note_index);
+        }
+    }
+
+    pub fn clear_search(&mut self) {
+        if self.search.take().is_some() {
+            self.current_page = 0;
+            self.selected_note_index = 0;
+            self.refresh_visible();
+        }
+    }
+
+    pub fn highlights(&self, note_index: usize) -> Option<&NoteHighlights> {
+        self.search.as_ref().and_then(|search| search.highlights.get(&note_index))
+    }
+
+    /// Position of the selected row in `rows`, across all pages. Without
+    /// grouping that is also its position in `visible`.
+    pub fn selected_position(&self) -> usize {
+        self.current_page * self.page_size() + self.selected_note_index
+    }
+
+    pub fn select_first(&mut self) {
+        if let Some(&note_index) = self.visible.first() {
+            self.select_note(note_index);
+        }
+    }
+
+    pub fn select_last(&mut self) {
+        if let Some(&note_index) = self.visible.last() {
+            self.select_note(note_index);
+        }
+    }
+
+    pub fn select_position(&mut self, position: usize) {
+        if position < self.rows.len() {
+            self.current_page = position / self.page_size();
+            self.selected_note_index = position % self.page_size();
+        }
+    }
+
+    /// Steps to the next search match, wrapping around, crossing pages and skipping group headers.
+    pub fn next_match(&mut self) {
+        self.step_match(1);
+    }
+
+    pub fn previous_match(&mut self) {
+        self.step_match(self.rows.len().saturating_sub(1));
+    }
+
+    /// Moves `offset` rows at a time, modulo the row count, until it lands on a note.
+    fn step_match(&mut self, offset: usize) {
+        let len = self.rows.len();
+        if self.search.is_none() || len == 0 {
+            return;
+        }
+        let mut position = self.selected_position();
+        for _ in 0..len {
+            position = (position + offset) % len;
+            if matches!(self.rows[position], Row::Note(_)) {
+                self.select_position(position);
+                return;
+            }
+        }
+    }
+
+    pub fn notes_on_current_page(&self) -> Vec<&Note> {
+        self.notes_on_page(self.current_page)
+    }
+
+    pub fn notes_on_page(&self, page: usize) -> Vec<&Note> {
+        self.rows_on_page(page).iter()
+            .filter_map(|row| match row {
+                Row::Note(i) => Some(&self.notes[*i]),
+                Row::Header { .. } => None,
+            })
+            .collect()
     }
 
     pub fn total_pages(&self) -> usize {
-        (self.notes.len() + PAGE_SIZE - 1) / PAGE_SIZE
+        self.rows.len().div_ceil(self.page_size())
+    }
+
+    pub fn rows_on_current_page(&self) -> &[Row] {
+        self.rows_on_page(self.current_page)
+    }
+
+    fn rows_on_page(&self, page: usize) -> &[Row] {
+        let start = (page * self.page_size()).min(self.rows.len());
+        let end = (start + self.page_size()).min(self.rows.len());
+        &self.rows[start..end]
+    }
+
+    pub fn selected_row(&self) -> Option<&Row> {
+        self.rows.get(self.selected_position())
+    }
+
+    /// Index into `notes` of the selected note, none when a group header is selected.
+    pub fn selected_absolute_index(&self) -> Option<usize> {
+        match self.selected_row() {
+            Some(Row::Note(i)) => Some(*i),
+            _ => None,
+        }
     }
 
     pub fn selected_note(&self) -> Option<&Note> {
-        let start = self.current_page * PAGE_SIZE;
-        let absolute_index = start + self.selected_note_index;
-        self.notes.get(absolute_index)
+        self.selected_absolute_index().map(|i| &self.notes[i])
     }
 
     pub fn selected_note_mut(&mut self) -> Option<&mut Note> {
-        let start = self.current_page * PAGE_SIZE;
-        let absolute_index = start + self.selected_note_index;
-        self.notes.get_mut(absolute_index)
+        self.selected_absolute_index().map(|i| &mut self.notes[i])
     }
 
-    pub fn next_note(&mut self) {
-        let notes_on_page = self.notes_on_current_page().len();
-        if notes_on_page > 0 {
-            self.selected_note_index = (self.selected_note_index + 1) % notes_on_page;
+    /// Where the selected row sits in the page's grid of cards.
+    pub fn selected_cell(&self) -> Cell {
+        let columns = self.view().layout.columns();
+        Cell { row: self.selected_note_index / columns, col: self.selected_note_index % columns }
+    }
+
+    /// Selects `cell` on `page`. A cell past the last card, in a page that
+    /// isn't full, selects the last card instead.
+    fn select_cell(&mut self, page: usize, cell: Cell) {
+        let rows_on_page = self.rows_on_page(page).len();
+        if rows_on_page == 0 {
+            return;
         }
+        self.current_page = page;
+        self.selected_note_index = (cell.row * self.view().layout.columns() + cell.col).min(rows_on_page - 1);
     }
 
-    pub fn next_two_notes(&mut self) {
-        let notes_on_page = self.notes_on_current_page().len();
-        if notes_on_page > 0 {
-            self.selected_note_index = (self.selected_note_index + 2) % notes_on_page;
+    /// Moves to the next card in reading order, on to the first card of the next page after the last one.
+    pub fn next_note(&mut self) {
+        let position = self.selected_position();
+        if position + 1 < self.rows.len() {
+            self.select_position(position + 1);
         }
     }
 
     pub fn previous_note(&mut self) {
-        let notes_on_page = self.notes_on_current_page().len();
-        if notes_on_page > 0 {
-            self.selected_note_index = (self.selected_note_index + notes_on_page - 1) % notes_on_page;
+        let position = self.selected_position();
+        if position > 0 {
+            self.select_position(position - 1);
         }
     }
 
-    pub fn previous_two_notes(&mut self) {
-        let notes_on_page = self.notes_on_current_page().len();
-        if notes_on_page > 0 {
-            self.selected_note_index = (self.selected_note_index + notes_on_page - 2) % notes_on_page;
+    /// Moves down a row in the active layout. From the bottom row it goes to
+    /// the top row of the next page, keeping the column.
+    pub fn next_row(&mut self) {
+        let cell = self.selected_cell();
+        let columns = self.view().layout.columns();
+        if (cell.row + 1) * columns < self.rows_on_current_page().len() {
+            self.select_cell(self.current_page, Cell { row: cell.row + 1, ..cell });
+        } else if self.current_page + 1 < self.total_pages() {
+            self.select_cell(self.current_page + 1, Cell { row: 0, ..cell });
         }
     }
 
+    /// Moves up a row, from the top row to the bottom row of the previous page.
+    pub fn previous_row(&mut self) {
+        let cell = self.selected_cell();
+        if cell.row > 0 {
+            self.select_cell(self.current_page, Cell { row: cell.row - 1, ..cell });
+        } else if self.current_page > 0 {
+            let bottom = self.view().layout.rows() - 1;
+            self.select_cell(self.current_page - 1, Cell { row: bottom, ..cell });
+        }
+    }
+
+    /// Turns the page, keeping the selection in the same cell.
     pub fn next_page(&mut self) {
-        let total_pages = self.total_pages();
-        if total_pages > 0 && self.current_page < total_pages - 1 {
-            self.current_page += 1;
-            self.selected_note_index = 0;
+        if self.current_page + 1 < self.total_pages() {
+            self.select_cell(self.current_page + 1, self.selected_cell());
         }
     }
 
     pub fn previous_page(&mut self) {
         if self.current_page > 0 {
-            self.current_page -= 1;
-            self.selected_note_index = 0;
+            self.select_cell(self.current_page - 1, self.selected_cell());
         }
     }
 }
-
-
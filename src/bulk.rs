@@ -0,0 +1,178 @@
+use crate::app::App;
+use crate::config::Config;
+use crate::journal::Operation;
+use crate::models::Note;
+use crate::vault::{self, VaultChange};
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+// Failures named in the summary, the rest are only counted
+const FAILURES_SHOWN: usize = 3;
+
+/// What a bulk action does to each marked note.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BulkAction {
+    MarkRead,
+    MarkUnread,
+    AddTag(String),
+    RemoveTag(String),
+    Archive,
+    Trash,
+    Export(PathBuf), // Markdown list of links, to a new file
+}
+
+impl BulkAction {
+    /// "archiving", for the progress in the status bar.
+    pub fn progress_label(&self) -> &'static str {
+        match self {
+            BulkAction::MarkRead => "marking read",
+            BulkAction::MarkUnread => "marking unread",
+            BulkAction::AddTag(_) => "tagging",
+            BulkAction::RemoveTag(_) => "untagging",
+            BulkAction::Archive => "archiving",
+            BulkAction::Trash => "trashing",
+            BulkAction::Export(_) => "exporting",
+        }
+    }
+
+    fn summary(&self, changed: usize) -> String {
+        match self {
+            BulkAction::MarkRead => format!("Marked {} notes read", changed),
+            BulkAction::MarkUnread => format!("Marked {} notes unread", changed),
+            BulkAction::AddTag(tag) => format!("Added [[{}]] to {} notes", tag, changed),
+            BulkAction::RemoveTag(tag) => format!("Removed [[{}]] from {} notes", tag, changed),
+            BulkAction::Archive => format!("Archived {} notes", changed),
+            BulkAction::Trash => format!("Moved {} notes to .trash", changed),
+            BulkAction::Export(path) => format!("Exported {} notes to {}", changed, path.display()),
+        }
+    }
+
+    /// Applies the action to one note, returns whether it changed anything.
+    /// Changes to the vault are added to `operations` for undoing.
+    fn apply(&self, note: &mut Note, config: &Config, first: bool, operations: &mut Vec<Operation>) -> io::Result<bool> {
+        match self {
+            BulkAction::MarkRead | BulkAction::MarkUnread => {
+                let read = *self == BulkAction::MarkRead;
+                if note.read == read {
+                    return Ok(false);
+                }
+                operations.push(vault::toggle_read_status(note, config)?);
+            }
+            BulkAction::AddTag(tag) => {
+                if note.tags.contains(tag) {
+                    return Ok(false);
+                }
+                operations.extend(vault::add_tag(note, tag, config)?);
+            }
+            BulkAction::RemoveTag(tag) => {
+                if !note.tags.contains(tag) {
+                    return Ok(false);
+                }
+                operations.extend(vault::remove_tag(note, tag, config)?);
+            }
+            BulkAction::Archive => {
+                if note.archived {
+                    return Ok(false);
+                }
+                operations.push(vault::toggle_archived(note, config)?);
+            }
+            BulkAction::Trash => {
+                let to = vault::trash_note(note, config)?;
+                operations.push(Operation::Move { from: note.file_path(config), to });
+            }
+            BulkAction::Export(path) => {
+                // The first note creates the file, refusing to overwrite one, the rest go after it
+                let mut file = if first {
+                    OpenOptions::new().write(true).create_new(true).open(path)?
+                } else {
+                    OpenOptions::new().append(true).open(path)?
+                };
+                match note.markdown_link() {
+                    Some(link) => writeln!(file, "- {}", link)?,
+                    None => writeln!(file, "- {}", note.title)?,
+                }
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// A bulk action under way, a few notes per tick so the screen keeps updating.
+#[derive(Debug, Clone)]
+pub struct BulkJob {
+    pub action: BulkAction,
+    pub paths: Vec<PathBuf>, // The notes to change, by file since indexes shift as notes change
+    pub done: usize,
+    changed: usize,
+    failures: Vec<String>,
+    operations: Vec<Operation>, // Undone together once the job is finished
+}
+
+impl BulkJob {
+    pub fn new(action: BulkAction, paths: Vec<PathBuf>) -> Self {
+        BulkJob { action, paths, done: 0, changed: 0, failures: Vec::new(), operations: Vec::new() }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.done >= self.paths.len()
+    }
+
+    /// "Archived 8 notes, 2 failed: "A" (Permission denied), "B" (...)".
+    pub fn summary(&self) -> String {
+        let mut summary = self.action.summary(self.changed);
+        if !self.failures.is_empty() {
+            summary.push_str(&format!(", {} failed: {}", self.failures.len(), self.failures.iter().take(FAILURES_SHOWN).cloned().collect::<Vec<_>>().join(", ")));
+            if self.failures.len() > FAILURES_SHOWN {
+                summary.push_str(&format!(" and {} more", self.failures.len() - FAILURES_SHOWN));
+            }
+        }
+        summary
+    }
+}
+
+/// Works through the running bulk job for about `budget`, at least one note,
+/// and puts its summary in the status bar once it is done.
+pub fn step(app: &mut App, config: &Config, budget: Duration) {
+    let Some(mut job) = app.bulk.take() else {
+        return;
+    };
+    let started = Instant::now();
+    let mut removed = Vec::new();
+    while !job.is_finished() {
+        let path = job.paths[job.done].clone();
+        // Export starts the file over until a note has been written
+        let first = job.changed == 0;
+        job.done += 1;
+        match app.notes.iter().position(|note| note.path == path) {
+            Some(i) => match job.action.apply(&mut app.notes[i], config, first, &mut job.operations) {
+                Ok(changed) => {
+                    job.changed += changed as usize;
+                    if job.action == BulkAction::Trash {
+                        app.marked.remove(&path);
+                        removed.push(VaultChange::Removed(path));
+                    }
+                }
+                Err(e) => job.failures.push(format!("\"{}\" ({})", app.notes[i].title, e)),
+            },
+            None => job.failures.push(format!("\"{}\" (no longer in the vault)", path.display())),
+        }
+        if started.elapsed() >= budget {
+            break;
+        }
+    }
+
+    if removed.is_empty() {
+        app.refresh_visible();
+    } else {
+        app.apply_vault_changes(removed);
+    }
+    if job.is_finished() {
+        app.status_message = Some(job.summary());
+        let label = format!("{} {} notes", job.action.progress_label(), job.changed);
+        app.journal.record(label, job.operations);
+    } else {
+        app.bulk = Some(job);
+    }
+}
@@ -0,0 +1,80 @@
+use crate::config::Config;
+use crate::models::Note;
+use crate::search::query::Filter;
+use std::io::{self, Write};
+
+pub const USAGE: &str = "\
+Usage: readitnow [OPTIONS]
+
+Browse the ReadItLater notes in your vault. With --filter, prints the
+matching notes instead of starting the interface.
+
+Options:
+  -f, --filter <QUERY>     Print notes matching QUERY, one per line: title, then URL or file
+  -s, --surprise           Print one random unread note, weighted as configured under `surprise`
+  -m, --max-minutes <N>    With --surprise, only pick notes that take at most N minutes to read
+  -h, --help               Show this help
+
+Query examples:
+  kind:video is:unread after:1m tag:infra
+  (domain:github.com OR domain:gitlab.com) -is:archived
+  words>2000 \"borrow checker\"";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Browse,
+    List { filter: String },
+    /// Picks among the notes matching `filter`, or all of them
+    Surprise { filter: Option<String>, max_minutes: Option<usize> },
+    Help,
+}
+
+/// Parses the arguments after the program name.
+pub fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Command, String> {
+    let mut filter = None;
+    let mut surprise = false;
+    let mut max_minutes = None;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        // `--option=value` is the same as `--option value`
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name.to_string(), Some(value.to_string())),
+            _ => (arg.clone(), None),
+        };
+        let mut value = |what: &str| inline_value.clone().or_else(|| args.next()).ok_or_else(|| format!("{} needs {}", name, what));
+        match name.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-f" | "--filter" => filter = Some(value("a query")?),
+            "-s" | "--surprise" => surprise = true,
+            "-m" | "--max-minutes" => {
+                let minutes = value("a number of minutes")?;
+                max_minutes = Some(minutes.parse().map_err(|_| format!("`{}` is not a number of minutes", minutes))?);
+            }
+            _ => return Err(format!("unknown argument `{}`", arg)),
+        }
+    }
+
+    match (surprise, filter) {
+        (true, filter) => Ok(Command::Surprise { filter, max_minutes }),
+        (false, _) if max_minutes.is_some() => Err("--max-minutes only works with --surprise".to_string()),
+        (false, Some(filter)) => Ok(Command::List { filter }),
+        (false, None) => Ok(Command::Browse),
+    }
+}
+
+/// Tab separated, so the output can go straight into `cut` or `fzf`.
+pub fn list_line(note: &Note, config: &Config) -> String {
+    let target = note.url.clone().unwrap_or_else(|| note.file_path(config).to_string_lossy().to_string());
+    format!("{}\t{}", note.title, target)
+}
+
+/// Writes every note matching `filter`, most recent first, and returns how many matched.
+pub fn list_notes(notes: &[Note], filter: &Filter, config: &Config, out: &mut impl Write) -> io::Result<usize> {
+    let mut count = 0;
+    for note in notes.iter().filter(|note| filter.matches(note)) {
+        writeln!(out, "{}", list_line(note, config))?;
+        count += 1;
+    }
+    Ok(count)
+}
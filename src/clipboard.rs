@@ -0,0 +1,50 @@
+use crate::config::Config;
+use crate::external::External;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+/// The OSC 52 sequence that asks the terminal to put `text` on the clipboard.
+/// It travels with the output, so it works over SSH. tmux takes it as it is
+/// when `set-clipboard` is on, and passes it on to the outer terminal.
+pub fn osc52(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", STANDARD.encode(text))
+}
+
+/// `sequence` wrapped in tmux's passthrough escape, with its escapes doubled.
+/// tmux only lets it through with `allow-passthrough` on.
+pub fn tmux_passthrough(sequence: &str) -> String {
+    format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))
+}
+
+/// Copies `text` with OSC 52, and with the `clipboard` command from the config
+/// too when there is one, for terminals that ignore OSC 52.
+pub fn copy(text: &str, config: &Config) -> io::Result<()> {
+    let sequence = osc52(text);
+    let mut stdout = io::stdout();
+    stdout.write_all(sequence.as_bytes())?;
+    if config.clipboard_tmux_passthrough && std::env::var_os("TMUX").is_some() {
+        stdout.write_all(tmux_passthrough(&sequence).as_bytes())?;
+    }
+    stdout.flush()?;
+
+    let Some(command) = config.clipboard.as_deref().and_then(|template| External::from_template(template, &[])) else {
+        return Ok(());
+    };
+    let mut child = Command::new(&command.program)
+        .args(&command.args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    let status = child.wait()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!("{} exited with {}", command.program, status)))
+    }
+}
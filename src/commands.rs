@@ -0,0 +1,243 @@
+use crate::app::{App, Row, SearchKind};
+use crate::bulk::BulkAction;
+use crate::config::{Config, ViewLayout};
+use crate::group::GroupBy;
+use crate::keybindings::{self, Action, AppAction};
+use crate::sort::{self, Sort, SortDirection, SortKey};
+
+/// Commands that take arguments, with their usage. Every action can be typed
+/// too, by its keybinding name with dashes: `:toggle-read`, `:page-down`.
+pub const COMMANDS: [(&str, &str); 7] = [
+    ("sort", "<key> [asc|desc]"),
+    ("filter", "<query>"),
+    ("search", "<query>"),
+    ("open", "<number>"),
+    ("mark-read", "[selected|marked|all-on-page|all]"),
+    ("mark-unread", "[selected|marked|all-on-page|all]"),
+    ("set", "layout|hide-read|group-by <value>"),
+];
+
+// `:reload` takes no arguments but isn't an action either
+const RELOAD: &str = "reload";
+
+const TARGETS: [&str; 4] = ["selected", "marked", "all-on-page", "all"];
+const SETTINGS: [&str; 3] = ["layout", "hide-read", "group-by"];
+
+/// A command typed at the `:` prompt.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Action(Action),
+    Sort(SortKey, Option<SortDirection>),
+    Filter(String),
+    Search(String),
+    Open(usize), // Position in the view, counting from 1
+    MarkRead(bool, Option<Target>), // No target is the marked notes, or the selected one without any
+    Set(Setting),
+    Reload,
+}
+
+/// Which notes a bulk command changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    Selected,
+    Marked,
+    Page,
+    All, // Every note the view shows, on any page
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Setting {
+    Layout(ViewLayout),
+    HideRead(bool),
+    GroupBy(Option<GroupBy>),
+}
+
+/// The name an action is typed as.
+pub fn action_command(action: Action) -> String {
+    action.name().replace('_', "-")
+}
+
+/// Every command name, for completion.
+pub fn command_names() -> Vec<String> {
+    let mut names: Vec<String> = COMMANDS.iter().map(|(name, _)| name.to_string()).collect();
+    names.push(RELOAD.to_string());
+    for action in Action::ALL {
+        let name = action_command(action);
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+impl Command {
+    pub fn parse(input: &str) -> Result<Command, String> {
+        let input = input.trim().trim_start_matches(':');
+        let (name, rest) = input.split_once(' ').unwrap_or((input, ""));
+        let name = name.replace('_', "-");
+        let rest = rest.trim();
+        let args: Vec<&str> = rest.split_whitespace().collect();
+
+        let command = match (name.as_str(), args.as_slice()) {
+            ("", _) => return Err("No command".to_string()),
+            ("sort", [key, order @ ..]) if order.len() <= 1 => {
+                let by = SortKey::parse(key).ok_or_else(|| format!("Unknown sort key `{}`", key))?;
+                let order = match order.first() {
+                    Some(order) => Some(SortDirection::parse(order).ok_or_else(|| format!("Expected asc or desc, not `{}`", order))?),
+                    None => None,
+                };
+                Command::Sort(by, order)
+            }
+            ("filter", [_, ..]) => Command::Filter(rest.to_string()),
+            ("search", [_, ..]) => Command::Search(rest.to_string()),
+            ("open", [number]) => match number.parse() {
+                Ok(number) if number > 0 => Command::Open(number),
+                _ => return Err(format!("Not a note number: {}", number)),
+            },
+            ("mark-read" | "mark-unread", [] | [_]) => {
+                let target = match args.first().copied() {
+                    None => None,
+                    Some("selected") => Some(Target::Selected),
+                    Some("marked") => Some(Target::Marked),
+                    Some("all-on-page" | "page") => Some(Target::Page),
+                    Some("all") => Some(Target::All),
+                    Some(other) => return Err(format!("Expected selected, marked, all-on-page or all, not `{}`", other)),
+                };
+                Command::MarkRead(name == "mark-read", target)
+            }
+            ("set", [setting, value @ ..]) => Command::Set(parse_setting(setting, value)?),
+            (RELOAD, []) => Command::Reload,
+            (name, args) => match Action::ALL.into_iter().find(|action| action_command(*action) == name) {
+                Some(action) if args.is_empty() => Command::Action(action),
+                Some(_) => return Err(format!("`{}` takes no arguments", name)),
+                None => match COMMANDS.iter().find(|(command, _)| *command == name) {
+                    Some((command, usage)) => return Err(format!("Usage: {} {}", command, usage)),
+                    None => return Err(format!("Unknown command `{}`", name)),
+                },
+            },
+        };
+        Ok(command)
+    }
+}
+
+fn parse_setting(setting: &str, value: &[&str]) -> Result<Setting, String> {
+    match (setting.replace('_', "-").as_str(), value) {
+        ("layout", ["list"]) => Ok(Setting::Layout(ViewLayout::list())),
+        ("layout", ["list", rows]) => match rows.parse() {
+            Ok(rows) if rows > 0 => Ok(Setting::Layout(ViewLayout::List { rows })),
+            _ => Err(format!("Not a number of rows: {}", rows)),
+        },
+        ("layout", ["grid"]) => Ok(Setting::Layout(ViewLayout::default())),
+        ("layout", ["grid", size]) => match size.split_once('x').map(|(columns, rows)| (columns.parse(), rows.parse())) {
+            Some((Ok(columns), Ok(rows))) if columns > 0 && rows > 0 => Ok(Setting::Layout(ViewLayout::Grid { columns, rows })),
+            _ => Err(format!("Expected a size like 3x2, not `{}`", size)),
+        },
+        ("layout", _) => Err("Usage: set layout list [rows] | grid [columns]x[rows]".to_string()),
+        ("hide-read", ["on" | "true" | "yes"]) => Ok(Setting::HideRead(true)),
+        ("hide-read", ["off" | "false" | "no"]) => Ok(Setting::HideRead(false)),
+        ("hide-read", _) => Err("Usage: set hide-read on|off".to_string()),
+        ("group-by", ["none" | "off"]) => Ok(Setting::GroupBy(None)),
+        ("group-by", [by]) => GroupBy::parse(by)
+            .map(|by| Setting::GroupBy(Some(by)))
+            .ok_or_else(|| format!("Expected none, domain, kind, tag or date, not `{}`", by)),
+        ("group-by", _) => Err("Usage: set group-by none|domain|kind|tag|date".to_string()),
+        (other, _) => Err(format!("Unknown setting `{}`, expected layout, hide-read or group-by", other)),
+    }
+}
+
+/// Every input that finishes the word being typed, in the order Tab cycles through them.
+pub fn complete(input: &str) -> Vec<String> {
+    let (done, word) = match input.rfind(' ') {
+        Some(i) => (&input[..=i], &input[i + 1..]),
+        None => ("", input),
+    };
+    let words: Vec<&str> = done.split_whitespace().collect();
+    let sort_keys: Vec<String> = SortKey::ALL.iter().map(|key| key.label().replace(' ', "-")).collect();
+    let group_by: Vec<String> = std::iter::once("none").chain(GroupBy::ALL.iter().map(GroupBy::label)).map(str::to_string).collect();
+    let owned = |options: &[&str]| options.iter().map(|option| option.to_string()).collect::<Vec<String>>();
+
+    let options: Vec<String> = match words.as_slice() {
+        [] => command_names(),
+        ["sort"] => sort_keys,
+        ["sort", _] => owned(&["asc", "desc"]),
+        ["mark-read" | "mark-unread"] => owned(&TARGETS),
+        ["set"] => owned(&SETTINGS),
+        ["set", "layout"] => owned(&["list", "grid"]),
+        ["set", "hide-read"] => owned(&["on", "off"]),
+        ["set", "group-by"] => group_by,
+        _ => Vec::new(),
+    };
+    options.into_iter()
+        .filter(|option| option.starts_with(word))
+        .map(|option| {
+            // Commands that take arguments get the space before them
+            let space = if words.is_empty() && COMMANDS.iter().any(|(name, _)| *name == option) { " " } else { "" };
+            format!("{}{}{}", done, option, space)
+        })
+        .collect()
+}
+
+pub fn run(command: Command, app: &mut App, config: &Config) -> AppAction {
+    match command {
+        Command::Action(action) => return keybindings::perform(action, app, config, None),
+        Command::Sort(by, order) => {
+            let current = app.view().sort;
+            let seed = if by == SortKey::Random && current.by != SortKey::Random { sort::time_seed() } else { current.seed };
+            app.set_sort(Sort { by, order: order.unwrap_or(current.order), seed });
+        }
+        Command::Filter(query) => {
+            app.set_filter_query(&query);
+            if let Some(error) = app.filter_error.take() {
+                app.status_message = Some(error);
+            }
+        }
+        Command::Search(query) => app.set_search_query(SearchKind::Fuzzy, &query),
+        Command::Open(number) => match app.visible.get(number - 1).copied() {
+            Some(note_index) => {
+                app.select_note(note_index);
+                if let Some(url) = &app.notes[note_index].url {
+                    let _ = open::that(url);
+                }
+            }
+            None => app.status_message = Some(format!("There is no note {} in this view", number)),
+        },
+        Command::MarkRead(read, target) => mark_read(app, read, target),
+        Command::Set(Setting::Layout(layout)) => app.set_layout(layout),
+        Command::Set(Setting::HideRead(hide_read)) => {
+            if app.hide_read != hide_read {
+                app.toggle_hide_read();
+            }
+        }
+        Command::Set(Setting::GroupBy(group_by)) => {
+            app.set_group_by(group_by);
+            if group_by.is_some() && app.grouping().is_none() {
+                app.status_message = Some("Only list views can be grouped".to_string());
+            }
+        }
+        Command::Reload => return AppAction::Reload,
+    }
+    AppAction::Continue
+}
+
+/// Marks the target notes read or unread on disk, as a bulk action. Without
+/// a target that is the marked notes when there are any, otherwise the selected one.
+fn mark_read(app: &mut App, read: bool, target: Option<Target>) {
+    let target = target.unwrap_or(if app.marked.is_empty() { Target::Selected } else { Target::Marked });
+    let indexes: Vec<usize> = match target {
+        Target::Selected => app.selected_absolute_index().into_iter().collect(),
+        Target::Marked => return app.start_bulk(bulk_action(read), app.marked_paths()),
+        Target::Page => app.rows_on_current_page().iter()
+            .filter_map(|row| match row {
+                Row::Note(i) => Some(*i),
+                Row::Header { .. } => None,
+            })
+            .collect(),
+        Target::All => app.visible.clone(),
+    };
+    let paths = indexes.into_iter().map(|i| app.notes[i].path.clone()).collect();
+    app.start_bulk(bulk_action(read), paths);
+}
+
+fn bulk_action(read: bool) -> BulkAction {
+    if read { BulkAction::MarkRead } else { BulkAction::MarkUnread }
+}
@@ -1,28 +1,438 @@
 use directories::{ProjectDirs, UserDirs};
 use serde::{Deserialize, Serialize};
 use serde_yaml;
-use std::{fs, io, path::Path};
+use std::{collections::BTreeMap, fs, io, path::{Path, PathBuf}};
+use crate::group::GroupBy;
+use crate::images::ImageConfig;
+use crate::journal::UndoConfig;
+use crate::keys::KeyList;
+use crate::sort::{Sort, SortDirection, SortKey};
+use crate::surprise::SurpriseConfig;
+use crate::theme::Theme;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub vault_path: String,
+    /// Most recent notes shown while browsing by date without a filter, 0 for all. Search, filters,
+    /// other sort orders and jumps to related notes always cover the whole vault.
     pub max_notes: usize,
     pub excerpt_lines: usize,
+    #[serde(default)]
     pub keybindings: Keybindings,
     pub thumbnail_cache: String,
+    /// Directory for the parsed note index and other vault caches
+    #[serde(default = "default_index_cache")]
+    pub index_cache: String,
+    #[serde(default)]
+    pub theme: ThemeConfig,
+    /// Start with read and archived notes removed from the pages
+    #[serde(default)]
+    pub hide_read: bool,
+    #[serde(default)]
+    pub card: CardConfig,
+    #[serde(default)]
+    pub images: ImageConfig,
+    /// Tabs along the top, switched with the number keys
+    #[serde(default = "default_views")]
+    pub views: Vec<ViewConfig>,
+    #[serde(default)]
+    pub surprise: SurpriseConfig,
+    /// Click, double click and scroll in the terminal. Off leaves the mouse to the terminal for selecting text
+    #[serde(default = "default_mouse")]
+    pub mouse: bool,
+    #[serde(default)]
+    pub undo: UndoConfig,
+    /// Command that opens a note, like `nvim +{line} {path}`. Falls back to $VISUAL, then $EDITOR
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub editor: Option<String>,
+    /// Command that copies its input, like `wl-copy`, for terminals without OSC 52
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clipboard: Option<String>,
+    /// Inside tmux, also send OSC 52 through tmux's passthrough, which needs `allow-passthrough on`
+    #[serde(default)]
+    pub clipboard_tmux_passthrough: bool,
+    /// Keys bound to commands of your own
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub actions: Vec<CustomAction>,
 }
 
+/// A key that runs a command on the selected note, like sending its URL to mpv.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomAction {
+    pub name: String,
+    pub key: KeyList,
+    /// `{url}`, `{path}`, `{title}` and `{tags}` are filled in from the note.
+    /// Run directly, not through a shell, with each value as part of one argument
+    pub command: String,
+    /// Hand the terminal to the command and wait for it, for programs that draw on it
+    #[serde(default)]
+    pub suspend: bool,
+    /// Parse the note again once the command exits, for commands that change it
+    #[serde(default)]
+    pub reload: bool,
+}
+
+/// A named tab: a filter query, the order its notes are shown in and how they are laid out.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ViewConfig {
+    pub name: String,
+    #[serde(default)]
+    pub filter: String,
+    #[serde(default)]
+    pub sort: Sort,
+    #[serde(default)]
+    pub layout: ViewLayout,
+    /// Splits a list layout into collapsible sections
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group_by: Option<GroupBy>,
+}
+
+impl ViewConfig {
+    pub fn new(name: &str, filter: &str, sort: Sort, layout: ViewLayout) -> Self {
+        ViewConfig { name: name.to_string(), filter: filter.to_string(), sort, layout, group_by: None }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum ViewLayout {
+    /// Cards with thumbnails, `columns` × `rows` per page
+    Grid {
+        #[serde(default = "default_grid_size")]
+        columns: usize,
+        #[serde(default = "default_grid_size")]
+        rows: usize,
+    },
+    /// One full width card per row
+    List {
+        #[serde(default = "default_list_rows")]
+        rows: usize,
+    },
+}
+
+fn default_grid_size() -> usize {
+    2
+}
+
+fn default_list_rows() -> usize {
+    6
+}
+
+impl Default for ViewLayout {
+    fn default() -> Self {
+        ViewLayout::Grid { columns: default_grid_size(), rows: default_grid_size() }
+    }
+}
+
+impl ViewLayout {
+    /// A list with the default number of rows.
+    pub fn list() -> Self {
+        ViewLayout::List { rows: default_list_rows() }
+    }
+
+    pub fn columns(&self) -> usize {
+        match self {
+            ViewLayout::Grid { columns, .. } => (*columns).max(1),
+            ViewLayout::List { .. } => 1,
+        }
+    }
+
+    pub fn rows(&self) -> usize {
+        match self {
+            ViewLayout::Grid { rows, .. } | ViewLayout::List { rows } => (*rows).max(1),
+        }
+    }
+
+    pub fn page_size(&self) -> usize {
+        self.columns() * self.rows()
+    }
+}
+
+/// The sort order, grouping and layout a view was left with.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SavedView {
+    pub sort: Sort,
+    pub layout: ViewLayout,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group_by: Option<GroupBy>,
+}
+
+impl SavedView {
+    pub fn of(view: &ViewConfig) -> Self {
+        SavedView { sort: view.sort, layout: view.layout, group_by: view.group_by }
+    }
+}
+
+/// Sort orders, groupings and layouts changed while browsing, by view name.
+/// They live in the index cache, so config.yaml stays as the user wrote it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ViewState {
+    views: BTreeMap<String, SavedView>,
+}
+
+impl ViewState {
+    fn path(config: &Config) -> PathBuf {
+        PathBuf::from(&config.index_cache).join("views.yaml")
+    }
+
+    pub fn load(config: &Config) -> Self {
+        fs::read_to_string(Self::path(config)).ok()
+            .and_then(|state_str| serde_yaml::from_str(&state_str).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, config: &Config) -> io::Result<()> {
+        fs::create_dir_all(&config.index_cache)?;
+        let state_str = serde_yaml::to_string(self)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(Self::path(config), state_str)
+    }
+
+    /// The configured views with the saved changes applied.
+    pub fn apply(&self, views: &[ViewConfig]) -> Vec<ViewConfig> {
+        views.iter().map(|view| match self.views.get(&view.name) {
+            Some(saved) => ViewConfig { sort: saved.sort, layout: saved.layout, group_by: saved.group_by, ..view.clone() },
+            None => view.clone(),
+        }).collect()
+    }
+
+    /// Remembers `current` for the view configured as `configured`, forgetting it
+    /// when it is back to the configured one. Returns whether anything changed.
+    pub fn update(&mut self, configured: &ViewConfig, current: SavedView) -> bool {
+        if current == SavedView::of(configured) {
+            self.views.remove(&configured.name).is_some()
+        } else {
+            self.views.insert(configured.name.clone(), current) != Some(current)
+        }
+    }
+}
+
+fn default_views() -> Vec<ViewConfig> {
+    let newest = Sort::new(SortKey::Modified, SortDirection::Desc);
+    vec![
+        ViewConfig::new("All", "", newest, ViewLayout::default()),
+        ViewConfig::new("Inbox", "is:unread", Sort::new(SortKey::Saved, SortDirection::Desc), ViewLayout::default()),
+        ViewConfig::new("Videos", "kind:video", newest, ViewLayout::default()),
+        ViewConfig::new("Long reads", "is:unread words>2000", newest, ViewLayout::List { rows: default_list_rows() }),
+    ]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FooterItem {
+    Tags,
+    Domain,
+    Date,
+    ReadingTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CardConfig {
+    /// Items shown on the last line of each card, left to right
+    pub footer: Vec<FooterItem>,
+    /// Tags never shown as chips; `readitnow/*` state tags are always hidden
+    pub hidden_tags: Vec<String>,
+    pub words_per_minute: usize,
+    /// chrono format string for the saved date
+    pub date_format: String,
+}
+
+impl Default for CardConfig {
+    fn default() -> Self {
+        CardConfig {
+            footer: vec![FooterItem::Tags, FooterItem::Domain, FooterItem::Date, FooterItem::ReadingTime],
+            hidden_tags: vec!["ReadItLater".to_string()],
+            words_per_minute: 230,
+            date_format: "%Y-%m-%d".to_string(),
+        }
+    }
+}
+
+impl CardConfig {
+    pub fn is_tag_shown(&self, tag: &str) -> bool {
+        !tag.starts_with("readitnow/") && !self.hidden_tags.iter().any(|hidden| hidden == tag)
+    }
+}
+
+/// `name` picks a built-in preset or a file from the themes directory, any
+/// other keys override single elements of that theme.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThemeConfig {
+    #[serde(default = "default_theme_name")]
+    pub name: String,
+    #[serde(flatten)]
+    pub overrides: Theme,
+}
+
+fn default_theme_name() -> String {
+    "dark".to_string()
+}
+
+impl Default for ThemeConfig {
+    fn default() -> Self {
+        ThemeConfig {
+            name: default_theme_name(),
+            overrides: Theme::default(),
+        }
+    }
+}
+
+/// Keys for each action, as specs like `ctrl+d`, `shift+enter`, `f5` or
+/// `space`, or sequences like `g g`; one or a list per action. Actions left
+/// out keep their defaults.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Keybindings {
-    pub open_link: String,
-    pub open_file: String,
-    pub up: String,
-    pub down: String,
-    pub left: String,
-    pub right: String,
-    pub page_up: String,
-    pub page_down: String,
-    pub quit: String,
+    pub open_link: KeyList,
+    pub open_file: KeyList,
+    pub up: KeyList,
+    pub down: KeyList,
+    pub left: KeyList,
+    pub right: KeyList,
+    pub page_up: KeyList,
+    pub page_down: KeyList,
+    pub first: KeyList,
+    pub last: KeyList,
+    pub quit: KeyList,
+    pub toggle_read: KeyList,
+    pub archive: KeyList,
+    pub hide_read: KeyList,
+    pub search: KeyList,
+    pub full_text_search: KeyList,
+    pub filter: KeyList,
+    pub next_view: KeyList,
+    pub previous_view: KeyList,
+    pub sort: KeyList,
+    pub reverse_sort: KeyList,
+    pub group_by: KeyList,
+    pub toggle_group: KeyList,
+    pub related: KeyList,
+    pub duplicates: KeyList,
+    pub tags: KeyList,
+    pub surprise: KeyList,
+    pub surprise_budget: KeyList,
+    pub next_match: KeyList,
+    pub previous_match: KeyList,
+    /// Opens the `:` command prompt
+    pub command: KeyList,
+    /// Marks or unmarks the selected note for a bulk action
+    pub mark: KeyList,
+    pub mark_page: KeyList,
+    /// Marks every note the view shows, on any page
+    pub mark_all: KeyList,
+    /// Opens the bulk action menu for the marked notes
+    pub bulk: KeyList,
+    /// Reverses the last change to the vault
+    pub undo: KeyList,
+    /// Makes the last undone change again
+    pub redo: KeyList,
+    /// Copy the selected note's URL, title, `[title](url)` or `[[wiki-link]]`
+    pub yank_url: KeyList,
+    pub yank_title: KeyList,
+    pub yank_markdown: KeyList,
+    pub yank_wiki_link: KeyList,
+    /// Lists every key, custom actions included
+    pub help: KeyList,
+    /// Clears the search, then the filter, then the checked tags
+    pub back: KeyList,
+    /// How long to wait for the rest of a sequence. A lone digit switches to
+    /// that view at once, and a count left when this runs out is dropped.
+    pub timeout_ms: u64,
+}
+
+impl Default for Keybindings {
+    fn default() -> Self {
+        Keybindings {
+            open_link: KeyList::new(&["enter"]),
+            open_file: KeyList::new(&["shift+enter"]),
+            up: KeyList::new(&["up", "k"]),
+            down: KeyList::new(&["down", "j"]),
+            left: KeyList::new(&["left", "h"]),
+            right: KeyList::new(&["right", "l"]),
+            page_up: KeyList::new(&["pageup"]),
+            page_down: KeyList::new(&["pagedown"]),
+            first: KeyList::new(&["g g", "home"]),
+            last: KeyList::new(&["G", "end"]),
+            quit: KeyList::new(&["q"]),
+            toggle_read: KeyList::new(&["r"]),
+            archive: KeyList::new(&["d d"]),
+            hide_read: KeyList::new(&["H"]),
+            search: KeyList::new(&["/"]),
+            full_text_search: KeyList::new(&["F"]),
+            filter: KeyList::new(&["f"]),
+            next_view: KeyList::new(&["tab", "g t"]),
+            previous_view: KeyList::new(&["shift+tab", "g T"]),
+            sort: KeyList::new(&["s"]),
+            reverse_sort: KeyList::new(&["S"]),
+            group_by: KeyList::new(&["g b"]),
+            toggle_group: KeyList::new(&["space"]),
+            related: KeyList::new(&["R"]),
+            duplicates: KeyList::new(&["D"]),
+            tags: KeyList::new(&["t"]),
+            surprise: KeyList::new(&["z"]),
+            surprise_budget: KeyList::new(&["Z"]),
+            next_match: KeyList::new(&["n"]),
+            previous_match: KeyList::new(&["N"]),
+            command: KeyList::new(&[":"]),
+            mark: KeyList::new(&["m"]),
+            mark_page: KeyList::new(&["M"]),
+            mark_all: KeyList::new(&["ctrl+a"]),
+            bulk: KeyList::new(&["b"]),
+            undo: KeyList::new(&["u"]),
+            redo: KeyList::new(&["ctrl+r"]),
+            yank_url: KeyList::new(&["y y"]),
+            yank_title: KeyList::new(&["y t"]),
+            yank_markdown: KeyList::new(&["y m"]),
+            yank_wiki_link: KeyList::new(&["y w"]),
+            help: KeyList::new(&["?"]),
+            back: KeyList::new(&["esc"]),
+            timeout_ms: 1000,
+        }
+    }
+}
+
+impl Keybindings {
+    /// Configs written before keys could be lists hold the old defaults as
+    /// single keys, like `up: up`. Those get the whole new default list, so
+    /// `k` and friends work after upgrading. Keys changed by hand are kept.
+    pub fn upgrade_legacy_defaults(&mut self) {
+        let defaults = Keybindings::default();
+        let legacy = [
+            (&mut self.open_link, defaults.open_link, "enter"),
+            (&mut self.open_file, defaults.open_file, "shift+enter"),
+            (&mut self.up, defaults.up, "up"),
+            (&mut self.down, defaults.down, "down"),
+            (&mut self.left, defaults.left, "left"),
+            (&mut self.right, defaults.right, "right"),
+            (&mut self.page_up, defaults.page_up, "pageup"),
+            (&mut self.page_down, defaults.page_down, "pagedown"),
+            (&mut self.quit, defaults.quit, "q"),
+        ];
+        for (keys, default, old_default) in legacy {
+            if keys.0 == [old_default] {
+                *keys = default;
+            }
+        }
+    }
+}
+
+fn cache_dir(name: &str) -> String {
+    if let Some(proj_dirs) = ProjectDirs::from("com", "readitnow", "readitnow") {
+        proj_dirs.cache_dir().join(name)
+            .to_string_lossy().to_string()
+    } else {
+        format!(".cache/readitnow/{}", name)
+    }
+}
+
+fn default_index_cache() -> String {
+    cache_dir("index")
+}
+
+fn default_mouse() -> bool {
+    true
 }
 
 impl Default for Config {
@@ -34,43 +444,59 @@ impl Default for Config {
             "vault/ReadItLater Inbox".to_string()
         };
 
-        let thumbnail_cache = if let Some(proj_dirs) = ProjectDirs::from("com", "readitnow", "readitnow") {
-            proj_dirs.cache_dir().join("thumbnails")
-                .to_string_lossy().to_string()
-        } else {
-            ".cache/readitnow/thumbnails".to_string()
-        };
+        let thumbnail_cache = cache_dir("thumbnails");
 
         Config {
             vault_path,
             max_notes: 20,
             excerpt_lines: 5,
-            keybindings: Keybindings {
-                open_link: "enter".to_string(),
-                open_file: "shift+enter".to_string(),
-                up: "up".to_string(),
-                down: "down".to_string(),
-                left: "left".to_string(),
-                right: "right".to_string(),
-                page_up: "pageup".to_string(),
-                page_down: "pagedown".to_string(),
-                quit: "q".to_string(),
-            },
+            keybindings: Keybindings::default(),
             thumbnail_cache,
+            index_cache: default_index_cache(),
+            theme: ThemeConfig::default(),
+            hide_read: false,
+            card: CardConfig::default(),
+            images: ImageConfig::default(),
+            views: default_views(),
+            surprise: SurpriseConfig::default(),
+            mouse: default_mouse(),
+            undo: UndoConfig::default(),
+            editor: None,
+            clipboard: None,
+            clipboard_tmux_passthrough: false,
+            actions: Vec::new(),
         }
     }
 }
 
 impl Config {
+    pub fn config_dir() -> Option<PathBuf> {
+        ProjectDirs::from("com", "readitnow", "readitnow").map(|proj_dirs| proj_dirs.config_dir().to_path_buf())
+    }
+
+    pub fn path() -> Option<PathBuf> {
+        Self::config_dir().map(|dir| dir.join("config.yaml"))
+    }
+
+    pub fn themes_dir() -> Option<PathBuf> {
+        Self::config_dir().map(|dir| dir.join("themes"))
+    }
+
+    /// Resolves the configured theme and applies the inline overrides on top.
+    pub fn load_theme(&self) -> io::Result<Theme> {
+        let base = Theme::load(&self.theme.name, Self::themes_dir().as_deref())?;
+        Ok(base.merge(&self.theme.overrides))
+    }
+
     pub fn load_or_default() -> Self {
         if let Some(proj_dirs) = ProjectDirs::from("com", "readitnow", "readitnow") {
             let config_dir = proj_dirs.config_dir();
             let config_path = config_dir.join("config.yaml");
 
-            if config_path.exists() {
-                if let Ok(config) = Self::load(&config_path) {
-                    return config;
-                }
+            if config_path.exists()
+                && let Ok(config) = Self::load(&config_path)
+            {
+                return config;
             }
         }
 
@@ -87,8 +513,10 @@ impl Config {
 
     pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
         let config_str = fs::read_to_string(path)?;
-        serde_yaml::from_str(&config_str)
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        let mut config: Config = serde_yaml::from_str(&config_str)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        config.keybindings.upgrade_legacy_defaults();
+        Ok(config)
     }
 
     pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
@@ -0,0 +1,21 @@
+use crate::models::Note;
+use std::collections::BTreeMap;
+
+/// Notes sharing a canonical URL, as indexes into `notes`. The earliest saved
+/// note of each group comes first, it is the one kept by default when merging.
+pub fn find_duplicates(notes: &[Note]) -> Vec<Vec<usize>> {
+    let mut by_url: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for (i, note) in notes.iter().enumerate() {
+        if let Some(url) = note.canonical_url() {
+            by_url.entry(url).or_default().push(i);
+        }
+    }
+    by_url.into_values()
+        .filter(|group| group.len() > 1)
+        .map(|mut group| {
+            // Undated notes last, ties keep the vault order
+            group.sort_by_key(|&i| (notes[i].saved_date().is_none(), notes[i].saved_date()));
+            group
+        })
+        .collect()
+}
@@ -0,0 +1,130 @@
+use crate::config::Config;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+
+/// A program run outside the app, like the editor for a note.
+#[derive(Debug, Clone, PartialEq)]
+pub struct External {
+    pub program: String,
+    pub args: Vec<String>,
+    pub suspend: bool, // Hand it the terminal and wait, for programs that draw on it
+    pub reload: Option<PathBuf>, // Note to parse again once it exits
+}
+
+impl External {
+    /// Builds the command from a template, filling in each `{name}` with its
+    /// value. Values are put into words after splitting, so spaces in them
+    /// don't split arguments. No shell is involved. None for a template
+    /// without a program.
+    pub fn from_template(template: &str, values: &[(&str, &str)]) -> Option<External> {
+        let mut words = split_words(template).into_iter().map(|word| fill(&word, values));
+        Some(External { program: words.next()?, args: words.collect(), suspend: false, reload: None })
+    }
+
+    /// Runs the command, waiting for it unless it neither suspends the app nor
+    /// has a note to reload. Programs that don't get the terminal get no input or output.
+    pub fn run(&self) -> io::Result<Option<ExitStatus>> {
+        let mut command = Command::new(&self.program);
+        command.args(&self.args);
+        if !self.suspend {
+            command.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null());
+            if self.reload.is_none() {
+                command.spawn()?;
+                return Ok(None);
+            }
+        }
+        command.status().map(Some)
+    }
+
+    /// What to tell the user about how it went, nothing when it went fine.
+    pub fn report(&self, result: io::Result<Option<ExitStatus>>) -> Option<String> {
+        match result {
+            Ok(Some(status)) if !status.success() => Some(format!("{} exited with {}", self.program, status)),
+            Ok(_) => None,
+            Err(e) => Some(format!("Couldn't run {}: {}", self.program, e)),
+        }
+    }
+}
+
+/// Replaces each `{name}` in one pass, so braces in a value are left as they are.
+fn fill(word: &str, values: &[(&str, &str)]) -> String {
+    let mut filled = String::new();
+    let mut rest = word;
+    while let Some(open) = rest.find('{') {
+        filled.push_str(&rest[..open]);
+        rest = &rest[open..];
+        let value = rest.find('}').and_then(|close| {
+            values.iter().find(|(name, _)| *name == &rest[1..close]).map(|(_, value)| (close, value))
+        });
+        match value {
+            Some((close, value)) => {
+                filled.push_str(value);
+                rest = &rest[close + 1..];
+            }
+            None => {
+                filled.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    filled.push_str(rest);
+    filled
+}
+
+/// Splits on whitespace, keeping words in single or double quotes together.
+fn split_words(template: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote = None;
+    for c in template.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => word.get_or_insert_with(String::new).push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (None, c) => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    words
+}
+
+/// The editor for a note: `editor` from the config, else `$VISUAL` or `$EDITOR`
+/// with the path after it. The note is parsed again once the editor exits.
+pub fn editor_command(config: &Config, path: &Path, line: usize) -> Option<External> {
+    let template = match &config.editor {
+        Some(editor) => editor.clone(),
+        None => {
+            let editor = ["VISUAL", "EDITOR"].iter().find_map(|name| std::env::var(name).ok().filter(|value| !value.trim().is_empty()))?;
+            format!("{} {{path}}", editor)
+        }
+    };
+    let path_str = path.to_string_lossy();
+    let line_str = line.to_string();
+    let mut external = External::from_template(&template, &[("path", &path_str), ("line", &line_str)])?;
+    if !template.contains("{path}") {
+        external.args.push(path_str.to_string());
+    }
+    external.suspend = true;
+    external.reload = Some(path.to_path_buf());
+    Some(external)
+}
+
+/// The first line, counting from 1, with a word of the search query in it, so
+/// the editor opens at the match. 1 without a search or a match.
+pub fn match_line(content: &str, query: Option<&str>) -> usize {
+    let words: Vec<String> = query.unwrap_or("").split_whitespace().map(str::to_lowercase).collect();
+    if words.is_empty() {
+        return 1;
+    }
+    content.lines()
+        .position(|line| {
+            let line = line.to_lowercase();
+            words.iter().any(|word| line.contains(word.as_str()))
+        })
+        .map_or(1, |i| i + 1)
+}
@@ -0,0 +1,107 @@
+use crate::config::CardConfig;
+use crate::models::{Note, NoteKind};
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupBy {
+    Domain,
+    Kind,
+    /// First tag shown on the card, hidden and state tags don't count
+    Tag,
+    /// Today, this week, this month or older, by the saved date
+    Date,
+}
+
+impl GroupBy {
+    pub const ALL: [GroupBy; 4] = [GroupBy::Domain, GroupBy::Kind, GroupBy::Tag, GroupBy::Date];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            GroupBy::Domain => "domain",
+            GroupBy::Kind => "kind",
+            GroupBy::Tag => "tag",
+            GroupBy::Date => "date",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<GroupBy> {
+        GroupBy::ALL.into_iter().find(|by| by.label() == name.to_lowercase())
+    }
+
+    /// The grouping after `current` in `ALL`, back to none after the last one.
+    pub fn cycle(current: Option<GroupBy>) -> Option<GroupBy> {
+        match current {
+            None => Some(GroupBy::ALL[0]),
+            Some(by) => {
+                let position = GroupBy::ALL.iter().position(|other| *other == by).unwrap_or(0);
+                GroupBy::ALL.get(position + 1).copied()
+            }
+        }
+    }
+}
+
+/// A section of the list: notes sharing a domain, kind, tag or date bucket.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Group {
+    pub label: String,
+    pub notes: Vec<usize>, // Indexes into `notes`, in display order
+}
+
+/// Splits `indexes` into groups, keeping their order inside each group. Kinds
+/// and date buckets come in a fixed order, domains and tags in the order they
+/// first appear; notes without one go last.
+pub fn group_notes(notes: &[Note], indexes: &[usize], by: GroupBy, card: &CardConfig, today: NaiveDate) -> Vec<Group> {
+    let mut keyed: Vec<((usize, String), Vec<usize>)> = Vec::new();
+    for &i in indexes {
+        let key = group_key(&notes[i], by, card, today);
+        match keyed.iter_mut().find(|(existing, _)| *existing == key) {
+            Some((_, group)) => group.push(i),
+            None => keyed.push((key, vec![i])),
+        }
+    }
+    // Stable, so groups of the same rank stay in order of first appearance
+    keyed.sort_by_key(|((rank, _), _)| *rank);
+    keyed.into_iter().map(|((_, label), notes)| Group { label, notes }).collect()
+}
+
+/// The label of the group `note` falls in.
+pub fn group_label(note: &Note, by: GroupBy, card: &CardConfig, today: NaiveDate) -> String {
+    group_key(note, by, card, today).1
+}
+
+/// The group a note falls in, with a rank ordering the groups.
+fn group_key(note: &Note, by: GroupBy, card: &CardConfig, today: NaiveDate) -> (usize, String) {
+    match by {
+        GroupBy::Domain => match note.domain() {
+            Some(domain) => (0, domain),
+            None => (1, "No domain".to_string()),
+        },
+        GroupBy::Kind => match note.kind() {
+            NoteKind::Article => (0, "Articles".to_string()),
+            NoteKind::Video => (1, "Videos".to_string()),
+            NoteKind::Tweet => (2, "Tweets".to_string()),
+            NoteKind::Note => (3, "Notes".to_string()),
+        },
+        GroupBy::Tag => match note.tags.iter().find(|tag| card.is_tag_shown(tag)) {
+            Some(tag) => (0, tag.clone()),
+            None => (1, "Untagged".to_string()),
+        },
+        GroupBy::Date => {
+            let (rank, label) = match note.saved_date() {
+                Some(saved) if saved >= today => (0, "Today"),
+                Some(saved) if saved >= week_start(today) => (1, "This week"),
+                Some(saved) if saved.year() == today.year() && saved.month() == today.month() => (2, "This month"),
+                Some(_) => (3, "Older"),
+                None => (4, "Undated"),
+            };
+            (rank, label.to_string())
+        }
+    }
+}
+
+/// The Monday of `day`'s week.
+fn week_start(day: NaiveDate) -> NaiveDate {
+    day - chrono::Duration::days(i64::from(day.weekday().num_days_from_monday()))
+}
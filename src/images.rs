@@ -0,0 +1,296 @@
+use ratatui::layout::Rect;
+use ratatui_image::{picker::{Picker, ProtocolType}, protocol::StatefulProtocol, Resize};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use crate::vault::download_and_cache_thumbnail;
+
+// Used when the terminal doesn't report its pixel size
+pub const DEFAULT_FONT_SIZE: (u16, u16) = (8, 12);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageProtocol {
+    Auto,
+    Kitty,
+    Sixel,
+    Iterm2,
+    Halfblocks,
+    /// Don't render thumbnails at all
+    None,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImageConfig {
+    pub protocol: ImageProtocol,
+    /// Cell size in pixels as `[width, height]`, queried from the terminal when unset
+    pub font_size: Option<(u16, u16)>,
+    /// Decoded thumbnails kept in memory, 0 for no limit
+    pub cache_max_entries: usize,
+    /// Approximate memory used by decoded thumbnails, 0 for no limit
+    pub cache_max_bytes: usize,
+}
+
+impl Default for ImageConfig {
+    fn default() -> Self {
+        ImageConfig {
+            protocol: ImageProtocol::Auto,
+            font_size: None,
+            cache_max_entries: 64,
+            cache_max_bytes: 64 * 1024 * 1024,
+        }
+    }
+}
+
+impl ImageProtocol {
+    fn protocol_type(self) -> Option<ProtocolType> {
+        match self {
+            ImageProtocol::Kitty => Some(ProtocolType::Kitty),
+            ImageProtocol::Sixel => Some(ProtocolType::Sixel),
+            ImageProtocol::Iterm2 => Some(ProtocolType::Iterm2),
+            ImageProtocol::Halfblocks => Some(ProtocolType::Halfblocks),
+            ImageProtocol::Auto | ImageProtocol::None => None,
+        }
+    }
+}
+
+/// Builds the picker used for every thumbnail, or `None` when images are disabled.
+///
+/// The cell size comes from the terminal's window size ioctl. The protocol is
+/// a guess from `TERM`, `TERM_PROGRAM` and `LC_TERMINAL`; only when those name
+/// no known terminal does ratatui-image ask the terminal itself, with escape
+/// sequences whose reply it reads from stdin without a timeout. So this has to
+/// run once after entering the alternate screen and before the event loop
+/// starts reading input.
+pub fn detect_picker(config: &ImageConfig) -> Option<Picker> {
+    if config.protocol == ImageProtocol::None {
+        return None;
+    }
+
+    let mut picker = match config.font_size {
+        Some(font_size) => Picker::new(font_size),
+        None => Picker::from_termios().unwrap_or_else(|_| Picker::new(DEFAULT_FONT_SIZE)),
+    };
+    match config.protocol.protocol_type() {
+        Some(protocol_type) => picker.protocol_type = protocol_type,
+        None => {
+            picker.guess_protocol();
+        }
+    }
+    Some(picker)
+}
+
+/// Identifies one decoded thumbnail: the same file is cached separately per
+/// card size and greyscale variant.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ImageKey {
+    pub path: PathBuf,
+    /// Where to download `path` from when it isn't cached on disk yet
+    pub url: Option<String>,
+    pub greyscale: bool,
+    pub width: u16,
+    pub height: u16,
+}
+
+pub enum ImageStatus<'a> {
+    Ready(&'a mut Box<dyn StatefulProtocol>),
+    Loading,
+    Failed,
+}
+
+struct CacheEntry {
+    protocol: Box<dyn StatefulProtocol>,
+    bytes: usize,
+    last_used: u64,
+}
+
+struct LoadedImage {
+    key: ImageKey,
+    result: Result<(Box<dyn StatefulProtocol>, usize), String>,
+}
+
+/// Thumbnails decoded and encoded by a background thread, kept in memory
+/// with least-recently-used eviction once either limit is reached.
+pub struct ImageCache {
+    entries: HashMap<ImageKey, CacheEntry>,
+    pending: HashSet<ImageKey>,
+    failed: HashSet<ImageKey>,
+    max_entries: usize,
+    max_bytes: usize,
+    bytes: usize,
+    clock: u64,
+    requests: Option<Sender<ImageKey>>,
+    results: Option<Receiver<LoadedImage>>,
+}
+
+impl ImageCache {
+    /// A cache without a loader thread, nothing is ever loaded into it.
+    pub fn disabled() -> Self {
+        ImageCache {
+            entries: HashMap::new(),
+            pending: HashSet::new(),
+            failed: HashSet::new(),
+            max_entries: 0,
+            max_bytes: 0,
+            bytes: 0,
+            clock: 0,
+            requests: None,
+            results: None,
+        }
+    }
+
+    /// Spawns the loader thread, or returns a disabled cache when `picker` is `None`.
+    pub fn new(picker: Option<Picker>, config: &ImageConfig) -> Self {
+        let mut cache = ImageCache::disabled();
+        cache.max_entries = config.cache_max_entries;
+        cache.max_bytes = config.cache_max_bytes;
+
+        if let Some(picker) = picker {
+            let (request_tx, request_rx) = mpsc::channel();
+            let (result_tx, result_rx) = mpsc::channel();
+            thread::spawn(move || load_images(picker, request_rx, result_tx));
+            cache.requests = Some(request_tx);
+            cache.results = Some(result_rx);
+        }
+        cache
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.requests.is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    /// Returns the cached thumbnail, queueing it for loading when it isn't cached yet.
+    pub fn get_or_request(&mut self, key: &ImageKey) -> ImageStatus<'_> {
+        if self.failed.contains(key) {
+            return ImageStatus::Failed;
+        }
+        if !self.entries.contains_key(key) {
+            self.request(key.clone());
+            return ImageStatus::Loading;
+        }
+
+        self.clock += 1;
+        let entry = self.entries.get_mut(key).unwrap();
+        entry.last_used = self.clock;
+        ImageStatus::Ready(&mut entry.protocol)
+    }
+
+    /// Queues a thumbnail in the background without touching the cache order.
+    pub fn request(&mut self, key: ImageKey) {
+        if self.entries.contains_key(&key) || self.failed.contains(&key) || self.pending.contains(&key) {
+            return;
+        }
+        if let Some(requests) = &self.requests
+            && requests.send(key.clone()).is_ok()
+        {
+            self.pending.insert(key);
+        }
+    }
+
+    /// Moves finished thumbnails from the loader thread into the cache.
+    /// Returns true when anything new can be drawn.
+    pub fn receive(&mut self) -> bool {
+        let Some(results) = &self.results else {
+            return false;
+        };
+        let loaded: Vec<LoadedImage> = results.try_iter().collect();
+        let received = !loaded.is_empty();
+
+        for LoadedImage { key, result } in loaded {
+            self.pending.remove(&key);
+            match result {
+                Ok((protocol, bytes)) => self.insert(key, protocol, bytes),
+                Err(_) => {
+                    self.failed.insert(key);
+                }
+            }
+        }
+        received
+    }
+
+    pub fn insert(&mut self, key: ImageKey, protocol: Box<dyn StatefulProtocol>, bytes: usize) {
+        self.clock += 1;
+        if let Some(previous) = self.entries.insert(key, CacheEntry { protocol, bytes, last_used: self.clock }) {
+            self.bytes -= previous.bytes;
+        }
+        self.bytes += bytes;
+        self.evict();
+    }
+
+    /// Drops every cached thumbnail of `path`, e.g. after the file was replaced.
+    pub fn invalidate(&mut self, path: &Path) {
+        let stale: Vec<ImageKey> = self.entries.keys().filter(|key| key.path == path).cloned().collect();
+        for key in stale {
+            if let Some(entry) = self.entries.remove(&key) {
+                self.bytes -= entry.bytes;
+            }
+        }
+        self.failed.retain(|key| key.path != path);
+    }
+
+    fn over_limit(&self) -> bool {
+        (self.max_entries > 0 && self.entries.len() > self.max_entries)
+            || (self.max_bytes > 0 && self.bytes > self.max_bytes)
+    }
+
+    fn evict(&mut self) {
+        // The most recently inserted entry is always kept, even when it alone exceeds max_bytes
+        while self.entries.len() > 1 && self.over_limit() {
+            let oldest = self.entries.iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+            match oldest.and_then(|key| self.entries.remove(&key)) {
+                Some(entry) => self.bytes -= entry.bytes,
+                None => break,
+            }
+        }
+    }
+}
+
+fn load_images(mut picker: Picker, requests: Receiver<ImageKey>, results: Sender<LoadedImage>) {
+    for key in requests {
+        let result = load_image(&mut picker, &key);
+        if results.send(LoadedImage { key, result }).is_err() {
+            break;
+        }
+    }
+}
+
+/// Downloads when needed, then decodes, shrinks and encodes a thumbnail for a `width` x `height` cell area,
+/// so rendering it later needs no further work.
+fn load_image(picker: &mut Picker, key: &ImageKey) -> Result<(Box<dyn StatefulProtocol>, usize), String> {
+    if let (false, Some(url), Some(cache_dir)) = (key.path.exists(), &key.url, key.path.parent()) {
+        download_and_cache_thumbnail(url, cache_dir).map_err(|e| e.to_string())?;
+    }
+    let image = image::open(&key.path).map_err(|e| e.to_string())?;
+    let (font_width, font_height) = picker.font_size;
+    let image = image.thumbnail(
+        u32::from(key.width) * u32::from(font_width),
+        u32::from(key.height) * u32::from(font_height),
+    );
+    let image = if key.greyscale { image.grayscale() } else { image };
+    // The protocol keeps the source image next to its encoded form
+    let bytes = image.as_bytes().len() * 2;
+
+    let mut protocol = picker.new_resize_protocol(image);
+    let area = Rect::new(0, 0, key.width, key.height);
+    if let Some(rect) = protocol.needs_resize(&Resize::Fit, area) {
+        protocol.resize_encode(&Resize::Fit, None, rect);
+    }
+    Ok((protocol, bytes))
+}
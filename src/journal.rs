@@ -0,0 +1,242 @@
+use crate::app::App;
+use crate::config::Config;
+use crate::vault::{self, VaultChange};
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::{Path, PathBuf}};
+
+/// How far back changes can be undone.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UndoConfig {
+    /// Keep the journal in the index cache, so changes can be undone after a restart
+    pub persist: bool,
+    /// Changes kept, 0 for no limit
+    pub limit: usize,
+}
+
+impl Default for UndoConfig {
+    fn default() -> Self {
+        UndoConfig {
+            persist: false,
+            limit: 100,
+        }
+    }
+}
+
+/// A change the app made to the vault, with enough recorded to reverse it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Operation {
+    /// `removed` at byte `start` was replaced by `inserted`. The md5 of the
+    /// whole file before and after tells whether it was changed since.
+    Edit {
+        path: PathBuf,
+        before: String,
+        after: String,
+        start: usize,
+        removed: String,
+        inserted: String,
+    },
+    Move { from: PathBuf, to: PathBuf },
+}
+
+impl Operation {
+    /// The edit from `before` to `after` as a single splice, around the longest common prefix and suffix.
+    pub fn edit(path: &Path, before: &str, after: &str) -> Operation {
+        let mut start = before.bytes().zip(after.bytes()).take_while(|(a, b)| a == b).count();
+        while !before.is_char_boundary(start) {
+            start -= 1;
+        }
+        let max_suffix = before.len().min(after.len()) - start;
+        let mut suffix = before.bytes().rev().zip(after.bytes().rev()).take(max_suffix).take_while(|(a, b)| a == b).count();
+        while !before.is_char_boundary(before.len() - suffix) {
+            suffix -= 1;
+        }
+        Operation::Edit {
+            path: path.to_path_buf(),
+            before: content_hash(before),
+            after: content_hash(after),
+            start,
+            removed: before[start..before.len() - suffix].to_string(),
+            inserted: after[start..after.len() - suffix].to_string(),
+        }
+    }
+
+    /// Why the operation can't be undone (or redone) right now, if it can't.
+    fn check(&self, undo: bool) -> Result<(), String> {
+        match self {
+            Operation::Edit { path, before, after, .. } => {
+                let expected = if undo { after } else { before };
+                match fs::read_to_string(path) {
+                    Ok(content) if content_hash(&content) == *expected => Ok(()),
+                    Ok(_) => Err(format!("{} was changed since", file_name(path))),
+                    Err(e) => Err(format!("{}: {}", file_name(path), e)),
+                }
+            }
+            Operation::Move { from, to } => {
+                let (source, target) = if undo { (to, from) } else { (from, to) };
+                if !source.exists() {
+                    Err(format!("{} is gone", file_name(source)))
+                } else if target.exists() {
+                    Err(format!("{} exists already", file_name(target)))
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    fn apply(&self, undo: bool) -> io::Result<()> {
+        match self {
+            Operation::Edit { path, start, removed, inserted, .. } => {
+                let (old, new) = if undo { (inserted, removed) } else { (removed, inserted) };
+                let content = fs::read_to_string(path)?;
+                let end = start + old.len();
+                if content.get(*start..end) != Some(old.as_str()) {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, format!("the change doesn't apply to {}", file_name(path))));
+                }
+                fs::write(path, format!("{}{}{}", &content[..*start], new, &content[end..]))
+            }
+            Operation::Move { from, to } => {
+                let (source, target) = if undo { (to, from) } else { (from, to) };
+                fs::rename(source, target)
+            }
+        }
+    }
+
+    /// What the app has to reload once the operation is applied.
+    fn changes(&self, undo: bool, config: &Config) -> Vec<VaultChange> {
+        match self {
+            Operation::Edit { path, .. } => vec![vault::reload_note(path, config)],
+            Operation::Move { from, to } => {
+                let (source, target) = if undo { (to, from) } else { (from, to) };
+                // Only notes in the vault are shown, not the ones in `.trash`
+                let mut changes = vec![VaultChange::Removed(source.clone())];
+                if target.parent() == Some(Path::new(&config.vault_path)) {
+                    changes.push(vault::reload_note(target, config));
+                }
+                changes
+            }
+        }
+    }
+}
+
+fn content_hash(content: &str) -> String {
+    format!("{:x}", md5::compute(content))
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map_or_else(|| path.display().to_string(), |name| format!("\"{}\"", name.to_string_lossy()))
+}
+
+/// Operations undone and redone together, like every note of a bulk action.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    pub label: String, // "marking \"Title\" read", for the status bar
+    pub operations: Vec<Operation>,
+}
+
+/// The undo and redo stacks, newest last.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Journal {
+    pub undo: Vec<Entry>,
+    pub redo: Vec<Entry>,
+    #[serde(skip)]
+    pub limit: usize, // Entries kept, 0 for no limit
+}
+
+impl Journal {
+    pub fn new(limit: usize) -> Self {
+        Journal { limit, ..Journal::default() }
+    }
+
+    /// Adds an entry to undo. A new change can't be redone past, so it drops the redo stack.
+    pub fn record(&mut self, label: impl Into<String>, operations: Vec<Operation>) {
+        if operations.is_empty() {
+            return;
+        }
+        self.undo.push(Entry { label: label.into(), operations });
+        self.redo.clear();
+        if self.limit > 0 && self.undo.len() > self.limit {
+            let excess = self.undo.len() - self.limit;
+            self.undo.drain(..excess);
+        }
+    }
+
+    fn path(config: &Config) -> PathBuf {
+        PathBuf::from(&config.index_cache).join("journal.yaml")
+    }
+
+    /// The journal saved by the last session when `undo.persist` is on, otherwise an empty one.
+    pub fn load(config: &Config) -> Self {
+        let journal = if config.undo.persist {
+            fs::read_to_string(Self::path(config)).ok()
+                .and_then(|journal_str| serde_yaml::from_str::<Journal>(&journal_str).ok())
+                .unwrap_or_default()
+        } else {
+            Journal::default()
+        };
+        Journal { limit: config.undo.limit, ..journal }
+    }
+
+    pub fn save(&self, config: &Config) -> io::Result<()> {
+        if !config.undo.persist {
+            return Ok(());
+        }
+        fs::create_dir_all(&config.index_cache)?;
+        let journal_str = serde_yaml::to_string(self)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(Self::path(config), journal_str)
+    }
+}
+
+/// Reverses the newest change, unless a file it touched was changed since.
+pub fn undo(app: &mut App, config: &Config) {
+    step(app, config, true);
+}
+
+/// Applies the newest undone change again.
+pub fn redo(app: &mut App, config: &Config) {
+    step(app, config, false);
+}
+
+fn step(app: &mut App, config: &Config, undo: bool) {
+    let (stack, verb) = if undo { (&mut app.journal.undo, "undo") } else { (&mut app.journal.redo, "redo") };
+    let Some(entry) = stack.pop() else {
+        app.status_message = Some(format!("Nothing to {}", verb));
+        return;
+    };
+    // Every file has to be as recorded before any is touched
+    if let Err(reason) = entry.operations.iter().try_for_each(|operation| operation.check(undo)) {
+        app.status_message = Some(format!("Can't {} {}: {}", verb, entry.label, reason));
+        stack.push(entry);
+        return;
+    }
+
+    let operations: Vec<&Operation> = if undo { entry.operations.iter().rev().collect() } else { entry.operations.iter().collect() };
+    for (applied, operation) in operations.iter().enumerate() {
+        if let Err(e) = operation.apply(undo) {
+            // Put back what was already applied, so the entry still matches the files
+            let mut changes = Vec::new();
+            for done in operations[..applied].iter().rev() {
+                if done.apply(!undo).is_err() {
+                    changes.extend(done.changes(undo, config));
+                }
+            }
+            let mut message = format!("Can't {} {}: {}", verb, entry.label, e);
+            if !changes.is_empty() {
+                message.push_str(", and some files couldn't be put back");
+            }
+            app.apply_vault_changes(changes);
+            app.status_message = Some(message);
+            if undo { app.journal.undo.push(entry) } else { app.journal.redo.push(entry) }
+            return;
+        }
+    }
+    let changes = operations.iter().flat_map(|operation| operation.changes(undo, config)).collect();
+    app.apply_vault_changes(changes);
+
+    let done = if undo { "Undid" } else { "Redid" };
+    app.status_message = Some(format!("{} {}", done, entry.label));
+    if undo { app.journal.redo.push(entry) } else { app.journal.undo.push(entry) }
+}
@@ -1,54 +1,849 @@
-use crate::app::App;
-use crate::config::Config;
-use crate::vault;
+use crate::app::{App, PromptKind, Row, SearchKind};
+use crate::bulk::BulkAction;
+use crate::commands::{self, Command};
+use crate::config::{Config, CustomAction, Keybindings};
+use crate::external::{self, External};
+use crate::journal::{self, Operation};
+use crate::keys::{self, KeyList, KeySpec};
+use crate::models::Note;
+use crate::surprise::Surprise;
+use crate::vault::{self, VaultChange};
+use chrono::Local;
 use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 use open;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
-#[derive(PartialEq)]
+// Counts past this are clamped, so a stuck key can't queue up endless moves
+const MAX_COUNT: usize = 9999;
+
+// Lines the help popup scrolls by a page
+const HELP_PAGE: isize = 10;
+
+static NO_KEYS: KeyList = KeyList(Vec::new());
+
+#[derive(Debug, PartialEq)]
 pub enum AppAction {
     Continue,
     Quit,
+    Reload, // Re-read every note from the vault
+    Run(External), // Left to the event loop, which owns the terminal
+    Yank { what: &'static str, text: String }, // For the clipboard, which is reached through the terminal too
+}
+
+/// Everything a key can be bound to, named as in `Config.keybindings`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    OpenLink,
+    OpenFile,
+    Up,
+    Down,
+    Left,
+    Right,
+    PageUp,
+    PageDown,
+    First,
+    Last,
+    Quit,
+    ToggleRead,
+    Archive,
+    HideRead,
+    Search,
+    FullTextSearch,
+    Filter,
+    NextView,
+    PreviousView,
+    Sort,
+    ReverseSort,
+    GroupBy,
+    ToggleGroup,
+    Related,
+    Duplicates,
+    Tags,
+    Surprise,
+    SurpriseBudget,
+    NextMatch,
+    PreviousMatch,
+    Command,
+    Mark,
+    MarkPage,
+    MarkAll,
+    Bulk,
+    Undo,
+    Redo,
+    YankUrl,
+    YankTitle,
+    YankMarkdown,
+    YankWikiLink,
+    Help,
+    Back,
+    Custom(usize), // Index into `Config.actions`
+}
+
+impl Action {
+    pub const ALL: [Action; 43] = [
+        Action::OpenLink,
+        Action::OpenFile,
+        Action::Up,
+        Action::Down,
+        Action::Left,
+        Action::Right,
+        Action::PageUp,
+        Action::PageDown,
+        Action::First,
+        Action::Last,
+        Action::Quit,
+        Action::ToggleRead,
+        Action::Archive,
+        Action::HideRead,
+        Action::Search,
+        Action::FullTextSearch,
+        Action::Filter,
+        Action::NextView,
+        Action::PreviousView,
+        Action::Sort,
+        Action::ReverseSort,
+        Action::GroupBy,
+        Action::ToggleGroup,
+        Action::Related,
+        Action::Duplicates,
+        Action::Tags,
+        Action::Surprise,
+        Action::SurpriseBudget,
+        Action::NextMatch,
+        Action::PreviousMatch,
+        Action::Command,
+        Action::Mark,
+        Action::MarkPage,
+        Action::MarkAll,
+        Action::Bulk,
+        Action::Undo,
+        Action::Redo,
+        Action::YankUrl,
+        Action::YankTitle,
+        Action::YankMarkdown,
+        Action::YankWikiLink,
+        Action::Help,
+        Action::Back,
+    ];
+
+    /// The key under `keybindings` in the config.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::OpenLink => "open_link",
+            Action::OpenFile => "open_file",
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::Left => "left",
+            Action::Right => "right",
+            Action::PageUp => "page_up",
+            Action::PageDown => "page_down",
+            Action::First => "first",
+            Action::Last => "last",
+            Action::Quit => "quit",
+            Action::ToggleRead => "toggle_read",
+            Action::Archive => "archive",
+            Action::HideRead => "hide_read",
+            Action::Search => "search",
+            Action::FullTextSearch => "full_text_search",
+            Action::Filter => "filter",
+            Action::NextView => "next_view",
+            Action::PreviousView => "previous_view",
+            Action::Sort => "sort",
+            Action::ReverseSort => "reverse_sort",
+            Action::GroupBy => "group_by",
+            Action::ToggleGroup => "toggle_group",
+            Action::Related => "related",
+            Action::Duplicates => "duplicates",
+            Action::Tags => "tags",
+            Action::Surprise => "surprise",
+            Action::SurpriseBudget => "surprise_budget",
+            Action::NextMatch => "next_match",
+            Action::PreviousMatch => "previous_match",
+            Action::Command => "command",
+            Action::Mark => "mark",
+            Action::MarkPage => "mark_page",
+            Action::MarkAll => "mark_all",
+            Action::Bulk => "bulk",
+            Action::Undo => "undo",
+            Action::Redo => "redo",
+            Action::YankUrl => "yank_url",
+            Action::YankTitle => "yank_title",
+            Action::YankMarkdown => "yank_markdown",
+            Action::YankWikiLink => "yank_wiki_link",
+            Action::Help => "help",
+            Action::Back => "back",
+            Action::Custom(_) => "custom",
+        }
+    }
+
+    /// The step `perform` repeats once per count typed before this action.
+    fn repeated_step(&self) -> Option<fn(&mut App)> {
+        match self {
+            Action::Up => Some(App::previous_row),
+            Action::Down => Some(App::next_row),
+            Action::Left => Some(App::previous_note),
+            Action::Right => Some(App::next_note),
+            Action::PageUp => Some(App::previous_page),
+            Action::PageDown => Some(App::next_page),
+            Action::NextView => Some(App::next_view),
+            Action::PreviousView => Some(App::previous_view),
+            Action::NextMatch => Some(App::next_match),
+            Action::PreviousMatch => Some(App::previous_match),
+            _ => None,
+        }
+    }
+
+    /// Whether a count typed before this action is used, as a repeat or as a note number.
+    fn takes_count(&self) -> bool {
+        self.repeated_step().is_some() || matches!(self, Action::First | Action::Last)
+    }
+
+    fn keys<'a>(&self, keybindings: &'a Keybindings) -> &'a KeyList {
+        match self {
+            Action::OpenLink => &keybindings.open_link,
+            Action::OpenFile => &keybindings.open_file,
+            Action::Up => &keybindings.up,
+            Action::Down => &keybindings.down,
+            Action::Left => &keybindings.left,
+            Action::Right => &keybindings.right,
+            Action::PageUp => &keybindings.page_up,
+            Action::PageDown => &keybindings.page_down,
+            Action::First => &keybindings.first,
+            Action::Last => &keybindings.last,
+            Action::Quit => &keybindings.quit,
+            Action::ToggleRead => &keybindings.toggle_read,
+            Action::Archive => &keybindings.archive,
+            Action::HideRead => &keybindings.hide_read,
+            Action::Search => &keybindings.search,
+            Action::FullTextSearch => &keybindings.full_text_search,
+            Action::Filter => &keybindings.filter,
+            Action::NextView => &keybindings.next_view,
+            Action::PreviousView => &keybindings.previous_view,
+            Action::Sort => &keybindings.sort,
+            Action::ReverseSort => &keybindings.reverse_sort,
+            Action::GroupBy => &keybindings.group_by,
+            Action::ToggleGroup => &keybindings.toggle_group,
+            Action::Related => &keybindings.related,
+            Action::Duplicates => &keybindings.duplicates,
+            Action::Tags => &keybindings.tags,
+            Action::Surprise => &keybindings.surprise,
+            Action::SurpriseBudget => &keybindings.surprise_budget,
+            Action::NextMatch => &keybindings.next_match,
+            Action::PreviousMatch => &keybindings.previous_match,
+            Action::Command => &keybindings.command,
+            Action::Mark => &keybindings.mark,
+            Action::MarkPage => &keybindings.mark_page,
+            Action::MarkAll => &keybindings.mark_all,
+            Action::Bulk => &keybindings.bulk,
+            Action::Undo => &keybindings.undo,
+            Action::Redo => &keybindings.redo,
+            Action::YankUrl => &keybindings.yank_url,
+            Action::YankTitle => &keybindings.yank_title,
+            Action::YankMarkdown => &keybindings.yank_markdown,
+            Action::YankWikiLink => &keybindings.yank_wiki_link,
+            Action::Help => &keybindings.help,
+            Action::Back => &keybindings.back,
+            // Custom actions carry their own keys
+            Action::Custom(_) => &NO_KEYS,
+        }
+    }
 }
 
-// TODO: use the keybindings from the config
-pub fn handle_key_event(key: KeyEvent, app: &mut App, config: &Config) -> AppAction {
-    if key.kind == KeyEventKind::Press {
-        match key.code {
-            KeyCode::Char(c) if c.to_string() == config.keybindings.quit => {
-                return AppAction::Quit;
-            }
-            KeyCode::Char(c) if c.to_string() == "j" => app.next_two_notes(),
-            KeyCode::Char(c) if c.to_string() == "k" => app.previous_two_notes(),
-            KeyCode::Char(c) if c.to_string() == "h" => app.previous_note(),
-            KeyCode::Char(c) if c.to_string() == "l" => app.next_note(),
-            KeyCode::Up => app.previous_two_notes(),
-            KeyCode::Down => app.next_two_notes(),
-            KeyCode::Left => app.previous_note(),
-            KeyCode::Right => app.next_note(),
-            KeyCode::PageDown => app.next_page(),
-            KeyCode::PageUp => app.previous_page(),
-            KeyCode::Enter => {
-                if key.modifiers.contains(KeyModifiers::SHIFT) {
-                    // Shift+Enter: Open file
-                    // TODO: open file in default editor, now its opening in kitty
-                    if let Some(note) = app.selected_note() {
-                        let _ = open::that(format!("{}/{}.md", config.vault_path, note.title));
-                    }
-                } else if let Some(note) = app.selected_note() {
-                    if let Some(url) = &note.url {
-                        let _ = open::that(url);
+/// Which action each key or key sequence runs, built once from `Config.keybindings`.
+#[derive(Debug, Clone, Default)]
+pub struct KeyMap {
+    bindings: HashMap<Vec<KeySpec>, Action>,
+    timeout: Duration,
+}
+
+impl KeyMap {
+    /// Parses every key spec. Specs that don't parse are skipped and a key bound
+    /// twice keeps its first action in `Action::ALL`; both come back as problems
+    /// to report at startup.
+    pub fn new(keybindings: &Keybindings) -> (KeyMap, Vec<String>) {
+        Self::with_actions(keybindings, &[])
+    }
+
+    /// Like `new`, with the custom actions from the config bound after the built-in ones.
+    pub fn with_actions(keybindings: &Keybindings, custom: &[CustomAction]) -> (KeyMap, Vec<String>) {
+        let name = |action: Action| match action {
+            Action::Custom(i) => custom[i].name.as_str(),
+            _ => action.name(),
+        };
+        let all = Action::ALL.into_iter().map(|action| (action, action.keys(keybindings)))
+            .chain(custom.iter().enumerate().map(|(i, custom)| (Action::Custom(i), &custom.key)));
+        let mut bindings: HashMap<Vec<KeySpec>, Action> = HashMap::new();
+        let mut problems = Vec::new();
+        for (action, key_list) in all {
+            for spec in &key_list.0 {
+                let keys = match keys::parse_sequence(spec) {
+                    Ok(keys) => keys,
+                    Err(error) => {
+                        problems.push(format!("{}: {}", name(action), error));
+                        continue;
+                    }
+                };
+                match bindings.get(&keys) {
+                    Some(existing) if *existing != action => {
+                        problems.push(format!(
+                            "`{}` is bound to both {} and {}",
+                            keys::format_sequence(&keys),
+                            name(*existing),
+                            name(action),
+                        ));
+                    }
+                    Some(_) => {}
+                    None => {
+                        bindings.insert(keys, action);
                     }
                 }
             }
-            KeyCode::Char(c) if c.to_string() == "r" => {
-                // TODO: test this
-                if let Some(note) = app.selected_note_mut() {
-                    let _ = vault::toggle_read_status(note, config);
+        }
+        let timeout = Duration::from_millis(keybindings.timeout_ms);
+        (KeyMap { bindings, timeout }, problems)
+    }
+
+    /// The action bound to a single key.
+    pub fn action(&self, key: &KeyEvent) -> Option<Action> {
+        self.bindings.get(&vec![KeySpec::from(key)]).copied()
+    }
+
+    /// The action bound to exactly `keys`, and whether longer sequences start with them.
+    fn lookup(&self, keys: &[KeySpec]) -> (Option<Action>, bool) {
+        let is_prefix = self.bindings.keys().any(|bound| bound.len() > keys.len() && bound.starts_with(keys));
+        (self.bindings.get(keys).copied(), is_prefix)
+    }
+
+    /// The keys that run `action`, for help texts.
+    pub fn keys(&self, action: Action) -> Vec<String> {
+        let mut keys: Vec<String> = self.bindings.iter()
+            .filter(|(_, bound)| **bound == action)
+            .map(|(keys, _)| keys::format_sequence(keys))
+            .collect();
+        keys.sort();
+        keys
+    }
+}
+
+pub fn handle_key_event(key: KeyEvent, app: &mut App, config: &Config, keymap: &KeyMap) -> AppAction {
+    if key.kind != KeyEventKind::Press {
+        return AppAction::Continue;
+    }
+    app.status_message = None;
+    if app.prompt.is_some() {
+        return handle_prompt_key(key, app, config);
+    }
+    if app.related.is_some() {
+        return handle_related_key(key, app, config);
+    }
+    if app.duplicates.is_some() {
+        handle_duplicates_key(key, app, config);
+        return AppAction::Continue;
+    }
+    if app.bulk_menu {
+        handle_bulk_menu_key(key, app);
+        return AppAction::Continue;
+    }
+    if app.help.is_some() {
+        handle_help_key(key, app);
+        return AppAction::Continue;
+    }
+    if app.tags.focused {
+        handle_tags_key(key, app);
+        return AppAction::Continue;
+    }
+
+    let spec = KeySpec::from(&key);
+    let pending = &mut app.pending_keys;
+    if spec.code == KeyCode::Esc && !pending.is_empty() {
+        pending.clear();
+        return AppAction::Continue;
+    }
+
+    // Digits make up a count unless a sequence is under way or they are bound
+    if let KeyCode::Char(c @ '0'..='9') = spec.code
+        && spec.modifiers.is_empty()
+        && pending.keys.is_empty()
+        && (c != '0' || pending.count.is_some())
+        && keymap.lookup(&[spec]) == (None, false)
+    {
+        let digit = c as usize - '0' as usize;
+        let starts_count = pending.count.is_none();
+        pending.count = Some((pending.count.unwrap_or(0) * 10 + digit).min(MAX_COUNT));
+        pending.last_key = Some(Instant::now());
+        // A lone digit switches to that view right away. A second digit, or a
+        // move that takes the count, goes back to the view it left.
+        if starts_count {
+            app.pending_keys.view_before_count = Some(app.active_view);
+            app.switch_view(digit - 1);
+        } else if let Some(view) = app.pending_keys.view_before_count.take() {
+            app.switch_view(view);
+        }
+        return AppAction::Continue;
+    }
+
+    let mut keys = pending.keys.clone();
+    keys.push(spec);
+    match keymap.lookup(&keys) {
+        // Wait for the next key, or the timeout to run what was typed so far
+        (_, true) => {
+            pending.keys = keys;
+            pending.last_key = Some(Instant::now());
+            AppAction::Continue
+        }
+        (Some(action), false) => {
+            let count = take_count(app, action);
+            perform(action, app, config, count)
+        }
+        (None, false) => {
+            pending.clear();
+            AppAction::Continue
+        }
+    }
+}
+
+/// Runs a complete sequence left waiting for longer than the timeout, and drops
+/// a count nothing followed. Call it regularly from the event loop.
+pub fn handle_pending_timeout(app: &mut App, config: &Config, keymap: &KeyMap, now: Instant) -> AppAction {
+    let pending = &mut app.pending_keys;
+    if pending.last_key.is_none_or(|last_key| now.duration_since(last_key) < keymap.timeout) {
+        return AppAction::Continue;
+    }
+    match keymap.lookup(&pending.keys) {
+        (Some(action), _) => {
+            let count = take_count(app, action);
+            perform(action, app, config, count)
+        }
+        (None, _) => {
+            pending.clear();
+            AppAction::Continue
+        }
+    }
+}
+
+/// Clears the pending keys and returns their count, going back to the view a
+/// lone digit left when `action` takes the count.
+fn take_count(app: &mut App, action: Action) -> Option<usize> {
+    let pending = std::mem::take(&mut app.pending_keys);
+    if action.takes_count() && let Some(view) = pending.view_before_count {
+        app.switch_view(view);
+    }
+    pending.count
+}
+
+/// Runs an action on the cards. Moves, view switches and match steps repeat
+/// `count` times, `first` and `last` go to the note with that number instead.
+pub fn perform(action: Action, app: &mut App, config: &Config, count: Option<usize>) -> AppAction {
+    let times = count.unwrap_or(1);
+    if let Some(step) = action.repeated_step() {
+        (0..times).for_each(|_| step(app));
+        return AppAction::Continue;
+    }
+    match action {
+        Action::Quit => return AppAction::Quit,
+        // Repeated above
+        Action::Up | Action::Down | Action::Left | Action::Right | Action::PageUp | Action::PageDown => {}
+        Action::NextView | Action::PreviousView | Action::NextMatch | Action::PreviousMatch => {}
+        Action::First | Action::Last if count.is_some() => {
+            if let Some(&note_index) = app.visible.get(times.saturating_sub(1).min(app.visible.len().saturating_sub(1))) {
+                app.select_note(note_index);
+            }
+        }
+        Action::First => app.select_first(),
+        Action::Last => app.select_last(),
+        Action::OpenLink if matches!(app.selected_row(), Some(Row::Header { .. })) => app.toggle_group(),
+        Action::OpenLink => {
+            if let Some(url) = app.selected_note().and_then(|note| note.url.as_ref()) {
+                let _ = open::that(url);
+            }
+        }
+        Action::OpenFile => {
+            if let Some(note) = app.selected_note() {
+                return edit(note.file_path(config), app, config);
+            }
+        }
+        Action::ToggleRead => {
+            if let Some(note) = app.selected_note_mut()
+                && let Ok(operation) = vault::toggle_read_status(note, config) {
+                let label = format!("marking \"{}\" {}", note.title, if note.read { "read" } else { "unread" });
+                app.journal.record(label, vec![operation]);
+            }
+            app.refresh_visible();
+        }
+        Action::Archive => {
+            if let Some(note) = app.selected_note_mut()
+                && let Ok(operation) = vault::toggle_archived(note, config) {
+                let label = format!("{} \"{}\"", if note.archived { "archiving" } else { "unarchiving" }, note.title);
+                app.journal.record(label, vec![operation]);
+            }
+            app.refresh_visible();
+        }
+        Action::Undo => journal::undo(app, config),
+        Action::Redo => journal::redo(app, config),
+        Action::YankUrl | Action::YankTitle | Action::YankMarkdown | Action::YankWikiLink => return yank(action, app, config),
+        Action::Help => app.open_help(help_entries(config)),
+        Action::Custom(i) => return run_custom(&config.actions[i], app, config),
+        Action::HideRead => app.toggle_hide_read(),
+        Action::Search => app.open_prompt(PromptKind::Search(SearchKind::Fuzzy)),
+        Action::FullTextSearch => app.open_prompt(PromptKind::Search(SearchKind::FullText)),
+        Action::Filter => app.open_prompt(PromptKind::Filter),
+        Action::Sort => app.cycle_sort_key(),
+        Action::ReverseSort => app.reverse_sort(),
+        Action::GroupBy => app.cycle_group_by(),
+        Action::ToggleGroup => app.toggle_group(),
+        Action::Related => app.open_related(),
+        Action::Duplicates => app.open_duplicates(),
+        Action::Tags => app.toggle_tag_sidebar(),
+        Action::Surprise => app.surprise_me(&surprise(config), None),
+        Action::SurpriseBudget => app.open_prompt(PromptKind::SurpriseBudget),
+        Action::Command => app.open_prompt(PromptKind::Command),
+        Action::Mark => app.toggle_mark(),
+        Action::MarkPage => app.mark_page(),
+        Action::MarkAll => app.mark_all(),
+        Action::Bulk => app.open_bulk_menu(),
+        Action::Back if !app.marked.is_empty() => app.clear_marks(),
+        Action::Back if app.search.is_some() => app.clear_search(),
+        Action::Back if app.filter.is_none() => app.clear_tags(),
+        Action::Back => app.clear_filter(),
+    }
+    AppAction::Continue
+}
+
+/// The tag sidebar while it has the keyboard. Space checks a tag, Enter
+/// checks only that one and `a` switches between needing all or any of them.
+fn handle_tags_key(key: KeyEvent, app: &mut App) {
+    match key.code {
+        KeyCode::Char('t') | KeyCode::Char('q') => app.toggle_tag_sidebar(),
+        KeyCode::Esc => app.unfocus_tag_sidebar(),
+        KeyCode::Down | KeyCode::Char('j') => app.next_tag(),
+        KeyCode::Up | KeyCode::Char('k') => app.previous_tag(),
+        KeyCode::Char(' ') => app.toggle_tag_checked(),
+        KeyCode::Enter => app.only_tag(),
+        KeyCode::Char('a') => app.toggle_tag_match(),
+        KeyCode::Char('c') => app.clear_tags(),
+        _ => {}
+    }
+}
+
+/// Copies the selected note's URL, title or a link to it.
+fn yank(action: Action, app: &mut App, config: &Config) -> AppAction {
+    let Some(note) = app.selected_note() else {
+        return AppAction::Continue;
+    };
+    let (what, text) = match action {
+        Action::YankUrl => ("URL", note.url.clone()),
+        Action::YankTitle => ("title", Some(note.title.clone())),
+        Action::YankMarkdown => ("Markdown link", note.markdown_link()),
+        _ => {
+            // Obsidian links notes by file name
+            let path = note.file_path(config);
+            let name = path.file_stem().map_or_else(|| note.title.clone(), |stem| stem.to_string_lossy().to_string());
+            ("wiki-link", Some(format!("[[{}]]", name)))
+        }
+    };
+    match text {
+        Some(text) => AppAction::Yank { what, text },
+        None => {
+            app.status_message = Some(format!("\"{}\" has no URL", note.title));
+            AppAction::Continue
+        }
+    }
+}
+
+/// The help popup only scrolls.
+fn handle_help_key(key: KeyEvent, app: &mut App) {
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('?') | KeyCode::Char('q') => app.close_help(),
+        KeyCode::Down | KeyCode::Char('j') => app.scroll_help(1),
+        KeyCode::Up | KeyCode::Char('k') => app.scroll_help(-1),
+        KeyCode::PageDown | KeyCode::Char(' ') => app.scroll_help(HELP_PAGE),
+        KeyCode::PageUp => app.scroll_help(-HELP_PAGE),
+        _ => {}
+    }
+}
+
+/// Every action with its keys, custom ones last with their commands.
+fn help_entries(config: &Config) -> Vec<(String, String)> {
+    let builtin = Action::ALL.into_iter()
+        .map(|action| (action.keys(&config.keybindings).0.join(", "), action.name().replace('_', " ")));
+    let custom = config.actions.iter()
+        .map(|custom| (custom.key.0.join(", "), format!("{}: {}", custom.name, custom.command)));
+    builtin.chain(custom).collect()
+}
+
+/// Fills in a custom action's command from the selected note.
+fn run_custom(custom: &CustomAction, app: &mut App, config: &Config) -> AppAction {
+    let Some(note) = app.selected_note() else {
+        return AppAction::Continue;
+    };
+    if custom.command.contains("{url}") && note.url.is_none() {
+        app.status_message = Some(format!("\"{}\" has no URL for {}", note.title, custom.name));
+        return AppAction::Continue;
+    }
+    let path = note.file_path(config);
+    let values = [
+        ("url", note.url.clone().unwrap_or_default()),
+        ("path", path.to_string_lossy().to_string()),
+        ("title", note.title.clone()),
+        ("tags", note.tags.join(",")),
+    ];
+    let values: Vec<(&str, &str)> = values.iter().map(|(name, value)| (*name, value.as_str())).collect();
+    let Some(mut external) = External::from_template(&custom.command, &values) else {
+        app.status_message = Some(format!("{} has no command", custom.name));
+        return AppAction::Continue;
+    };
+    external.suspend = custom.suspend;
+    external.reload = custom.reload.then_some(path);
+    app.status_message = Some(format!("Ran {} on \"{}\"", custom.name, note.title));
+    AppAction::Run(external)
+}
+
+/// The bulk action menu, for the marked notes. Tags and the export file are asked for next.
+fn handle_bulk_menu_key(key: KeyEvent, app: &mut App) {
+    let action = match key.code {
+        KeyCode::Char('r') => BulkAction::MarkRead,
+        KeyCode::Char('u') => BulkAction::MarkUnread,
+        KeyCode::Char('a') => BulkAction::Archive,
+        KeyCode::Char('x') => BulkAction::Trash,
+        KeyCode::Char('+') | KeyCode::Char('-') | KeyCode::Char('e') => {
+            app.close_bulk_menu();
+            app.open_prompt(match key.code {
+                KeyCode::Char('+') => PromptKind::AddTag,
+                KeyCode::Char('-') => PromptKind::RemoveTag,
+                _ => PromptKind::Export,
+            });
+            return;
+        }
+        KeyCode::Esc | KeyCode::Char('b') | KeyCode::Char('q') => {
+            app.close_bulk_menu();
+            return;
+        }
+        _ => return,
+    };
+    let paths = app.marked_paths();
+    app.start_bulk(action, paths);
+}
+
+/// The related notes popup takes the keyboard while it is open.
+fn handle_related_key(key: KeyEvent, app: &mut App, config: &Config) -> AppAction {
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('R') | KeyCode::Char('q') => app.close_related(),
+        KeyCode::Down | KeyCode::Char('j') | KeyCode::Tab => app.next_related(),
+        KeyCode::Up | KeyCode::Char('k') | KeyCode::BackTab => app.previous_related(),
+        KeyCode::Enter if key.modifiers.contains(KeyModifiers::SHIFT) => {
+            if let Some(note) = app.selected_related() {
+                return edit(note.file_path(config), app, config);
+            }
+        }
+        KeyCode::Enter => app.jump_to_related(),
+        KeyCode::Char('o') => {
+            if let Some(url) = app.selected_related().and_then(|note| note.url.as_ref()) {
+                let _ = open::that(url);
+            }
+        }
+        _ => {}
+    }
+    AppAction::Continue
+}
+
+/// Opens a note in the editor, at the first line matching the search if there is one.
+fn edit(path: PathBuf, app: &mut App, config: &Config) -> AppAction {
+    let query = app.search.as_ref().map(|search| search.query.as_str());
+    let line = fs::read_to_string(&path).map_or(1, |content| external::match_line(&content, query));
+    match external::editor_command(config, &path, line) {
+        Some(editor) => AppAction::Run(editor),
+        None => {
+            app.status_message = Some("No editor set, add `editor` to the config or set $EDITOR".to_string());
+            AppAction::Continue
+        }
+    }
+}
+
+/// The duplicates popup. `m` keeps the selected note and `t` trashes it, both
+/// only move files into the vault's `.trash` folder.
+fn handle_duplicates_key(key: KeyEvent, app: &mut App, config: &Config) {
+    let Some((group, selected)) = app.duplicates.as_ref()
+        .and_then(|panel| panel.selected_note())
+        .map(|(group, selected)| (group.to_vec(), selected))
+    else {
+        app.close_duplicates();
+        return;
+    };
+
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('D') | KeyCode::Char('q') => app.close_duplicates(),
+        KeyCode::Down | KeyCode::Char('j') | KeyCode::Tab => app.next_duplicate(),
+        KeyCode::Up | KeyCode::Char('k') | KeyCode::BackTab => app.previous_duplicate(),
+        KeyCode::Enter => app.jump_to_duplicate(),
+        KeyCode::Char('o') => {
+            if let Some(url) = &app.notes[selected].url {
+                let _ = open::that(url);
+            }
+        }
+        KeyCode::Char('t') => {
+            let note = &app.notes[selected];
+            match vault::trash_note(note, config) {
+                Ok(to) => {
+                    app.status_message = Some(format!("Moved \"{}\" to .trash", note.title));
+                    let label = format!("trashing \"{}\"", note.title);
+                    let path = note.path.clone();
+                    app.journal.record(label, vec![Operation::Move { from: note.file_path(config), to }]);
+                    app.apply_vault_changes(vec![VaultChange::Removed(path)]);
+                }
+                Err(e) => app.status_message = Some(format!("Couldn't trash \"{}\": {}", note.title, e)),
+            }
+        }
+        KeyCode::Char('m') => {
+            let others: Vec<Note> = group.iter().filter(|&&i| i != selected).map(|&i| app.notes[i].clone()).collect();
+            let mut keeper = app.notes[selected].clone();
+            let mut operations = match vault::merge_tags(&mut keeper, &others.iter().collect::<Vec<_>>(), config) {
+                Ok(operation) => operation.into_iter().collect::<Vec<_>>(),
+                Err(e) => {
+                    app.status_message = Some(format!("Couldn't merge into \"{}\": {}", keeper.title, e));
+                    return;
+                }
+            };
+            let label = format!("merging duplicates into \"{}\"", keeper.title);
+            let mut changes = vec![VaultChange::Updated(Box::new(keeper))];
+            let mut failures = Vec::new();
+            for other in &others {
+                match vault::trash_note(other, config) {
+                    Ok(to) => {
+                        operations.push(Operation::Move { from: other.file_path(config), to });
+                        changes.push(VaultChange::Removed(other.path.clone()));
+                    }
+                    Err(e) => failures.push(format!("\"{}\" ({})", other.title, e)),
+                }
+            }
+            app.journal.record(label, operations);
+            app.status_message = Some(if failures.is_empty() {
+                format!("Merged {} duplicates, moved to .trash", others.len())
+            } else {
+                format!("Merged {} duplicates, {} couldn't be trashed: {}", others.len(), failures.len(), failures.join(", "))
+            });
+            app.apply_vault_changes(changes);
+        }
+        _ => {}
+    }
+}
+
+fn surprise(config: &Config) -> Surprise<'_> {
+    Surprise {
+        config: &config.surprise,
+        words_per_minute: config.card.words_per_minute,
+        today: Local::now().date_naive(),
+    }
+}
+
+/// Line editing for the status bar prompt. Search runs on every keystroke,
+/// commands get Tab completion and Up/Down history.
+fn handle_prompt_key(key: KeyEvent, app: &mut App, config: &Config) -> AppAction {
+    let Some(prompt) = app.prompt.as_mut() else {
+        return AppAction::Continue;
+    };
+    let kind = prompt.kind;
+    if !matches!(key.code, KeyCode::Tab | KeyCode::BackTab) {
+        prompt.completions.clear();
+    }
+
+    match key.code {
+        KeyCode::Esc => {
+            app.close_prompt();
+            match kind {
+                PromptKind::Search(_) => app.clear_search(),
+                PromptKind::Filter => app.clear_filter(),
+                _ => {}
+            }
+            return AppAction::Continue;
+        }
+        KeyCode::Enter => {
+            let input = prompt.input.trim().to_string();
+            app.close_prompt();
+            match kind {
+                PromptKind::SurpriseBudget => match input.parse() {
+                    Ok(minutes) => app.surprise_me(&surprise(config), Some(minutes)),
+                    Err(_) if input.is_empty() => app.surprise_me(&surprise(config), None),
+                    Err(_) => app.status_message = Some(format!("Not a number of minutes: {}", input)),
+                },
+                PromptKind::AddTag | PromptKind::RemoveTag => {
+                    // `#tag` and `[[tag]]` name the same tag
+                    let tag = input.trim_start_matches('#').trim_start_matches("[[").trim_end_matches("]]").trim().to_string();
+                    if !tag.is_empty() {
+                        let action = if kind == PromptKind::AddTag { BulkAction::AddTag(tag) } else { BulkAction::RemoveTag(tag) };
+                        let paths = app.marked_paths();
+                        app.start_bulk(action, paths);
+                    }
+                }
+                PromptKind::Export if !input.is_empty() => {
+                    // Relative to where readitnow was started, shown in full so it can be found
+                    let path = std::path::absolute(&input).unwrap_or_else(|_| input.into());
+                    if path.exists() {
+                        app.status_message = Some(format!("{} exists already, export to a new file", path.display()));
+                    } else {
+                        let paths = app.marked_paths();
+                        app.start_bulk(BulkAction::Export(path), paths);
+                    }
+                }
+                PromptKind::Command if !input.is_empty() => {
+                    if app.command_history.last() != Some(&input) {
+                        app.command_history.push(input.clone());
+                    }
+                    match Command::parse(&input) {
+                        Ok(command) => return commands::run(command, app, config),
+                        Err(error) => app.status_message = Some(error),
+                    }
                 }
+                _ => {}
+            }
+            return AppAction::Continue;
+        }
+        KeyCode::Tab | KeyCode::BackTab if kind == PromptKind::Command => {
+            // A single completion is taken, so the next Tab completes the next word
+            if prompt.completions.len() <= 1 {
+                prompt.completions = commands::complete(&prompt.input);
+                prompt.completion = 0;
+            } else if key.code == KeyCode::Tab {
+                prompt.completion = (prompt.completion + 1) % prompt.completions.len();
+            } else {
+                prompt.completion = (prompt.completion + prompt.completions.len() - 1) % prompt.completions.len();
             }
-            _ => {}
+            if let Some(completion) = prompt.completions.get(prompt.completion) {
+                prompt.input = completion.clone();
+            }
+            return AppAction::Continue;
+        }
+        KeyCode::Up | KeyCode::Down if kind == PromptKind::Command => {
+            let history = &app.command_history;
+            prompt.history_position = match (key.code, prompt.history_position) {
+                (KeyCode::Up, None) => history.len().checked_sub(1),
+                (KeyCode::Up, Some(position)) => Some(position.saturating_sub(1)),
+                (_, Some(position)) if position + 1 < history.len() => Some(position + 1),
+                _ => None,
+            };
+            prompt.input = prompt.history_position.map(|position| history[position].clone()).unwrap_or_default();
+            return AppAction::Continue;
         }
+        KeyCode::Backspace => {
+            prompt.input.pop();
+        }
+        KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => prompt.input.clear(),
+        KeyCode::Char('w') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            let trimmed = prompt.input.trim_end().len();
+            let word_start = prompt.input[..trimmed].rfind(' ').map_or(0, |i| i + 1);
+            prompt.input.truncate(word_start);
+        }
+        KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => prompt.input.push(c),
+        _ => return AppAction::Continue,
     }
 
+    let input = prompt.input.clone();
+    match kind {
+        PromptKind::Search(search_kind) => app.set_search_query(search_kind, &input),
+        PromptKind::Filter => app.set_filter_query(&input),
+        _ => {}
+    }
     AppAction::Continue
-} 
\ No newline at end of file
+}
@@ -0,0 +1,230 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Instant;
+
+/// A key with its modifiers, parsed from specs like `ctrl+d`, `shift+enter`,
+/// `alt+x`, `f5`, `space` or `G`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeySpec {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeySpec {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self::normalized(code, modifiers)
+    }
+
+    pub fn parse(spec: &str) -> Result<KeySpec, String> {
+        let spec = spec.trim();
+        // `+` is both the separator and a key: `+` and `ctrl++`
+        let (modifier_names, key) = if spec == "+" {
+            ("", "+")
+        } else if let Some(modifier_names) = spec.strip_suffix("++") {
+            (modifier_names, "+")
+        } else {
+            spec.rsplit_once('+').unwrap_or(("", spec))
+        };
+        if key.is_empty() {
+            return Err(format!("`{}` has no key", spec));
+        }
+
+        let mut modifiers = KeyModifiers::NONE;
+        for name in modifier_names.split('+').filter(|name| !name.is_empty()) {
+            modifiers |= match name.to_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "meta" | "option" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(format!("unknown modifier `{}` in `{}`", name, spec)),
+            };
+        }
+
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match key.to_lowercase().as_str() {
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "space" => KeyCode::Char(' '),
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" | "ins" => KeyCode::Insert,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" | "pgup" => KeyCode::PageUp,
+                "pagedown" | "pgdn" => KeyCode::PageDown,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                name => match name.strip_prefix('f').and_then(|number| number.parse::<u8>().ok()) {
+                    Some(number @ 1..=24) => KeyCode::F(number),
+                    _ => return Err(format!("unknown key `{}` in `{}`", key, spec)),
+                },
+            },
+        };
+        Ok(Self::normalized(code, modifiers))
+    }
+
+    /// Whether `event` is this key. Shift is folded into the character, so `G`
+    /// matches whether or not the terminal reports Shift with it.
+    pub fn matches(&self, event: &KeyEvent) -> bool {
+        *self == KeySpec::from(event)
+    }
+
+    /// Shifted characters are written as the character itself and Shift+Tab
+    /// as BackTab, the way terminals send them.
+    fn normalized(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let modifiers = modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        match code {
+            KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) => KeySpec {
+                code: KeyCode::Char(c.to_uppercase().next().unwrap_or(c)),
+                modifiers: modifiers - KeyModifiers::SHIFT,
+            },
+            KeyCode::Tab if modifiers.contains(KeyModifiers::SHIFT) => KeySpec {
+                code: KeyCode::BackTab,
+                modifiers: modifiers - KeyModifiers::SHIFT,
+            },
+            KeyCode::BackTab => KeySpec { code, modifiers: modifiers - KeyModifiers::SHIFT },
+            _ => KeySpec { code, modifiers },
+        }
+    }
+}
+
+impl From<&KeyEvent> for KeySpec {
+    fn from(event: &KeyEvent) -> Self {
+        Self::normalized(event.code, event.modifiers)
+    }
+}
+
+impl fmt::Display for KeySpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in [(KeyModifiers::CONTROL, "ctrl+"), (KeyModifiers::ALT, "alt+"), (KeyModifiers::SHIFT, "shift+")] {
+            if self.modifiers.contains(modifier) {
+                f.write_str(name)?;
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::Enter => f.write_str("enter"),
+            KeyCode::Esc => f.write_str("esc"),
+            KeyCode::Tab => f.write_str("tab"),
+            KeyCode::BackTab => f.write_str("shift+tab"),
+            KeyCode::Backspace => f.write_str("backspace"),
+            KeyCode::Delete => f.write_str("delete"),
+            KeyCode::Insert => f.write_str("insert"),
+            KeyCode::Home => f.write_str("home"),
+            KeyCode::End => f.write_str("end"),
+            KeyCode::PageUp => f.write_str("pageup"),
+            KeyCode::PageDown => f.write_str("pagedown"),
+            KeyCode::Up => f.write_str("up"),
+            KeyCode::Down => f.write_str("down"),
+            KeyCode::Left => f.write_str("left"),
+            KeyCode::Right => f.write_str("right"),
+            KeyCode::F(number) => write!(f, "f{}", number),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+/// Parses one key or a sequence of keys separated by spaces, like `g g` or
+/// `ctrl+w l`. A run of plain characters that isn't a key name, like `gg`, is
+/// a sequence too.
+pub fn parse_sequence(spec: &str) -> Result<Vec<KeySpec>, String> {
+    let mut keys = Vec::new();
+    for token in spec.split_whitespace() {
+        match KeySpec::parse(token) {
+            Ok(key) => keys.push(key),
+            Err(_) if !token.contains('+') && token.chars().count() > 1 => {
+                keys.extend(token.chars().map(|c| KeySpec::new(KeyCode::Char(c), KeyModifiers::NONE)));
+            }
+            Err(error) => return Err(error),
+        }
+    }
+    if keys.is_empty() {
+        return Err(format!("`{}` has no key", spec));
+    }
+    Ok(keys)
+}
+
+/// Writes plain characters next to each other, like `gg`, and puts spaces
+/// around anything else, like `ctrl+w l`.
+pub fn format_sequence(keys: &[KeySpec]) -> String {
+    let is_plain = |key: &KeySpec| matches!(key.code, KeyCode::Char(c) if c != ' ') && key.modifiers.is_empty();
+    let mut text = String::new();
+    for (i, key) in keys.iter().enumerate() {
+        if i > 0 && !(is_plain(key) && is_plain(&keys[i - 1])) {
+            text.push(' ');
+        }
+        text.push_str(&key.to_string());
+    }
+    text
+}
+
+/// A count and the first keys of a sequence, typed but not yet run.
+#[derive(Debug, Clone, Default)]
+pub struct PendingKeys {
+    pub count: Option<usize>,
+    pub keys: Vec<KeySpec>,
+    pub last_key: Option<Instant>, // When the sequence times out is counted from here
+    pub view_before_count: Option<usize>, // Left when a lone digit switched views
+}
+
+impl PendingKeys {
+    pub fn is_empty(&self) -> bool {
+        self.count.is_none() && self.keys.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        *self = PendingKeys::default();
+    }
+}
+
+impl fmt::Display for PendingKeys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(count) = self.count {
+            write!(f, "{}", count)?;
+        }
+        f.write_str(&format_sequence(&self.keys))
+    }
+}
+
+/// The keys bound to one action. Written in the config as a single spec or a list.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "OneOrMany", into = "OneOrMany")]
+pub struct KeyList(pub Vec<String>);
+
+impl KeyList {
+    pub fn new(specs: &[&str]) -> Self {
+        KeyList(specs.iter().map(|spec| spec.to_string()).collect())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl From<OneOrMany> for KeyList {
+    fn from(keys: OneOrMany) -> Self {
+        match keys {
+            OneOrMany::One(spec) => KeyList(vec![spec]),
+            OneOrMany::Many(specs) => KeyList(specs),
+        }
+    }
+}
+
+impl From<KeyList> for OneOrMany {
+    fn from(keys: KeyList) -> Self {
+        match <[String; 1]>::try_from(keys.0) {
+            Ok([spec]) => OneOrMany::One(spec),
+            Err(specs) => OneOrMany::Many(specs),
+        }
+    }
+}
@@ -1,9 +1,26 @@
 pub mod app;
+pub mod bulk;
+pub mod clipboard;
+pub mod journal;
 pub mod models;
 pub mod ui;
 pub mod vault;
 pub mod keybindings;
+pub mod keys;
+pub mod mouse;
 pub mod config;
 pub mod markdown_processor;
+pub mod theme;
+pub mod urls;
+pub mod images;
+pub mod search;
+pub mod cli;
+pub mod commands;
+pub mod sort;
+pub mod surprise;
+pub mod duplicates;
+pub mod external;
+pub mod group;
+pub mod tags;
 
 
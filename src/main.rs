@@ -1,50 +1,209 @@
-mod models;
-mod ui;
-mod app;
-mod vault;
-mod keybindings;
-mod config;
-mod markdown_processor;
-
-use std::{io, time::Duration};
-use crossterm::{event::{self, Event}, terminal::{self, EnterAlternateScreen, LeaveAlternateScreen}, ExecutableCommand};
+use std::{io, process::ExitStatus, time::{Duration, Instant}};
+use crossterm::{event::{self, DisableMouseCapture, EnableMouseCapture, Event}, terminal::{self, EnterAlternateScreen, LeaveAlternateScreen}, ExecutableCommand};
 use ratatui::prelude::*;
-use crate::config::Config;
-use crate::ui::Renderer;
-use crate::app::App;
+use readitnow::{bulk, cli, clipboard, images, keybindings, mouse, sort, vault};
+use readitnow::config::{Config, SavedView, ViewState};
+use readitnow::external::External;
+use readitnow::journal::Journal;
+use readitnow::theme::Theme;
+use readitnow::ui::Renderer;
+use readitnow::app::App;
+use readitnow::images::ImageCache;
+use readitnow::cli::Command;
+use readitnow::search::fulltext::FullTextIndex;
+use readitnow::search::query::Filter;
+use readitnow::surprise::Surprise;
+use chrono::Local;
+use readitnow::keybindings::{AppAction, KeyMap};
+use readitnow::vault::VaultWatcher;
+
+// How often the vault is checked for notes changed by other programs
+const VAULT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+// Time spent on a bulk action between redraws
+const BULK_STEP: Duration = Duration::from_millis(50);
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let mut terminal = setup_terminal()?;
+    match cli::parse_args(std::env::args().skip(1)) {
+        Ok(Command::Browse) => browse(),
+        Ok(Command::List { filter }) => list(&filter),
+        Ok(Command::Surprise { filter, max_minutes }) => surprise(filter.as_deref().unwrap_or(""), max_minutes),
+        Ok(Command::Help) => {
+            println!("{}", cli::USAGE);
+            Ok(())
+        }
+        Err(message) => {
+            eprintln!("readitnow: {}\n\n{}", message, cli::USAGE);
+            std::process::exit(2);
+        }
+    }
+}
 
+fn parse_filter_or_exit(query: &str) -> Filter {
+    Filter::parse(query).unwrap_or_else(|error| {
+        eprintln!("readitnow: invalid filter: {}", error);
+        std::process::exit(2);
+    })
+}
+
+fn list(query: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let filter = parse_filter_or_exit(query);
     let config = Config::load_or_default();
+    let notes = vault::scan_vault(&config)?;
+    cli::list_notes(&notes, &filter, &config, &mut io::stdout().lock())?;
+    Ok(())
+}
+
+fn surprise(query: &str, max_minutes: Option<usize>) -> Result<(), Box<dyn std::error::Error>> {
+    let filter = parse_filter_or_exit(query);
+    let config = Config::load_or_default();
+    let notes = vault::scan_vault(&config)?;
+    let candidates: Vec<usize> = (0..notes.len()).filter(|&i| filter.matches(&notes[i])).collect();
+    let surprise = Surprise {
+        config: &config.surprise,
+        words_per_minute: config.card.words_per_minute,
+        today: Local::now().date_naive(),
+    };
+    match surprise.pick(&notes, &candidates, max_minutes, sort::time_seed()) {
+        Some(i) => println!("{}", cli::list_line(&notes[i], &config)),
+        None => {
+            eprintln!("readitnow: nothing unread to pick");
+            std::process::exit(1);
+        }
+    }
+    Ok(())
+}
+
+fn browse() -> Result<(), Box<dyn std::error::Error>> {
+    let config = Config::load_or_default();
+    // The status bar clears on the first key, stderr stays readable after quitting
+    let (keymap, problems) = KeyMap::with_actions(&config.keybindings, &config.actions);
+    for problem in &problems {
+        eprintln!("readitnow: keybindings: {}", problem);
+    }
+    let (theme, theme_error) = match config.load_theme() {
+        Ok(theme) => (theme, None),
+        Err(e) => {
+            eprintln!("readitnow: theme '{}': {}, using dark", config.theme.name, e);
+            (Theme::dark().merge(&config.theme.overrides), Some(format!("Theme '{}': {}", config.theme.name, e)))
+        }
+    };
+
+    let mut terminal = setup_terminal()?;
+    if config.mouse {
+        io::stdout().execute(EnableMouseCapture)?;
+    }
+
+    // Must run before the event loop starts reading stdin
+    let picker = images::detect_picker(&config.images);
 
     let notes = vault::scan_vault(&config).expect("Failed to load notes from the vault");
 
     let mut app = App::new(notes);
+    app.browse_limit = config.max_notes;
+    app.image_cache = ImageCache::new(picker, &config.images);
+    app.hide_read = config.hide_read;
+    app.card = config.card.clone();
+    let mut view_state = ViewState::load(&config);
+    app.set_views(&view_state.apply(&config.views));
+    app.fulltext = FullTextIndex::load_or_build(&app.notes, &config);
+    app.journal = Journal::load(&config);
+    let mut watcher = VaultWatcher::new(&app.notes);
+    let mut last_poll = Instant::now();
+    let mut vault_changed = false;
+
+    let mut startup_problems: Vec<String> = theme_error.into_iter().collect();
+    if !problems.is_empty() {
+        startup_problems.push(format!("Keybindings: {}", problems.join("; ")));
+    }
+    if !startup_problems.is_empty() {
+        app.status_message = Some(startup_problems.join(" | "));
+    }
 
-    let mut renderer = Renderer::new();
+    let mut renderer = Renderer::with_config(&config, theme);
 
     // Application loop
     loop {
+        app.image_cache.receive();
+        if last_poll.elapsed() >= VAULT_POLL_INTERVAL {
+            let changes = watcher.poll(&config);
+            vault_changed |= !changes.is_empty();
+            app.apply_vault_changes(changes);
+            last_poll = Instant::now();
+        }
         terminal.draw(|frame| {
             renderer.render_app(&mut app, frame);
         })?;
 
-        if event::poll(Duration::from_millis(250))? {
-            if let Event::Key(key) = event::read()? {
-                if keybindings::handle_key_event(key, &mut app, &config)
-                    == keybindings::AppAction::Quit
-                {
-                    break;
+        let mut action = AppAction::Continue;
+        // Keep redrawing while a bulk action runs, to show its progress
+        let timeout = if app.bulk.is_some() { Duration::ZERO } else { Duration::from_millis(250) };
+        if event::poll(timeout)? {
+            action = match event::read()? {
+                Event::Key(key) => keybindings::handle_key_event(key, &mut app, &config, &keymap),
+                Event::Mouse(mouse_event) => mouse::handle_mouse_event(mouse_event, &mut app, &config, &renderer.hits, Instant::now()),
+                _ => AppAction::Continue,
+            };
+        }
+        if action == AppAction::Continue {
+            action = keybindings::handle_pending_timeout(&mut app, &config, &keymap, Instant::now());
+        }
+        match action {
+            AppAction::Quit => break,
+            AppAction::Reload => {
+                let changes = watcher.reload(&config);
+                vault_changed |= !changes.is_empty();
+                app.apply_vault_changes(changes);
+                last_poll = Instant::now();
+                app.status_message = Some(format!("Reloaded {} notes", app.notes.len()));
+            }
+            AppAction::Run(external) => {
+                let result = if external.suspend {
+                    run_suspended(&mut terminal, &external, config.mouse)
+                } else {
+                    external.run()
+                };
+                if let Some(message) = external.report(result) {
+                    app.status_message = Some(message);
+                }
+                if let Some(path) = &external.reload {
+                    app.apply_vault_changes(vec![vault::reload_note(path, &config)]);
+                    vault_changed = true;
                 }
             }
+            AppAction::Yank { what, text } => {
+                app.status_message = Some(match clipboard::copy(&text, &config) {
+                    Ok(()) => format!("Copied the {}: {}", what, text),
+                    Err(e) => format!("Couldn't copy the {}: {}", what, e),
+                });
+            }
+            AppAction::Continue => {}
+        }
+        bulk::step(&mut app, &config, BULK_STEP);
+        if sync_views(&mut view_state, &config, &app) {
+            let _ = view_state.save(&config);
         }
     }
 
+    if vault_changed {
+        // Only caches, they get rebuilt from the notes next time
+        let _ = app.fulltext.save(&config);
+        let _ = vault::save_index(&app.notes, &config);
+    }
+    let _ = app.journal.save(&config);
+
     restore_terminal()?;
     Ok(())
 }
 
+/// Records sort orders, groupings and layouts changed in the app, returns whether any changed.
+fn sync_views(view_state: &mut ViewState, config: &Config, app: &App) -> bool {
+    let mut changed = false;
+    for (view_config, view) in config.views.iter().zip(&app.views) {
+        changed |= view_state.update(view_config, SavedView { sort: view.sort, layout: view.layout, group_by: view.group_by });
+    }
+    changed
+}
+
 fn setup_terminal() -> Result<Terminal<impl Backend>, Box<dyn std::error::Error>> {
     let mut stdout = io::stdout();
     terminal::enable_raw_mode()?;
@@ -52,9 +211,30 @@ fn setup_terminal() -> Result<Terminal<impl Backend>, Box<dyn std::error::Error>
     Ok(Terminal::new(CrosstermBackend::new(stdout))?)
 }
 
+/// Gives the terminal to `external` until it exits, then takes it back and redraws.
+/// Every step is tried even when one fails, so the app keeps a usable terminal,
+/// and the first error is returned for the status bar.
+fn run_suspended(terminal: &mut Terminal<impl Backend>, external: &External, mouse: bool) -> io::Result<Option<ExitStatus>> {
+    let mut stdout = io::stdout();
+    let released: io::Result<()> = [
+        stdout.execute(DisableMouseCapture).map(drop),
+        stdout.execute(LeaveAlternateScreen).map(drop),
+        terminal::disable_raw_mode(),
+    ].into_iter().collect();
+    let result = released.and_then(|()| external.run());
+    let taken_back: io::Result<()> = [
+        terminal::enable_raw_mode(),
+        stdout.execute(EnterAlternateScreen).map(drop),
+        if mouse { stdout.execute(EnableMouseCapture).map(drop) } else { Ok(()) },
+        terminal.clear(),
+    ].into_iter().collect();
+    taken_back.and(result)
+}
+
 fn restore_terminal() -> Result<(), Box<dyn std::error::Error>> {
     let mut stdout = io::stdout();
     terminal::disable_raw_mode()?;
+    stdout.execute(DisableMouseCapture)?;
     stdout.execute(LeaveAlternateScreen)?;
     Ok(())
 }
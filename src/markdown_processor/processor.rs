@@ -8,6 +8,12 @@ pub struct MarkdownProcessor {
     obsidian_regex: ObsidianRegex,
 }
 
+impl Default for MarkdownProcessor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl MarkdownProcessor {
     pub fn new() -> Self {
         Self::with_config(MarkdownProcessorConfig::default())
@@ -1,10 +1,51 @@
-use crate::vault::download_and_cache_thumbnail;
+use crate::vault::thumbnail_cache_path;
 use crate::markdown_processor::{MarkdownProcessor, MarkdownProcessorConfig};
+use crate::urls;
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 use crate::config::Config;
 
+// Frontmatter keys holding the date a note was saved, in order of preference
+const SAVED_DATE_KEYS: [&str; 4] = ["saved", "created", "date", "added"];
+
+/// What a note was saved from, judged by its URL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoteKind {
+    Video,
+    Tweet,
+    Article,
+    Note, // No URL at all
+}
+
+impl NoteKind {
+    pub fn from_url(url: Option<&str>) -> Self {
+        let Some(domain) = url.and_then(urls::domain) else {
+            return NoteKind::Note;
+        };
+        let is = |site: &str| domain == site || domain.ends_with(&format!(".{}", site));
+        if is("youtube.com") || is("youtu.be") || is("vimeo.com") {
+            NoteKind::Video
+        } else if is("twitter.com") || is("x.com") || is("t.co") {
+            NoteKind::Tweet
+        } else {
+            NoteKind::Article
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "video" | "youtube" => Some(NoteKind::Video),
+            "tweet" | "twitter" => Some(NoteKind::Tweet),
+            "article" | "web" => Some(NoteKind::Article),
+            "note" => Some(NoteKind::Note),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Note {
     pub title: String,
@@ -12,7 +53,15 @@ pub struct Note {
     pub tags: Vec<String>,
     pub url: Option<String>,
     pub thumbnail: Option<String>,
+    pub thumbnail_url: Option<String>, // Downloaded into `thumbnail` the first time it is shown
     pub read: bool,
+    pub archived: bool,
+    pub starred: bool,
+    pub path: PathBuf,
+    pub modified: Option<SystemTime>,
+    pub created: Option<NaiveDate>,
+    pub word_count: usize,
+    pub rating: Option<u8>, // `rating` from the frontmatter
 }
 
 impl Clone for Note {
@@ -23,7 +72,15 @@ impl Clone for Note {
             tags: self.tags.clone(),
             url: self.url.clone(),
             thumbnail: self.thumbnail.clone(),
+            thumbnail_url: self.thumbnail_url.clone(),
             read: self.read,
+            archived: self.archived,
+            starred: self.starred,
+            path: self.path.clone(),
+            modified: self.modified,
+            created: self.created,
+            word_count: self.word_count,
+            rating: self.rating,
         }
     }
 }
@@ -36,17 +93,69 @@ impl Default for Note {
             tags: Vec::new(),
             url: None,
             thumbnail: None,
+            thumbnail_url: None,
             read: false,
+            archived: false,
+            starred: false,
+            path: PathBuf::new(),
+            modified: None,
+            created: None,
+            word_count: 0,
+            rating: None,
         }
     }
 }
 
 impl Note {
-    pub fn from_markdown(content: &str, filename: &str, excerpt_lines: usize, config: &Config) -> Self {
-        let mut note = Note::default();
+    /// Read and archived notes are the ones the "hide read" toggle removes.
+    pub fn is_done(&self) -> bool {
+        self.read || self.archived
+    }
+
+    /// The note's file, falling back to `<vault>/<title>.md` for notes not loaded from disk.
+    pub fn file_path(&self, config: &Config) -> PathBuf {
+        if self.path.as_os_str().is_empty() {
+            PathBuf::from(&config.vault_path).join(format!("{}.md", self.title))
+        } else {
+            self.path.clone()
+        }
+    }
+
+    /// `[title](url)`, with the brackets in the title escaped, or `None` without a URL.
+    pub fn markdown_link(&self) -> Option<String> {
+        let title = self.title.replace('[', "\\[").replace(']', "\\]");
+        self.url.as_ref().map(|url| format!("[{}]({})", title, url))
+    }
+
+    pub fn kind(&self) -> NoteKind {
+        NoteKind::from_url(self.url.as_deref())
+    }
+
+    pub fn canonical_url(&self) -> Option<String> {
+        self.url.as_deref().and_then(urls::canonical_url)
+    }
 
+    pub fn domain(&self) -> Option<String> {
+        self.url.as_deref().and_then(urls::domain)
+    }
+
+    /// Frontmatter date when present, otherwise the day the file was last modified.
+    pub fn saved_date(&self) -> Option<NaiveDate> {
+        self.created.or_else(|| {
+            self.modified.map(|modified| DateTime::<Local>::from(modified).date_naive())
+        })
+    }
+
+    pub fn reading_minutes(&self, words_per_minute: usize) -> usize {
+        self.word_count.div_ceil(words_per_minute.max(1)).max(1)
+    }
+
+    pub fn from_markdown(content: &str, filename: &str, excerpt_lines: usize, config: &Config) -> Self {
         // Title from filename
-        note.title = filename.replace(".md", "");
+        let mut note = Note {
+            title: filename.replace(".md", ""),
+            ..Note::default()
+        };
 
         // Excerpt - clean Obsidian syntax before creating excerpt
         let processor_config = MarkdownProcessorConfig {
@@ -71,6 +180,12 @@ impl Note {
             }
         }
 
+        note.word_count = processor.process(content).split_whitespace().count();
+        if let Some(frontmatter) = frontmatter(content) {
+            note.created = frontmatter_date(&frontmatter);
+            note.rating = frontmatter.get("rating").and_then(parse_rating);
+        }
+
         // Tags
         let tag_re = Regex::new(r"\[\[([^\]]+)\]\]").unwrap();
         note.tags = tag_re.captures_iter(content)
@@ -83,35 +198,73 @@ impl Note {
             note.url = Some(cap[1].to_string());
         }
 
-        // Thumbnail Detection
+        // Thumbnail Detection, the download itself happens lazily in the image loader
         if let Some(url) = &note.url {
-            let cache_dir = Path::new(&config.thumbnail_cache);
             if url.contains("twitter.com") || url.contains("t.co") {
                 // TODO: fix x thumbnails are not right
                 // For Twitter, we'll just use a placeholder for now, actual image fetching will be complex
-                if let Ok(path) = download_and_cache_thumbnail(url, cache_dir) {
-                    note.thumbnail = Some(path);
-                }
+                note.thumbnail_url = Some(url.clone());
             } else if url.contains("youtube.com") || url.contains("youtu.be") {
                 let youtube_re = Regex::new(r"(?:https?://)?(?:www\.)?(?:m\.)?(?:youtube\.com|youtu\.be)/(?:watch\?v=|embed/|v/|)([^\s&]+)").unwrap();
-                if let Some(cap) = youtube_re.captures(url) {
-                    if let Some(video_id) = cap.get(1) {
-                        let thumbnail_url = format!("https://img.youtube.com/vi/{}/mqdefault.jpg", video_id.as_str());
-                        if let Ok(path) = download_and_cache_thumbnail(&thumbnail_url, cache_dir) {
-                            note.thumbnail = Some(path);
-                        }
-                    }
+                if let Some(video_id) = youtube_re.captures(url).and_then(|cap| cap.get(1)) {
+                    note.thumbnail_url = Some(format!("https://img.youtube.com/vi/{}/mqdefault.jpg", video_id.as_str()));
                 }
             }
         }
+        if let Some(thumbnail_url) = &note.thumbnail_url {
+            let cache_dir = Path::new(&config.thumbnail_cache);
+            note.thumbnail = Some(thumbnail_cache_path(thumbnail_url, cache_dir).to_string_lossy().to_string());
+        }
 
         // Read status
         if content.contains("[[readitnow/read]]") {
             note.read = true;
         }
+        if content.contains("[[readitnow/archived]]") {
+            note.archived = true;
+        }
+        if content.contains("[[readitnow/starred]]") {
+            note.starred = true;
+        }
 
         note
     }
 }
 
+fn frontmatter(content: &str) -> Option<serde_yaml::Mapping> {
+    let frontmatter_re = Regex::new(r"(?s)\A---\r?\n(.*?)\r?\n---").unwrap();
+    let frontmatter = frontmatter_re.captures(content)?;
+    serde_yaml::from_str(&frontmatter[1]).ok()
+}
+
+fn frontmatter_date(values: &serde_yaml::Mapping) -> Option<NaiveDate> {
+    SAVED_DATE_KEYS.iter()
+        .filter_map(|key| values.get(*key))
+        .filter_map(|value| value.as_str())
+        .find_map(parse_date)
+}
+
+/// Accepts `4`, `4.5`, `"4/5"` or `"★★★★☆"`, anything above 5 is capped.
+fn parse_rating(value: &serde_yaml::Value) -> Option<u8> {
+    let rating = match value {
+        serde_yaml::Value::Number(number) => number.as_f64()?,
+        serde_yaml::Value::String(text) if text.contains('★') => text.chars().filter(|&c| c == '★').count() as f64,
+        serde_yaml::Value::String(text) => text.split('/').next()?.trim().parse().ok()?,
+        _ => return None,
+    };
+    (rating >= 0.0).then(|| rating.round().min(5.0) as u8)
+}
 
+pub fn parse_date(value: &str) -> Option<NaiveDate> {
+    let value = value.trim();
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Some(date);
+    }
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(value) {
+        return Some(datetime.date_naive());
+    }
+    ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .map(|datetime| datetime.date())
+}
@@ -0,0 +1,152 @@
+use crate::app::App;
+use crate::config::Config;
+use crate::keybindings::{self, Action, AppAction};
+use crossterm::event::{KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::{Position, Rect};
+use std::time::{Duration, Instant};
+
+// A second click on the same thing within this long is a double click
+const DOUBLE_CLICK: Duration = Duration::from_millis(500);
+
+/// What a part of the screen shows, for telling what was clicked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hit {
+    Row(usize), // Position on the current page
+    Tab(usize),
+    Sidebar,
+    Tag(usize), // Index into the tag tree
+    Popup,
+    Related(usize),
+    Duplicate(usize), // Position across all duplicate groups
+}
+
+/// The areas drawn in the last frame, recorded by the renderer.
+#[derive(Debug, Clone, Default)]
+pub struct HitMap {
+    areas: Vec<(Rect, Hit)>,
+}
+
+impl HitMap {
+    pub fn clear(&mut self) {
+        self.areas.clear();
+    }
+
+    pub fn push(&mut self, area: Rect, hit: Hit) {
+        self.areas.push((area, hit));
+    }
+
+    /// What is drawn at a cell. Popups are drawn last, so later areas cover earlier ones.
+    pub fn at(&self, column: u16, row: u16) -> Option<Hit> {
+        self.areas.iter().rev()
+            .find(|(area, _)| area.contains(Position::new(column, row)))
+            .map(|(_, hit)| *hit)
+    }
+}
+
+/// A click, kept to recognise the next one as a double click.
+#[derive(Debug, Clone, Copy)]
+pub struct Click {
+    pub hit: Hit,
+    pub at: Instant,
+}
+
+/// Clicks select cards, tags and popup rows and switch tabs, double clicks open
+/// what is selected, ctrl+clicks mark cards, and the wheel pages through cards
+/// or scrolls the list under it.
+pub fn handle_mouse_event(mouse: MouseEvent, app: &mut App, config: &Config, hits: &HitMap, now: Instant) -> AppAction {
+    // The prompt has the keyboard, and a click shouldn't lose what was typed
+    if app.prompt.is_some() {
+        return AppAction::Continue;
+    }
+    let hit = hits.at(mouse.column, mouse.row);
+    match mouse.kind {
+        MouseEventKind::Down(MouseButton::Left) => {
+            app.status_message = None;
+            app.pending_keys.clear();
+            let double = app.last_click.is_some_and(|click| Some(click.hit) == hit && now.duration_since(click.at) <= DOUBLE_CLICK);
+            // A third click starts over
+            app.last_click = match hit {
+                Some(hit) if !double => Some(Click { hit, at: now }),
+                _ => None,
+            };
+            let marking = mouse.modifiers.contains(KeyModifiers::CONTROL);
+            click(hit, double, marking, app, config)
+        }
+        MouseEventKind::ScrollDown => scroll(hit, app, config, true),
+        MouseEventKind::ScrollUp => scroll(hit, app, config, false),
+        _ => AppAction::Continue,
+    }
+}
+
+fn click(hit: Option<Hit>, double: bool, marking: bool, app: &mut App, config: &Config) -> AppAction {
+    // Popups close when clicked outside of
+    if app.related.is_some() || app.duplicates.is_some() || app.bulk_menu || app.help.is_some() {
+        match hit {
+            Some(Hit::Related(i)) => {
+                if let Some(panel) = &mut app.related {
+                    panel.selected = i;
+                }
+                if double {
+                    app.jump_to_related();
+                }
+            }
+            Some(Hit::Duplicate(i)) => {
+                if let Some(panel) = &mut app.duplicates {
+                    panel.selected = i;
+                }
+                if double {
+                    app.jump_to_duplicate();
+                }
+            }
+            Some(Hit::Popup) => {}
+            _ => {
+                app.close_related();
+                app.close_duplicates();
+                app.close_bulk_menu();
+                app.close_help();
+            }
+        }
+        return AppAction::Continue;
+    }
+
+    match hit {
+        Some(Hit::Row(position)) => {
+            app.unfocus_tag_sidebar();
+            app.selected_note_index = position;
+            if marking {
+                app.toggle_mark();
+            } else if double {
+                return keybindings::perform(Action::OpenLink, app, config, None);
+            }
+        }
+        Some(Hit::Tab(index)) => app.switch_view(index),
+        Some(Hit::Tag(index)) => {
+            app.tags.focused = true;
+            app.tags.selected = index;
+            if double {
+                app.only_tag();
+            } else {
+                app.toggle_tag_checked();
+            }
+        }
+        Some(Hit::Sidebar) => app.tags.focused = true,
+        Some(Hit::Popup | Hit::Related(_) | Hit::Duplicate(_)) | None => {}
+    }
+    AppAction::Continue
+}
+
+fn scroll(hit: Option<Hit>, app: &mut App, config: &Config, down: bool) -> AppAction {
+    if app.related.is_some() {
+        if down { app.next_related() } else { app.previous_related() }
+    } else if app.duplicates.is_some() {
+        if down { app.next_duplicate() } else { app.previous_duplicate() }
+    } else if app.help.is_some() {
+        app.scroll_help(if down { 1 } else { -1 });
+    } else if matches!(hit, Some(Hit::Sidebar | Hit::Tag(_))) {
+        if down { app.next_tag() } else { app.previous_tag() }
+    } else {
+        let action = if down { Action::PageDown } else { Action::PageUp };
+        return keybindings::perform(action, app, config, None);
+    }
+    AppAction::Continue
+}
@@ -0,0 +1,409 @@
+use crate::config::Config;
+use crate::markdown_processor::{MarkdownProcessor, MarkdownProcessorConfig};
+use crate::models::Note;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use std::{fs, io};
+
+// Bump when tokenizing or the stored layout changes
+const FULLTEXT_INDEX_VERSION: u32 = 1;
+
+// BM25 parameters, the usual defaults
+const BM25_K1: f64 = 1.2;
+const BM25_B: f64 = 0.75;
+
+const SNIPPET_CONTEXT_CHARS: usize = 80;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Document {
+    modified: Option<SystemTime>,
+    text: String,
+    #[serde(skip)]
+    length: usize,
+}
+
+/// What gets written to disk: the cleaned text of every note. Postings are
+/// rebuilt from it on load, which is far cheaper than cleaning the markdown.
+#[derive(Default, Serialize, Deserialize)]
+struct StoredIndex {
+    version: u32,
+    documents: HashMap<PathBuf, Document>,
+}
+
+/// Inverted index over the cleaned text of every note, keyed by note path.
+#[derive(Default)]
+pub struct FullTextIndex {
+    documents: HashMap<PathBuf, Document>,
+    // term -> document -> token positions, ordered so prefix queries are a range scan
+    postings: BTreeMap<String, HashMap<PathBuf, Vec<u32>>>,
+    total_length: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryClause {
+    Term(String),
+    Prefix(String),
+    Phrase(Vec<String>),
+}
+
+#[derive(Debug, Clone)]
+pub struct FullTextResult {
+    pub path: PathBuf,
+    pub score: f64,
+    pub snippet: String,
+    /// Char positions in `snippet` of the words and phrases that matched
+    pub highlights: Vec<usize>,
+}
+
+/// Lowercased alphanumeric runs with their byte ranges in `text`.
+pub fn tokenize(text: &str) -> Vec<(String, usize, usize)> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices() {
+        match (c.is_alphanumeric(), start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                tokens.push((text[s..i].to_lowercase(), s, i));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        tokens.push((text[s..].to_lowercase(), s, text.len()));
+    }
+    tokens
+}
+
+/// Parses `"exact phrases"`, `prefix*` and plain terms. Every clause has to match.
+pub fn parse_query(query: &str) -> Vec<QueryClause> {
+    let mut clauses = Vec::new();
+    for (i, part) in query.split('"').enumerate() {
+        // Odd parts sit between quotes
+        if i % 2 == 1 {
+            let terms: Vec<String> = tokenize(part).into_iter().map(|(term, _, _)| term).collect();
+            match terms.len() {
+                0 => {}
+                1 => clauses.push(QueryClause::Term(terms[0].clone())),
+                _ => clauses.push(QueryClause::Phrase(terms)),
+            }
+            continue;
+        }
+        for word in part.split_whitespace() {
+            let is_prefix = word.ends_with('*');
+            for (term, _, _) in tokenize(word) {
+                clauses.push(if is_prefix { QueryClause::Prefix(term) } else { QueryClause::Term(term) });
+            }
+        }
+    }
+    clauses
+}
+
+pub fn clean_text(markdown: &str) -> String {
+    let processor = MarkdownProcessor::with_config(MarkdownProcessorConfig {
+        preserve_formatting: false,
+        convert_headings: false,
+        include_link_text: true,
+        max_line_length: 0,
+    });
+    processor.process(markdown)
+}
+
+impl FullTextIndex {
+    fn path(config: &Config) -> PathBuf {
+        PathBuf::from(&config.index_cache).join("fulltext.yaml")
+    }
+
+    /// Loads the stored index and brings it up to date with `notes`, reading
+    /// only the files whose mtime changed since it was saved.
+    pub fn load_or_build(notes: &[Note], config: &Config) -> Self {
+        let stored = fs::read_to_string(Self::path(config)).ok()
+            .and_then(|index_str| serde_yaml::from_str::<StoredIndex>(&index_str).ok())
+            .filter(|stored| stored.version == FULLTEXT_INDEX_VERSION)
+            .unwrap_or_default();
+
+        let mut index = FullTextIndex::default();
+        let mut changed = stored.documents.len() != notes.len();
+        let mut stored_documents = stored.documents;
+        for note in notes {
+            match stored_documents.remove(&note.path).filter(|doc| doc.modified == note.modified) {
+                Some(document) => index.insert_text(note.path.clone(), note.modified, document.text),
+                None => {
+                    changed = true;
+                    index.update_note(note);
+                }
+            }
+        }
+
+        if changed {
+            // Only a cache, failing to write it means cleaning the notes again next time
+            let _ = index.save(config);
+        }
+        index
+    }
+
+    pub fn save(&self, config: &Config) -> io::Result<()> {
+        fs::create_dir_all(&config.index_cache)?;
+        let stored = StoredIndex {
+            version: FULLTEXT_INDEX_VERSION,
+            documents: self.documents.clone(),
+        };
+        let index_str = serde_yaml::to_string(&stored)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(Self::path(config), index_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+
+    /// Re-reads and re-indexes a note's file. Unreadable files are dropped from the index.
+    pub fn update_note(&mut self, note: &Note) {
+        match fs::read_to_string(&note.path) {
+            Ok(content) => self.insert_text(note.path.clone(), note.modified, clean_text(&content)),
+            Err(_) => self.remove(&note.path),
+        }
+    }
+
+    pub fn insert_text(&mut self, path: PathBuf, modified: Option<SystemTime>, text: String) {
+        self.remove(&path);
+
+        let tokens = tokenize(&text);
+        for (position, (term, _, _)) in tokens.iter().enumerate() {
+            self.postings.entry(term.clone())
+                .or_default()
+                .entry(path.clone())
+                .or_default()
+                .push(position as u32);
+        }
+        self.total_length += tokens.len();
+        self.documents.insert(path, Document { modified, text, length: tokens.len() });
+    }
+
+    pub fn remove(&mut self, path: &Path) {
+        let Some(document) = self.documents.remove(path) else {
+            return;
+        };
+        self.total_length -= document.length;
+
+        let terms: HashSet<String> = tokenize(&document.text).into_iter().map(|(term, _, _)| term).collect();
+        for term in terms {
+            if let Some(documents) = self.postings.get_mut(&term) {
+                documents.remove(path);
+                if documents.is_empty() {
+                    self.postings.remove(&term);
+                }
+            }
+        }
+    }
+
+    /// Documents matching every clause, best BM25 score first.
+    pub fn search(&self, query: &str) -> Vec<FullTextResult> {
+        let clauses = parse_query(query);
+        if clauses.is_empty() || self.documents.is_empty() {
+            return Vec::new();
+        }
+
+        // Per clause: document -> positions where the clause matched
+        let mut matches: Vec<HashMap<&Path, Vec<u32>>> = Vec::new();
+        for clause in &clauses {
+            let clause_matches = self.clause_matches(clause);
+            if clause_matches.is_empty() {
+                return Vec::new();
+            }
+            matches.push(clause_matches);
+        }
+
+        let candidates: Vec<&Path> = matches[0].keys()
+            .filter(|path| matches[1..].iter().all(|m| m.contains_key(*path)))
+            .copied()
+            .collect();
+
+        let document_count = self.documents.len() as f64;
+        let average_length = (self.total_length as f64 / document_count).max(1.0);
+        let mut results: Vec<FullTextResult> = candidates.into_iter()
+            .map(|path| {
+                let length = self.documents[path].length as f64;
+                let score: f64 = matches.iter()
+                    .map(|clause_matches| {
+                        let frequency = clause_matches[path].len() as f64;
+                        let document_frequency = clause_matches.len() as f64;
+                        let idf = ((document_count - document_frequency + 0.5) / (document_frequency + 0.5) + 1.0).ln();
+                        let norm = BM25_K1 * (1.0 - BM25_B + BM25_B * length / average_length);
+                        idf * frequency * (BM25_K1 + 1.0) / (frequency + norm)
+                    })
+                    .sum();
+                let first_match = matches.iter().filter_map(|m| m[path].first()).min().copied().unwrap_or(0);
+                // A phrase is highlighted as a whole, from its first word to its last
+                let matched: Vec<(u32, u32)> = clauses.iter().zip(&matches)
+                    .flat_map(|(clause, clause_matches)| {
+                        let length = clause.len();
+                        clause_matches[path].iter().map(move |&start| (start, start + length))
+                    })
+                    .collect();
+                let (snippet, highlights) = self.snippet(path, first_match, &matched);
+                FullTextResult { path: path.to_path_buf(), score, snippet, highlights }
+            })
+            .collect();
+
+        results.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.path.cmp(&b.path)));
+        results
+    }
+
+    /// Cosine similarity between the TF-IDF vector of `path` and every other
+    /// document sharing a term with it, most similar first.
+    pub fn similar(&self, path: &Path) -> Vec<(PathBuf, f64)> {
+        let Some(document) = self.documents.get(path) else {
+            return Vec::new();
+        };
+        let document_count = self.documents.len() as f64;
+        let weight = |frequency: usize, document_frequency: usize| {
+            let idf = ((1.0 + document_count) / (1.0 + document_frequency as f64)).ln() + 1.0;
+            (1.0 + (frequency as f64).ln()) * idf
+        };
+
+        let terms: HashSet<String> = tokenize(&document.text).into_iter().map(|(term, _, _)| term).collect();
+        let mut dots: HashMap<&Path, f64> = HashMap::new();
+        let mut own_norm = 0.0;
+        for term in &terms {
+            let Some(documents) = self.postings.get(term) else {
+                continue;
+            };
+            let own_weight = weight(documents[path].len(), documents.len());
+            own_norm += own_weight * own_weight;
+            for (other, positions) in documents {
+                if other != path {
+                    *dots.entry(other.as_path()).or_default() += own_weight * weight(positions.len(), documents.len());
+                }
+            }
+        }
+        if dots.is_empty() || own_norm == 0.0 {
+            return Vec::new();
+        }
+
+        // Norms of the candidates need every term they contain, not only the shared ones
+        let mut norms: HashMap<&Path, f64> = HashMap::new();
+        for documents in self.postings.values() {
+            for (other, positions) in documents {
+                if dots.contains_key(other.as_path()) {
+                    let other_weight = weight(positions.len(), documents.len());
+                    *norms.entry(other.as_path()).or_default() += other_weight * other_weight;
+                }
+            }
+        }
+
+        let mut similar: Vec<(PathBuf, f64)> = dots.into_iter()
+            .map(|(other, dot)| (other.to_path_buf(), dot / (own_norm.sqrt() * norms[other].sqrt())))
+            .collect();
+        similar.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        similar
+    }
+
+    fn clause_matches(&self, clause: &QueryClause) -> HashMap<&Path, Vec<u32>> {
+        let mut result: HashMap<&Path, Vec<u32>> = HashMap::new();
+        match clause {
+            QueryClause::Term(term) => {
+                for (path, positions) in self.postings.get(term).into_iter().flatten() {
+                    result.insert(path.as_path(), positions.clone());
+                }
+            }
+            QueryClause::Prefix(prefix) => {
+                let terms = self.postings.range(prefix.clone()..)
+                    .take_while(|(term, _)| term.starts_with(prefix.as_str()));
+                for (_, documents) in terms {
+                    for (path, positions) in documents {
+                        result.entry(path.as_path()).or_default().extend(positions);
+                    }
+                }
+                for positions in result.values_mut() {
+                    positions.sort_unstable();
+                }
+            }
+            QueryClause::Phrase(terms) => {
+                let Some(first) = self.postings.get(&terms[0]) else {
+                    return result;
+                };
+                for (path, starts) in first {
+                    let phrase_starts: Vec<u32> = starts.iter()
+                        .copied()
+                        .filter(|&start| {
+                            terms[1..].iter().enumerate().all(|(offset, term)| {
+                                self.postings.get(term)
+                                    .and_then(|documents| documents.get(path))
+                                    .is_some_and(|positions| positions.binary_search(&(start + offset as u32 + 1)).is_ok())
+                            })
+                        })
+                        .collect();
+                    if !phrase_starts.is_empty() {
+                        result.insert(path.as_path(), phrase_starts);
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    /// A window of text around token `position`, with the `matched` token
+    /// ranges that fit in it highlighted.
+    fn snippet(&self, path: &Path, position: u32, matched: &[(u32, u32)]) -> (String, Vec<usize>) {
+        let text = &self.documents[path].text;
+        let tokens = tokenize(text);
+        let Some(&(_, token_start, _)) = tokens.get(position as usize) else {
+            return (String::new(), Vec::new());
+        };
+
+        let start = floor_char_boundary(text, token_start.saturating_sub(SNIPPET_CONTEXT_CHARS / 2));
+        // Don't start the snippet in the middle of a word
+        let start = match text[start..token_start].find(char::is_whitespace) {
+            Some(i) if start > 0 => start + i + 1,
+            _ => start,
+        };
+        let end = floor_char_boundary(text, (token_start + SNIPPET_CONTEXT_CHARS * 2).min(text.len()));
+
+        let prefix = if start > 0 { "…" } else { "" };
+        let suffix = if end < text.len() { "…" } else { "" };
+        let body = text[start..end].replace('\n', " ");
+        let snippet = format!("{}{}{}", prefix, body, suffix);
+
+        let offset = prefix.chars().count();
+        let mut highlights = Vec::new();
+        for &(first, last) in matched {
+            let (Some(&(_, match_start, _)), Some(&(_, _, match_end))) = (tokens.get(first as usize), tokens.get(last as usize - 1)) else {
+                continue;
+            };
+            if match_start < start || match_end > end {
+                continue;
+            }
+            // Line breaks became spaces, one char for one char
+            let char_start = offset + text[start..match_start].chars().count();
+            let char_length = text[match_start..match_end].chars().count();
+            highlights.extend(char_start..char_start + char_length);
+        }
+        highlights.sort_unstable();
+        highlights.dedup();
+        (snippet, highlights)
+    }
+}
+
+impl QueryClause {
+    /// Tokens a match of the clause spans.
+    fn len(&self) -> u32 {
+        match self {
+            QueryClause::Phrase(terms) => terms.len() as u32,
+            QueryClause::Term(_) | QueryClause::Prefix(_) => 1,
+        }
+    }
+}
+
+fn floor_char_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
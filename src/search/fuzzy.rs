@@ -0,0 +1,92 @@
+// Scoring in the spirit of fzf/fzy: every matched character scores, runs of
+// consecutive characters and matches at word starts score extra, and the
+// characters skipped inside the match cost a little.
+const SCORE_MATCH: i64 = 16;
+const BONUS_CONSECUTIVE: i64 = 12;
+const BONUS_WORD_START: i64 = 10;
+const BONUS_TEXT_START: i64 = 8;
+const PENALTY_GAP: i64 = 1;
+const MAX_GAP_PENALTY: i64 = 24;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    pub score: i64,
+    /// Char indices into the searched text
+    pub positions: Vec<usize>,
+}
+
+/// Matches `pattern` as a subsequence of `text`. Case-insensitive unless the
+/// pattern contains an uppercase letter.
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<FuzzyMatch> {
+    let case_sensitive = pattern.chars().any(char::is_uppercase);
+    let normalize = |c: char| if case_sensitive { c } else { c.to_lowercase().next().unwrap_or(c) };
+    let pattern: Vec<char> = pattern.chars().map(normalize).collect();
+    let text: Vec<char> = text.chars().collect();
+    if pattern.is_empty() {
+        return Some(FuzzyMatch { score: 0, positions: Vec::new() });
+    }
+
+    // Forward pass finds where the first complete match ends
+    let mut pattern_index = 0;
+    let mut end = None;
+    for (i, &c) in text.iter().enumerate() {
+        if normalize(c) == pattern[pattern_index] {
+            pattern_index += 1;
+            if pattern_index == pattern.len() {
+                end = Some(i);
+                break;
+            }
+        }
+    }
+    let end = end?;
+
+    // Backward pass from there finds the shortest window ending at `end`
+    let mut positions = Vec::with_capacity(pattern.len());
+    let mut pattern_index = pattern.len();
+    for i in (0..=end).rev() {
+        if normalize(text[i]) == pattern[pattern_index - 1] {
+            positions.push(i);
+            pattern_index -= 1;
+            if pattern_index == 0 {
+                break;
+            }
+        }
+    }
+    positions.reverse();
+
+    let score = score_positions(&text, &positions);
+    Some(FuzzyMatch { score, positions })
+}
+
+fn score_positions(text: &[char], positions: &[usize]) -> i64 {
+    let mut score = 0;
+    let mut previous: Option<usize> = None;
+
+    for &position in positions {
+        score += SCORE_MATCH;
+        if position == 0 {
+            score += BONUS_TEXT_START;
+        }
+        if is_word_start(text, position) {
+            score += BONUS_WORD_START;
+        }
+        if let Some(previous) = previous {
+            if position == previous + 1 {
+                score += BONUS_CONSECUTIVE;
+            } else {
+                score -= ((position - previous - 1) as i64 * PENALTY_GAP).min(MAX_GAP_PENALTY);
+            }
+        }
+        previous = Some(position);
+    }
+    score
+}
+
+fn is_word_start(text: &[char], position: usize) -> bool {
+    if position == 0 {
+        return true;
+    }
+    let previous = text[position - 1];
+    let current = text[position];
+    !previous.is_alphanumeric() || (previous.is_lowercase() && current.is_uppercase())
+}
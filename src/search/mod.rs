@@ -0,0 +1,90 @@
+pub mod fuzzy;
+pub mod fulltext;
+pub mod query;
+pub mod related;
+
+use crate::models::Note;
+use fuzzy::fuzzy_match;
+
+// How much a match in each field counts towards a note's rank
+const TITLE_WEIGHT: i64 = 3;
+const TAG_WEIGHT: i64 = 2;
+const URL_WEIGHT: i64 = 1;
+const EXCERPT_WEIGHT: i64 = 1;
+
+/// Char positions to highlight in a card, relative to the note's title and excerpt.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NoteHighlights {
+    pub title: Vec<usize>,
+    pub excerpt: Vec<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    pub note_index: usize,
+    pub score: i64,
+    pub highlights: NoteHighlights,
+}
+
+enum Field {
+    Title,
+    Excerpt,
+    Other,
+}
+
+/// Ranks `notes` against the whitespace separated terms of `query`. A note
+/// matches when every term fuzzy-matches its title, excerpt, a tag or its URL.
+pub fn fuzzy_search(notes: &[Note], query: &str) -> Vec<SearchResult> {
+    let terms: Vec<&str> = query.split_whitespace().collect();
+    if terms.is_empty() {
+        return Vec::new();
+    }
+
+    let mut results: Vec<SearchResult> = notes.iter()
+        .enumerate()
+        .filter_map(|(note_index, note)| {
+            let mut score = 0;
+            let mut highlights = NoteHighlights::default();
+            for term in &terms {
+                let (term_score, field, positions) = best_field_match(note, term)?;
+                score += term_score;
+                match field {
+                    Field::Title => highlights.title.extend(positions),
+                    Field::Excerpt => highlights.excerpt.extend(positions),
+                    Field::Other => {}
+                }
+            }
+            highlights.title.sort_unstable();
+            highlights.title.dedup();
+            highlights.excerpt.sort_unstable();
+            highlights.excerpt.dedup();
+            Some(SearchResult { note_index, score, highlights })
+        })
+        .collect();
+
+    // Stable, so equally ranked notes keep the vault order
+    results.sort_by_key(|result| std::cmp::Reverse(result.score));
+    results
+}
+
+fn best_field_match(note: &Note, term: &str) -> Option<(i64, Field, Vec<usize>)> {
+    let mut best: Option<(i64, Field, Vec<usize>)> = None;
+    let mut consider = |text: &str, weight: i64, field: Field| {
+        if let Some(found) = fuzzy_match(term, text) {
+            let score = found.score * weight;
+            if best.as_ref().is_none_or(|(best_score, _, _)| score > *best_score) {
+                best = Some((score, field, found.positions));
+            }
+        }
+    };
+
+    consider(&note.title, TITLE_WEIGHT, Field::Title);
+    for tag in &note.tags {
+        consider(tag, TAG_WEIGHT, Field::Other);
+    }
+    if let Some(url) = &note.url {
+        consider(url, URL_WEIGHT, Field::Other);
+    }
+    consider(&note.excerpt, EXCERPT_WEIGHT, Field::Excerpt);
+    best
+}
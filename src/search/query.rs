@@ -0,0 +1,323 @@
+use crate::models::{parse_date, Note, NoteKind};
+use chrono::{Days, Local, Months, NaiveDate};
+use std::fmt;
+
+/// A parsed filter query, e.g. `kind:video is:unread (tag:infra OR tag:k8s) after:30d`.
+///
+/// Terms next to each other must all match, `OR` and `NOT` (or a leading `-`)
+/// combine them and parentheses group them. Anything that isn't a known
+/// `field:value` is free text matched against the title, excerpt, tags and URL.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    All,
+    And(Vec<Filter>),
+    Or(Vec<Filter>),
+    Not(Box<Filter>),
+    Tag(String),
+    Kind(NoteKind),
+    Is(NoteState),
+    Domain(String),
+    Before(NaiveDate),
+    After(NaiveDate),
+    Words(Comparison, usize),
+    Text(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoteState {
+    Read,
+    Unread,
+    Starred,
+    Archived,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryError {
+    pub message: String,
+    pub position: usize, // Char offset in the query
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at column {}", self.message, self.position + 1)
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    Word(String),
+}
+
+impl Filter {
+    pub fn parse(query: &str) -> Result<Filter, QueryError> {
+        Self::parse_at(query, Local::now().date_naive())
+    }
+
+    /// Parses with relative dates like `after:30d` counted back from `today`.
+    pub fn parse_at(query: &str, today: NaiveDate) -> Result<Filter, QueryError> {
+        let tokens = lex(query)?;
+        let mut parser = Parser { tokens, next: 0, today, end: query.chars().count() };
+        if parser.peek().is_none() {
+            return Ok(Filter::All);
+        }
+        let filter = parser.parse_or()?;
+        match parser.tokens.get(parser.next) {
+            Some((_, position)) => Err(QueryError { message: "unmatched `)`".to_string(), position: *position }),
+            None => Ok(filter),
+        }
+    }
+
+    pub fn matches(&self, note: &Note) -> bool {
+        match self {
+            Filter::All => true,
+            Filter::And(filters) => filters.iter().all(|filter| filter.matches(note)),
+            Filter::Or(filters) => filters.iter().any(|filter| filter.matches(note)),
+            Filter::Not(filter) => !filter.matches(note),
+            Filter::Tag(tag) => note.tags.iter().any(|note_tag| {
+                // `tag:infra` also matches nested tags like `infra/k8s`
+                let note_tag = note_tag.to_lowercase();
+                note_tag == *tag || note_tag.strip_prefix(tag.as_str()).is_some_and(|rest| rest.starts_with('/'))
+            }),
+            Filter::Kind(kind) => note.kind() == *kind,
+            Filter::Is(state) => match state {
+                NoteState::Read => note.read,
+                NoteState::Unread => !note.is_done(),
+                NoteState::Starred => note.starred,
+                NoteState::Archived => note.archived,
+            },
+            Filter::Domain(domain) => note.domain().is_some_and(|note_domain| {
+                note_domain == *domain || note_domain.ends_with(&format!(".{}", domain))
+            }),
+            Filter::Before(date) => note.saved_date().is_some_and(|saved| saved < *date),
+            Filter::After(date) => note.saved_date().is_some_and(|saved| saved > *date),
+            Filter::Words(comparison, count) => match comparison {
+                Comparison::Less => note.word_count < *count,
+                Comparison::LessOrEqual => note.word_count <= *count,
+                Comparison::Greater => note.word_count > *count,
+                Comparison::GreaterOrEqual => note.word_count >= *count,
+            },
+            Filter::Text(text) => {
+                note.title.to_lowercase().contains(text)
+                    || note.excerpt.to_lowercase().contains(text)
+                    || note.url.as_deref().is_some_and(|url| url.to_lowercase().contains(text))
+                    || note.tags.iter().any(|tag| tag.to_lowercase().contains(text))
+            }
+        }
+    }
+}
+
+fn lex(query: &str) -> Result<Vec<(Token, usize)>, QueryError> {
+    let chars: Vec<char> = query.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let start = i;
+        match chars[i] {
+            c if c.is_whitespace() => i += 1,
+            '(' => {
+                tokens.push((Token::Open, start));
+                i += 1;
+            }
+            ')' => {
+                tokens.push((Token::Close, start));
+                i += 1;
+            }
+            '-' if chars.get(i + 1).is_some_and(|c| !c.is_whitespace()) => {
+                tokens.push((Token::Not, start));
+                i += 1;
+            }
+            _ => {
+                let mut word = String::new();
+                let mut quoted = false;
+                while i < chars.len() && !chars[i].is_whitespace() && chars[i] != '(' && chars[i] != ')' {
+                    if chars[i] == '"' {
+                        // Quotes keep spaces and parentheses, e.g. `tag:"to read"` or `"rust async"`
+                        quoted = true;
+                        let close = chars[i + 1..].iter().position(|&c| c == '"').ok_or(QueryError {
+                            message: "unclosed `\"`".to_string(),
+                            position: i,
+                        })?;
+                        word.extend(&chars[i + 1..i + 1 + close]);
+                        i += close + 2;
+                    } else {
+                        word.push(chars[i]);
+                        i += 1;
+                    }
+                }
+                let token = match word.as_str() {
+                    "AND" if !quoted => Token::And,
+                    "OR" if !quoted => Token::Or,
+                    "NOT" if !quoted => Token::Not,
+                    _ => Token::Word(word),
+                };
+                tokens.push((token, start));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    next: usize,
+    today: NaiveDate,
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next).map(|(token, _)| token)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens.get(self.next).map_or(self.end, |(_, position)| *position)
+    }
+
+    fn error<T>(&self, message: &str) -> Result<T, QueryError> {
+        Err(QueryError { message: message.to_string(), position: self.position() })
+    }
+
+    fn parse_or(&mut self) -> Result<Filter, QueryError> {
+        let mut filters = vec![self.parse_and()?];
+        while self.peek() == Some(&Token::Or) {
+            self.next += 1;
+            filters.push(self.parse_and()?);
+        }
+        Ok(if filters.len() == 1 { filters.remove(0) } else { Filter::Or(filters) })
+    }
+
+    fn parse_and(&mut self) -> Result<Filter, QueryError> {
+        let mut filters = vec![self.parse_unary()?];
+        loop {
+            match self.peek() {
+                None | Some(Token::Or) | Some(Token::Close) => break,
+                Some(Token::And) => {
+                    self.next += 1;
+                    filters.push(self.parse_unary()?);
+                }
+                Some(_) => filters.push(self.parse_unary()?),
+            }
+        }
+        Ok(if filters.len() == 1 { filters.remove(0) } else { Filter::And(filters) })
+    }
+
+    fn parse_unary(&mut self) -> Result<Filter, QueryError> {
+        let position = self.position();
+        match self.peek().cloned() {
+            Some(Token::Not) => {
+                self.next += 1;
+                Ok(Filter::Not(Box::new(self.parse_unary()?)))
+            }
+            Some(Token::Open) => {
+                self.next += 1;
+                if self.peek() == Some(&Token::Close) {
+                    return self.error("empty `()`");
+                }
+                let filter = self.parse_or()?;
+                if self.peek() != Some(&Token::Close) {
+                    return self.error("expected `)`");
+                }
+                self.next += 1;
+                Ok(filter)
+            }
+            Some(Token::Word(word)) => {
+                self.next += 1;
+                self.parse_term(&word, position)
+            }
+            Some(Token::Close) => self.error("unexpected `)`"),
+            Some(Token::And) | Some(Token::Or) => self.error("expected a term"),
+            None => self.error("query ends early"),
+        }
+    }
+
+    fn parse_term(&self, word: &str, position: usize) -> Result<Filter, QueryError> {
+        let error = |message: String| Err(QueryError { message, position });
+
+        if let Some(rest) = word.strip_prefix("words") {
+            let (comparison, count) = if let Some(count) = rest.strip_prefix(">=") {
+                (Comparison::GreaterOrEqual, count)
+            } else if let Some(count) = rest.strip_prefix("<=") {
+                (Comparison::LessOrEqual, count)
+            } else if let Some(count) = rest.strip_prefix('>') {
+                (Comparison::Greater, count)
+            } else if let Some(count) = rest.strip_prefix('<') {
+                (Comparison::Less, count)
+            } else {
+                return Ok(Filter::Text(word.to_lowercase()));
+            };
+            return match count.parse() {
+                Ok(count) => Ok(Filter::Words(comparison, count)),
+                Err(_) => error(format!("`{}` is not a word count", count)),
+            };
+        }
+
+        let Some((field, value)) = word.split_once(':') else {
+            return Ok(Filter::Text(word.to_lowercase()));
+        };
+        if value.is_empty() && ["tag", "kind", "is", "domain", "before", "after"].contains(&field) {
+            return error(format!("`{}:` needs a value", field));
+        }
+        match field {
+            "tag" => Ok(Filter::Tag(value.trim_start_matches('#').to_lowercase())),
+            "kind" => match NoteKind::parse(value) {
+                Some(kind) => Ok(Filter::Kind(kind)),
+                None => error(format!("unknown kind `{}`, expected video, tweet, article or note", value)),
+            },
+            "is" => match value.to_lowercase().as_str() {
+                "read" => Ok(Filter::Is(NoteState::Read)),
+                "unread" => Ok(Filter::Is(NoteState::Unread)),
+                "starred" => Ok(Filter::Is(NoteState::Starred)),
+                "archived" => Ok(Filter::Is(NoteState::Archived)),
+                _ => error(format!("unknown state `{}`, expected read, unread, starred or archived", value)),
+            },
+            "domain" => Ok(Filter::Domain(value.to_lowercase().trim_start_matches("www.").to_string())),
+            "before" | "after" => match self.parse_date(value) {
+                Some(date) if field == "before" => Ok(Filter::Before(date)),
+                Some(date) => Ok(Filter::After(date)),
+                None => error(format!("`{}` is not a date, use YYYY-MM-DD or a span like 30d", value)),
+            },
+            // Not a field, e.g. the scheme of a URL
+            _ => Ok(Filter::Text(word.to_lowercase())),
+        }
+    }
+
+    /// `YYYY-MM-DD`, `YYYY-MM`, `today`, `yesterday` or a span back from today: `3d`, `2w`, `6m`, `1y`.
+    fn parse_date(&self, value: &str) -> Option<NaiveDate> {
+        match value {
+            "today" => return Some(self.today),
+            "yesterday" => return self.today.pred_opt(),
+            _ => {}
+        }
+        if let Some(date) = parse_date(value) {
+            return Some(date);
+        }
+        if let Ok(date) = NaiveDate::parse_from_str(&format!("{}-01", value), "%Y-%m-%d") {
+            return Some(date);
+        }
+
+        let unit = value.chars().last()?;
+        let count: u32 = value[..value.len() - unit.len_utf8()].parse().ok()?;
+        match unit {
+            'd' => self.today.checked_sub_days(Days::new(count.into())),
+            'w' => self.today.checked_sub_days(Days::new(u64::from(count) * 7)),
+            'm' => self.today.checked_sub_months(Months::new(count)),
+            'y' => self.today.checked_sub_months(Months::new(count.checked_mul(12)?)),
+            _ => None,
+        }
+    }
+}
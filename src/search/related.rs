@@ -0,0 +1,70 @@
+use crate::config::CardConfig;
+use crate::models::Note;
+use crate::search::fulltext::FullTextIndex;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+// How much each signal counts towards relatedness, text similarity is in [0, 1]
+const TEXT_WEIGHT: f64 = 1.0;
+const TAG_WEIGHT: f64 = 0.5;
+const DOMAIN_WEIGHT: f64 = 0.2;
+
+pub const RELATED_LIMIT: usize = 5;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RelatedNote {
+    pub note_index: usize,
+    pub score: f64,
+}
+
+impl RelatedNote {
+    /// The score as a share of the highest possible one.
+    pub fn percent(&self) -> u32 {
+        (self.score / (TEXT_WEIGHT + TAG_WEIGHT + DOMAIN_WEIGHT) * 100.0).round() as u32
+    }
+}
+
+/// The notes most related to `notes[note_index]`: text similarity from the
+/// full-text index, plus shared tags and the same domain.
+pub fn related_notes(notes: &[Note], note_index: usize, fulltext: &FullTextIndex, card: &CardConfig, limit: usize) -> Vec<RelatedNote> {
+    let note = &notes[note_index];
+    let mut scores: HashMap<usize, f64> = HashMap::new();
+
+    let indexes: HashMap<&PathBuf, usize> = notes.iter().enumerate().map(|(i, note)| (&note.path, i)).collect();
+    for (path, similarity) in fulltext.similar(&note.path) {
+        if let Some(&i) = indexes.get(&path) {
+            *scores.entry(i).or_default() += TEXT_WEIGHT * similarity;
+        }
+    }
+
+    // Noise tags like ReadItLater would make every note related to every other
+    let tags = |note: &Note| -> HashSet<String> {
+        note.tags.iter().filter(|tag| card.is_tag_shown(tag)).map(|tag| tag.to_lowercase()).collect()
+    };
+    let own_tags = tags(note);
+    let own_domain = note.domain();
+    for (i, other) in notes.iter().enumerate() {
+        if i == note_index {
+            continue;
+        }
+        if !own_tags.is_empty() {
+            let other_tags = tags(other);
+            let shared = own_tags.intersection(&other_tags).count();
+            if shared > 0 {
+                let jaccard = shared as f64 / own_tags.union(&other_tags).count() as f64;
+                *scores.entry(i).or_default() += TAG_WEIGHT * jaccard;
+            }
+        }
+        if own_domain.is_some() && other.domain() == own_domain {
+            *scores.entry(i).or_default() += DOMAIN_WEIGHT;
+        }
+    }
+
+    let mut related: Vec<RelatedNote> = scores.into_iter()
+        .filter(|(i, _)| *i != note_index)
+        .map(|(note_index, score)| RelatedNote { note_index, score })
+        .collect();
+    related.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.note_index.cmp(&b.note_index)));
+    related.truncate(limit);
+    related
+}
@@ -0,0 +1,174 @@
+use crate::models::Note;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortKey {
+    /// File modification time
+    #[default]
+    Modified,
+    /// Date saved in the frontmatter, falling back to the modification date
+    Saved,
+    Title,
+    Domain,
+    ReadingTime,
+    /// `rating` in the frontmatter
+    Rating,
+    /// Shuffled by `seed`, the same seed always gives the same order
+    Random,
+}
+
+impl SortKey {
+    pub const ALL: [SortKey; 7] = [
+        SortKey::Modified,
+        SortKey::Saved,
+        SortKey::Title,
+        SortKey::Domain,
+        SortKey::ReadingTime,
+        SortKey::Rating,
+        SortKey::Random,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SortKey::Modified => "modified",
+            SortKey::Saved => "saved",
+            SortKey::Title => "title",
+            SortKey::Domain => "domain",
+            SortKey::ReadingTime => "reading time",
+            SortKey::Rating => "rating",
+            SortKey::Random => "random",
+        }
+    }
+
+    /// Reads a key as written in the config or shown in the status bar, `date` means saved.
+    pub fn parse(name: &str) -> Option<SortKey> {
+        match name.to_lowercase().replace(['_', '-'], " ").as_str() {
+            "date" => Some(SortKey::Saved),
+            "time" => Some(SortKey::ReadingTime),
+            name => SortKey::ALL.into_iter().find(|key| key.label() == name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortDirection {
+    Asc,
+    #[default]
+    Desc,
+}
+
+impl SortDirection {
+    pub fn parse(name: &str) -> Option<SortDirection> {
+        match name.to_lowercase().as_str() {
+            "asc" | "ascending" => Some(SortDirection::Asc),
+            "desc" | "descending" => Some(SortDirection::Desc),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Sort {
+    pub by: SortKey,
+    pub order: SortDirection,
+    #[serde(skip_serializing_if = "is_zero")]
+    pub seed: u64,
+}
+
+fn is_zero(seed: &u64) -> bool {
+    *seed == 0
+}
+
+impl Sort {
+    pub fn new(by: SortKey, order: SortDirection) -> Self {
+        Sort { by, order, seed: 0 }
+    }
+
+    /// The next key in `SortKey::ALL`, with a fresh seed when it comes to `Random`.
+    pub fn next_key(&self) -> Sort {
+        let position = SortKey::ALL.iter().position(|key| *key == self.by).unwrap_or(0);
+        let by = SortKey::ALL[(position + 1) % SortKey::ALL.len()];
+        let seed = if by == SortKey::Random { time_seed() } else { self.seed };
+        Sort { by, seed, ..*self }
+    }
+
+    pub fn reversed(&self) -> Sort {
+        let order = match self.order {
+            SortDirection::Asc => SortDirection::Desc,
+            SortDirection::Desc => SortDirection::Asc,
+        };
+        Sort { order, ..*self }
+    }
+
+    pub fn label(&self) -> String {
+        let arrow = match self.order {
+            SortDirection::Asc => "↑",
+            SortDirection::Desc => "↓",
+        };
+        format!("{} {}", self.by.label(), arrow)
+    }
+
+    fn compare(&self, a: &Note, b: &Note) -> Ordering {
+        let ordering = match self.by {
+            SortKey::Modified => a.modified.cmp(&b.modified),
+            SortKey::Saved => a.saved_date().cmp(&b.saved_date()),
+            SortKey::Title => a.title.to_lowercase().cmp(&b.title.to_lowercase()),
+            // Notes without a domain or rating go last in either direction
+            SortKey::Domain => return self.compare_present(a.domain(), b.domain()),
+            SortKey::ReadingTime => a.word_count.cmp(&b.word_count),
+            SortKey::Rating => return self.compare_present(a.rating, b.rating),
+            SortKey::Random => random_key(self.seed, a).cmp(&random_key(self.seed, b)),
+        };
+        self.directed(ordering)
+    }
+
+    fn directed(&self, ordering: Ordering) -> Ordering {
+        match self.order {
+            SortDirection::Asc => ordering,
+            SortDirection::Desc => ordering.reverse(),
+        }
+    }
+
+    fn compare_present<T: Ord>(&self, a: Option<T>, b: Option<T>) -> Ordering {
+        match (a, b) {
+            (Some(a), Some(b)) => self.directed(a.cmp(&b)),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+    }
+
+    /// Sorts indexes into `notes`. The sort is stable, ties keep their order.
+    pub fn apply(&self, notes: &[Note], indexes: &mut [usize]) {
+        indexes.sort_by(|&a, &b| self.compare(&notes[a], &notes[b]));
+    }
+}
+
+/// Hashes the note's path with the seed, so a note keeps its place in the
+/// shuffle while other notes come and go.
+fn random_key(seed: u64, note: &Note) -> u64 {
+    // FNV-1a, stable across runs unlike the std hasher
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in note.path.as_os_str().as_encoded_bytes().iter().chain(note.title.as_bytes()) {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    splitmix64(hash ^ seed)
+}
+
+pub fn splitmix64(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e3779b97f4a7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+pub fn time_seed() -> u64 {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_nanos() as u64);
+    splitmix64(nanos)
+}
@@ -0,0 +1,78 @@
+use crate::models::Note;
+use crate::sort::splitmix64;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+// Notes at least this old get the full age bonus
+const MAX_AGE_DAYS: f64 = 365.0;
+// Reading time at which the shortness bonus has halved
+const HALF_SHORT_MINUTES: f64 = 10.0;
+
+/// How much each trait raises a note's chance of being picked. Every unread
+/// note has a base weight of 1, each bonus adds up to its weight on top.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SurpriseConfig {
+    /// Favours notes that have waited longest, up to a year
+    pub age_weight: f64,
+    /// Favours highly rated notes, unrated ones count as 2.5 of 5
+    pub rating_weight: f64,
+    /// Favours quick reads
+    pub short_weight: f64,
+    /// Only pick notes that take at most this long to read
+    pub max_minutes: Option<usize>,
+}
+
+impl Default for SurpriseConfig {
+    fn default() -> Self {
+        SurpriseConfig {
+            age_weight: 1.0,
+            rating_weight: 1.0,
+            short_weight: 0.5,
+            max_minutes: None,
+        }
+    }
+}
+
+pub struct Surprise<'a> {
+    pub config: &'a SurpriseConfig,
+    pub words_per_minute: usize,
+    pub today: NaiveDate,
+}
+
+impl Surprise<'_> {
+    pub fn weight(&self, note: &Note) -> f64 {
+        let age_days = note.saved_date().map_or(0, |saved| (self.today - saved).num_days().max(0));
+        let age = (age_days as f64 / MAX_AGE_DAYS).min(1.0);
+        let rating = note.rating.map_or(0.5, |rating| f64::from(rating) / 5.0);
+        let minutes = note.reading_minutes(self.words_per_minute) as f64;
+        let short = HALF_SHORT_MINUTES / (HALF_SHORT_MINUTES + minutes);
+
+        1.0 + self.config.age_weight.max(0.0) * age
+            + self.config.rating_weight.max(0.0) * rating
+            + self.config.short_weight.max(0.0) * short
+    }
+
+    /// Picks one of the unread `candidates`, indexes into `notes`, in proportion
+    /// to their weights. `max_minutes` overrides the configured time budget.
+    pub fn pick(&self, notes: &[Note], candidates: &[usize], max_minutes: Option<usize>, seed: u64) -> Option<usize> {
+        let max_minutes = max_minutes.or(self.config.max_minutes);
+        let weighted: Vec<(usize, f64)> = candidates.iter()
+            .copied()
+            .filter(|&i| !notes[i].is_done())
+            .filter(|&i| max_minutes.is_none_or(|max| notes[i].reading_minutes(self.words_per_minute) <= max))
+            .map(|i| (i, self.weight(&notes[i])))
+            .collect();
+
+        let total: f64 = weighted.iter().map(|(_, weight)| weight).sum();
+        // Top 53 bits make an evenly spread float in [0, 1)
+        let mut target = (splitmix64(seed) >> 11) as f64 / (1u64 << 53) as f64 * total;
+        for &(i, weight) in &weighted {
+            if target < weight {
+                return Some(i);
+            }
+            target -= weight;
+        }
+        weighted.last().map(|(i, _)| *i)
+    }
+}
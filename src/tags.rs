@@ -0,0 +1,49 @@
+use crate::config::CardConfig;
+use crate::models::Note;
+use std::collections::{HashMap, HashSet};
+
+/// A tag in the sidebar tree. `infra/k8s` is a child of `infra`, and a note
+/// tagged `infra/k8s` counts towards both.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TagNode {
+    pub tag: String,  // Full lowercase path, as `tag:` filters take it
+    pub name: String, // Last segment, cased as first seen
+    pub depth: usize,
+    pub unread: usize,
+    pub total: usize,
+}
+
+/// Every tag shown on cards across `notes`, parents before their children and
+/// siblings in alphabetical order.
+pub fn tag_tree(notes: &[Note], card: &CardConfig) -> Vec<TagNode> {
+    let mut nodes: HashMap<String, TagNode> = HashMap::new();
+    for note in notes {
+        // A note tagged both `infra` and `infra/k8s` counts once for `infra`
+        let mut counted = HashSet::new();
+        for tag in note.tags.iter().filter(|tag| card.is_tag_shown(tag)) {
+            let segments: Vec<&str> = tag.split('/').filter(|segment| !segment.is_empty()).collect();
+            for depth in 0..segments.len() {
+                let path = segments[..=depth].join("/").to_lowercase();
+                if !counted.insert(path.clone()) {
+                    continue;
+                }
+                let node = nodes.entry(path.clone()).or_insert_with(|| TagNode {
+                    tag: path,
+                    name: segments[depth].to_string(),
+                    depth,
+                    unread: 0,
+                    total: 0,
+                });
+                node.total += 1;
+                if !note.is_done() {
+                    node.unread += 1;
+                }
+            }
+        }
+    }
+
+    let mut tree: Vec<TagNode> = nodes.into_values().collect();
+    // Comparing segment by segment keeps `a/b` right after `a` and before `ab`
+    tree.sort_by(|a, b| a.tag.split('/').cmp(b.tag.split('/')));
+    tree
+}
@@ -0,0 +1,328 @@
+use ratatui::style::{Color, Modifier, Style};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, io, path::Path, str::FromStr};
+
+pub const BUILTIN_THEMES: [&str; 3] = ["dark", "light", "high-contrast"];
+
+// Theme files may extend other themes, this guards against cycles
+const MAX_EXTENDS_DEPTH: usize = 8;
+
+/// Colors and modifiers for a single UI element. Unset fields inherit from
+/// the theme being extended or overridden.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StyleSpec {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fg: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bg: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modifiers: Option<Vec<String>>,
+}
+
+impl StyleSpec {
+    pub fn fg(color: &str) -> Self {
+        StyleSpec { fg: Some(color.to_string()), ..StyleSpec::default() }
+    }
+
+    pub fn with_bg(mut self, color: &str) -> Self {
+        self.bg = Some(color.to_string());
+        self
+    }
+
+    pub fn with_modifiers(mut self, modifiers: &[&str]) -> Self {
+        self.modifiers = Some(modifiers.iter().map(|m| m.to_string()).collect());
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fg.is_none() && self.bg.is_none() && self.modifiers.is_none()
+    }
+
+    pub fn merge(&self, overlay: &StyleSpec) -> StyleSpec {
+        StyleSpec {
+            fg: overlay.fg.clone().or_else(|| self.fg.clone()),
+            bg: overlay.bg.clone().or_else(|| self.bg.clone()),
+            modifiers: overlay.modifiers.clone().or_else(|| self.modifiers.clone()),
+        }
+    }
+
+    /// Unknown colors and modifiers are ignored rather than failing the whole theme.
+    pub fn style(&self) -> Style {
+        let mut style = Style::default();
+        if let Some(fg) = self.fg.as_deref().and_then(|c| Color::from_str(c).ok()) {
+            style = style.fg(fg);
+        }
+        if let Some(bg) = self.bg.as_deref().and_then(|c| Color::from_str(c).ok()) {
+            style = style.bg(bg);
+        }
+        for modifier in self.modifiers.iter().flatten() {
+            if let Some(modifier) = parse_modifier(modifier) {
+                style = style.add_modifier(modifier);
+            }
+        }
+        style
+    }
+}
+
+pub fn parse_modifier(name: &str) -> Option<Modifier> {
+    match name.to_lowercase().replace(['-', ' '], "_").as_str() {
+        "bold" => Some(Modifier::BOLD),
+        "dim" => Some(Modifier::DIM),
+        "italic" => Some(Modifier::ITALIC),
+        "underlined" | "underline" => Some(Modifier::UNDERLINED),
+        "slow_blink" | "blink" => Some(Modifier::SLOW_BLINK),
+        "rapid_blink" => Some(Modifier::RAPID_BLINK),
+        "reversed" | "reverse" => Some(Modifier::REVERSED),
+        "hidden" => Some(Modifier::HIDDEN),
+        "crossed_out" | "strikethrough" => Some(Modifier::CROSSED_OUT),
+        _ => None,
+    }
+}
+
+/// A full set of element styles. `Theme::default()` is an empty theme that
+/// overrides nothing, the presets are `dark`, `light` and `high_contrast`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    #[serde(skip_serializing_if = "StyleSpec::is_empty")]
+    pub border: StyleSpec,
+    #[serde(skip_serializing_if = "StyleSpec::is_empty")]
+    pub selected_border: StyleSpec,
+    #[serde(skip_serializing_if = "StyleSpec::is_empty")]
+    pub title: StyleSpec,
+    #[serde(skip_serializing_if = "StyleSpec::is_empty")]
+    pub excerpt: StyleSpec,
+    #[serde(skip_serializing_if = "StyleSpec::is_empty")]
+    pub tag_chip: StyleSpec,
+    /// Per-tag chip styles layered over `tag_chip`
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub tag_colors: BTreeMap<String, StyleSpec>,
+    #[serde(skip_serializing_if = "StyleSpec::is_empty")]
+    pub footer: StyleSpec,
+    #[serde(skip_serializing_if = "StyleSpec::is_empty")]
+    pub read: StyleSpec,
+    #[serde(skip_serializing_if = "StyleSpec::is_empty")]
+    pub unread: StyleSpec,
+    #[serde(skip_serializing_if = "StyleSpec::is_empty")]
+    pub archived: StyleSpec,
+    /// Prefix added to the card title of read notes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub read_badge: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub archived_badge: Option<String>,
+    /// Border of cards marked for a bulk action, and the prefix added to their title
+    #[serde(skip_serializing_if = "StyleSpec::is_empty")]
+    pub marked_border: StyleSpec,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub marked_badge: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub greyscale_read_thumbnails: Option<bool>,
+    #[serde(skip_serializing_if = "StyleSpec::is_empty")]
+    pub search_match: StyleSpec,
+    #[serde(skip_serializing_if = "StyleSpec::is_empty")]
+    pub status_bar: StyleSpec,
+    /// View names along the top
+    #[serde(skip_serializing_if = "StyleSpec::is_empty")]
+    pub tab: StyleSpec,
+    #[serde(skip_serializing_if = "StyleSpec::is_empty")]
+    pub selected_tab: StyleSpec,
+    /// Section headers in a grouped list
+    #[serde(skip_serializing_if = "StyleSpec::is_empty")]
+    pub group_header: StyleSpec,
+    #[serde(skip_serializing_if = "StyleSpec::is_empty")]
+    pub popup: StyleSpec,
+    #[serde(skip_serializing_if = "StyleSpec::is_empty")]
+    pub popup_border: StyleSpec,
+}
+
+/// A theme file in the themes directory, optionally extending another theme.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThemeFile {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extends: Option<String>,
+    #[serde(flatten)]
+    pub theme: Theme,
+}
+
+impl Theme {
+    pub fn dark() -> Self {
+        Theme {
+            border: StyleSpec::fg("gray"),
+            selected_border: StyleSpec::fg("yellow").with_modifiers(&["bold"]),
+            title: StyleSpec::fg("white").with_modifiers(&["bold"]),
+            excerpt: StyleSpec::fg("gray"),
+            tag_chip: StyleSpec::fg("black").with_bg("cyan"),
+            tag_colors: BTreeMap::new(),
+            footer: StyleSpec::fg("darkgray"),
+            read: StyleSpec::fg("darkgray").with_modifiers(&["dim"]),
+            unread: StyleSpec::fg("white"),
+            archived: StyleSpec::fg("darkgray").with_modifiers(&["dim", "italic"]),
+            read_badge: Some("✓".to_string()),
+            archived_badge: Some("▣".to_string()),
+            marked_border: StyleSpec::fg("magenta").with_modifiers(&["bold"]),
+            marked_badge: Some("●".to_string()),
+            greyscale_read_thumbnails: Some(true),
+            search_match: StyleSpec::fg("black").with_bg("yellow"),
+            status_bar: StyleSpec::fg("white").with_bg("darkgray"),
+            tab: StyleSpec::fg("gray"),
+            selected_tab: StyleSpec::fg("black").with_bg("yellow").with_modifiers(&["bold"]),
+            group_header: StyleSpec::fg("yellow").with_modifiers(&["bold"]),
+            popup: StyleSpec::fg("white").with_bg("black"),
+            popup_border: StyleSpec::fg("yellow"),
+        }
+    }
+
+    pub fn light() -> Self {
+        Theme {
+            border: StyleSpec::fg("darkgray"),
+            selected_border: StyleSpec::fg("blue").with_modifiers(&["bold"]),
+            title: StyleSpec::fg("black").with_modifiers(&["bold"]),
+            excerpt: StyleSpec::fg("black"),
+            tag_chip: StyleSpec::fg("white").with_bg("blue"),
+            tag_colors: BTreeMap::new(),
+            footer: StyleSpec::fg("darkgray"),
+            read: StyleSpec::fg("gray").with_modifiers(&["dim"]),
+            unread: StyleSpec::fg("black"),
+            archived: StyleSpec::fg("gray").with_modifiers(&["dim", "italic"]),
+            read_badge: Some("✓".to_string()),
+            archived_badge: Some("▣".to_string()),
+            marked_border: StyleSpec::fg("magenta").with_modifiers(&["bold"]),
+            marked_badge: Some("●".to_string()),
+            greyscale_read_thumbnails: Some(true),
+            search_match: StyleSpec::fg("black").with_bg("lightyellow"),
+            status_bar: StyleSpec::fg("black").with_bg("gray"),
+            tab: StyleSpec::fg("darkgray"),
+            selected_tab: StyleSpec::fg("white").with_bg("blue").with_modifiers(&["bold"]),
+            group_header: StyleSpec::fg("blue").with_modifiers(&["bold"]),
+            popup: StyleSpec::fg("black").with_bg("white"),
+            popup_border: StyleSpec::fg("blue"),
+        }
+    }
+
+    pub fn high_contrast() -> Self {
+        Theme {
+            border: StyleSpec::fg("white"),
+            selected_border: StyleSpec::fg("lightyellow").with_modifiers(&["bold", "reversed"]),
+            title: StyleSpec::fg("white").with_modifiers(&["bold", "underlined"]),
+            excerpt: StyleSpec::fg("white"),
+            tag_chip: StyleSpec::fg("black").with_bg("lightyellow").with_modifiers(&["bold"]),
+            tag_colors: BTreeMap::new(),
+            footer: StyleSpec::fg("white"),
+            read: StyleSpec::fg("gray").with_modifiers(&["italic"]),
+            unread: StyleSpec::fg("white").with_modifiers(&["bold"]),
+            archived: StyleSpec::fg("gray").with_modifiers(&["italic", "crossed_out"]),
+            read_badge: Some("[READ]".to_string()),
+            archived_badge: Some("[ARCHIVED]".to_string()),
+            marked_border: StyleSpec::fg("lightmagenta").with_modifiers(&["bold"]),
+            marked_badge: Some("[X]".to_string()),
+            greyscale_read_thumbnails: Some(false),
+            search_match: StyleSpec::fg("black").with_bg("lightgreen").with_modifiers(&["bold", "underlined"]),
+            status_bar: StyleSpec::fg("black").with_bg("white").with_modifiers(&["bold"]),
+            tab: StyleSpec::fg("white"),
+            selected_tab: StyleSpec::fg("black").with_bg("lightyellow").with_modifiers(&["bold"]),
+            group_header: StyleSpec::fg("lightyellow").with_modifiers(&["bold", "underlined"]),
+            popup: StyleSpec::fg("white").with_bg("black"),
+            popup_border: StyleSpec::fg("lightyellow").with_modifiers(&["bold"]),
+        }
+    }
+
+    pub fn tag_chip_style(&self, tag: &str) -> Style {
+        match self.tag_colors.get(tag) {
+            Some(spec) => self.tag_chip.merge(spec).style(),
+            None => self.tag_chip.style(),
+        }
+    }
+
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Theme::dark()),
+            "light" => Some(Theme::light()),
+            "high-contrast" | "high_contrast" => Some(Theme::high_contrast()),
+            _ => None,
+        }
+    }
+
+    pub fn merge(&self, overlay: &Theme) -> Theme {
+        Theme {
+            border: self.border.merge(&overlay.border),
+            selected_border: self.selected_border.merge(&overlay.selected_border),
+            title: self.title.merge(&overlay.title),
+            excerpt: self.excerpt.merge(&overlay.excerpt),
+            tag_chip: self.tag_chip.merge(&overlay.tag_chip),
+            tag_colors: {
+                let mut tag_colors = self.tag_colors.clone();
+                for (tag, spec) in &overlay.tag_colors {
+                    let merged = tag_colors.get(tag).cloned().unwrap_or_default().merge(spec);
+                    tag_colors.insert(tag.clone(), merged);
+                }
+                tag_colors
+            },
+            footer: self.footer.merge(&overlay.footer),
+            read: self.read.merge(&overlay.read),
+            unread: self.unread.merge(&overlay.unread),
+            archived: self.archived.merge(&overlay.archived),
+            read_badge: overlay.read_badge.clone().or_else(|| self.read_badge.clone()),
+            archived_badge: overlay.archived_badge.clone().or_else(|| self.archived_badge.clone()),
+            marked_border: self.marked_border.merge(&overlay.marked_border),
+            marked_badge: overlay.marked_badge.clone().or_else(|| self.marked_badge.clone()),
+            greyscale_read_thumbnails: overlay.greyscale_read_thumbnails.or(self.greyscale_read_thumbnails),
+            search_match: self.search_match.merge(&overlay.search_match),
+            status_bar: self.status_bar.merge(&overlay.status_bar),
+            tab: self.tab.merge(&overlay.tab),
+            selected_tab: self.selected_tab.merge(&overlay.selected_tab),
+            group_header: self.group_header.merge(&overlay.group_header),
+            popup: self.popup.merge(&overlay.popup),
+            popup_border: self.popup_border.merge(&overlay.popup_border),
+        }
+    }
+
+    /// Resolves a theme by name: built-in presets first, then
+    /// `<themes_dir>/<name>.yaml`. Theme files without `extends` build on `dark`.
+    pub fn load(name: &str, themes_dir: Option<&Path>) -> io::Result<Self> {
+        Self::load_with_depth(name, themes_dir, 0)
+    }
+
+    fn load_with_depth(name: &str, themes_dir: Option<&Path>, depth: usize) -> io::Result<Self> {
+        if let Some(theme) = Theme::builtin(name) {
+            return Ok(theme);
+        }
+        if depth >= MAX_EXTENDS_DEPTH {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Theme '{}' extends too deeply", name)));
+        }
+
+        let themes_dir = themes_dir.ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, format!("Unknown theme '{}'", name))
+        })?;
+        let theme_file = ThemeFile::load(themes_dir.join(format!("{}.yaml", name)))?;
+        let base_name = theme_file.extends.as_deref().unwrap_or("dark");
+        let base = Self::load_with_depth(base_name, Some(themes_dir), depth + 1)?;
+        Ok(base.merge(&theme_file.theme))
+    }
+}
+
+impl ThemeFile {
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let theme_str = fs::read_to_string(path)?;
+        serde_yaml::from_str(&theme_str)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+/// Lists the built-in themes followed by the theme files found in `themes_dir`.
+pub fn available_themes(themes_dir: Option<&Path>) -> Vec<String> {
+    let mut names: Vec<String> = BUILTIN_THEMES.iter().map(|name| name.to_string()).collect();
+    if let Some(entries) = themes_dir.and_then(|dir| fs::read_dir(dir).ok()) {
+        let mut files: Vec<String> = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "yaml"))
+            .filter_map(|path| path.file_stem().and_then(|s| s.to_str()).map(str::to_string))
+            .filter(|name| !names.contains(name))
+            .collect();
+        files.sort();
+        names.extend(files);
+    }
+    names
+}
@@ -1,46 +1,451 @@
-use crate::app::App;
-use ratatui::{prelude::*, widgets::{block::*, Borders, Paragraph, Wrap}};
+use crate::app::{App, PromptKind, Row, SearchKind, TagMatch};
+use ratatui::{prelude::*, widgets::{block::*, Borders, Clear, Paragraph, Wrap}};
+use crate::config::{CardConfig, Config, FooterItem};
+use crate::images::{ImageCache, ImageKey, ImageStatus};
 use crate::models::Note;
-use ratatui_image::{picker::Picker, StatefulImage, protocol::StatefulProtocol};
+use crate::mouse::{Hit, HitMap};
+use crate::search::NoteHighlights;
+use crate::theme::{StyleSpec, Theme};
+use ratatui_image::StatefulImage;
 use std::path::PathBuf;
-use std::collections::HashMap;
+use unicode_width::UnicodeWidthStr;
 
-pub struct Renderer {}
+const FOOTER_SEPARATOR: &str = " · ";
+const SIDEBAR_WIDTH: u16 = 30;
+
+pub struct Renderer {
+    theme: Theme,
+    card: CardConfig,
+    pub hits: HitMap, // What was drawn where in the last frame, for mouse clicks
+}
+
+/// Where the parts of a card go, thumbnail is `None` when images are disabled.
+struct CardLayout {
+    thumbnail: Option<Rect>,
+    excerpt: Rect,
+    footer: Rect,
+}
+
+/// How a card stands out from the others.
+#[derive(Clone, Copy)]
+struct CardState {
+    selected: bool,
+    marked: bool, // For a bulk action
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl Renderer {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            theme: Theme::dark(),
+            card: CardConfig::default(),
+            hits: HitMap::default(),
+        }
+    }
+
+    pub fn with_config(config: &Config, theme: Theme) -> Self {
+        Self {
+            theme,
+            card: config.card.clone(),
+            hits: HitMap::default(),
+        }
     }
 
     pub fn render_app(&mut self, app: &mut App, frame: &mut Frame<'_>) {
-        let area = frame.area();
-        // TODO: make this configurable
-        let num_cols = 2;
-        let num_rows = 2;
-        let notes_to_render = app.notes_on_current_page().to_vec();
+        self.hits.clear();
+        let [tabs_area, body_area, status_area] = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(0), Constraint::Length(1)])
+            .areas(frame.area());
+        let sidebar_width = if app.tags.open { SIDEBAR_WIDTH.min(body_area.width / 2) } else { 0 };
+        let [sidebar_area, grid_area] = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Length(sidebar_width), Constraint::Min(0)])
+            .areas(body_area);
+        let num_cols = app.view().layout.columns();
+        let num_rows = app.view().layout.rows();
+        let rows = app.rows_on_current_page().to_vec();
         let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![Constraint::Ratio(1, num_cols as u32); num_cols])
+            .split(grid_area);
+        // Group headers take a line each, the cards share what is left
+        let headers = rows.iter().filter(|row| matches!(row, Row::Header { .. })).count() as u16;
+        let card_height = chunks[0].height.saturating_sub(headers) / num_rows as u16;
+
+        let mut y = grid_area.y;
+        for (i, row) in rows.iter().enumerate().take(num_cols * num_rows) {
+            let is_selected = i == app.selected_note_index;
+            match row {
+                Row::Header { label, count, collapsed } => {
+                    // Only single-column lists are grouped
+                    let header_area = Rect::new(grid_area.x, y, grid_area.width, 1);
+                    self.render_group_header(frame, header_area, label, *count, *collapsed, is_selected);
+                    self.hits.push(header_area, Hit::Row(i));
+                    y += 1;
+                }
+                Row::Note(note_index) => {
+                    let col = i % num_cols;
+                    let card_area = Rect::new(chunks[col].x, y, chunks[col].width, card_height);
+                    let note = Note { excerpt: app.excerpt(*note_index).to_string(), ..app.notes[*note_index].clone() };
+                    let highlights = app.highlights(*note_index).cloned();
+                    let state = CardState { selected: is_selected, marked: app.is_marked(*note_index) };
+                    self.render_note_card(frame, card_area, &note, highlights.as_ref(), state, &mut app.image_cache);
+                    self.hits.push(card_area, Hit::Row(i));
+                    if col == num_cols - 1 {
+                        y += card_height;
+                    }
+                }
+            }
+        }
+
+        let card_size = Rect::new(0, 0, chunks[0].width, card_height);
+        self.prefetch_adjacent_pages(app, card_size);
+        self.render_tabs(app, frame, tabs_area);
+        if app.tags.open {
+            self.render_tag_sidebar(app, frame, sidebar_area);
+        }
+        self.render_status_bar(app, frame, status_area);
+        if app.related.is_some() {
+            self.render_related(app, frame, grid_area);
+        }
+        if app.duplicates.is_some() {
+            self.render_duplicates(app, frame, grid_area);
+        }
+        if app.bulk_menu {
+            self.render_bulk_menu(app, frame, grid_area);
+        }
+        if app.help.is_some() {
+            self.render_help(app, frame, grid_area);
+        }
+    }
+
+    /// "▾ label (count)", with ▸ instead when the group is folded.
+    fn render_group_header(&self, frame: &mut Frame, area: Rect, label: &str, count: usize, collapsed: bool, is_selected: bool) {
+        let marker = if collapsed { "▸" } else { "▾" };
+        let mut style = self.theme.group_header.style();
+        if is_selected {
+            style = style.add_modifier(Modifier::REVERSED);
+        }
+        let line = Line::from(vec![
+            Span::styled(format!(" {} {} ", marker, label), style),
+            Span::styled(format!("({})", count), self.theme.footer.style()),
+        ]);
+        frame.render_widget(Paragraph::new(line), area);
+    }
+
+    /// A bordered popup in the middle of `area`, returns the area inside the border.
+    fn render_popup(&mut self, frame: &mut Frame, area: Rect, title: String, hint: &str, content_height: usize) -> Rect {
+        let width = (area.width * 3 / 4).max(20).min(area.width);
+        let height = (content_height as u16).saturating_add(2).min(area.height);
+        let popup = Rect::new(area.x + (area.width - width) / 2, area.y + (area.height - height) / 2, width, height);
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .title_bottom(Line::from(hint.to_string()).right_aligned())
+            .border_style(self.theme.popup_border.style())
+            .style(self.theme.popup.style());
+        let inner = block.inner(popup);
+        frame.render_widget(Clear, popup);
+        frame.render_widget(block, popup);
+        self.hits.push(popup, Hit::Popup);
+        inner
+    }
+
+    /// A title on the left and `detail` right-aligned, cut to fit `width`.
+    fn popup_row(&self, title: &str, detail: String, width: usize, is_selected: bool) -> Line<'static> {
+        let title_width = width.saturating_sub(detail.width() + 3);
+        let mut line = truncate_spans(vec![Span::raw(format!(" {}", title))], title_width);
+        let used: usize = line.iter().map(|span| span.content.width()).sum();
+        line.push(Span::raw(" ".repeat(title_width.saturating_sub(used) + 1)));
+        line.push(Span::styled(detail, self.theme.footer.style()));
+        let style = if is_selected { Style::default().add_modifier(Modifier::REVERSED) } else { Style::default() };
+        Line::from(line).style(style)
+    }
+
+    fn render_duplicates(&mut self, app: &App, frame: &mut Frame, area: Rect) {
+        let Some(panel) = &app.duplicates else {
+            return;
+        };
+        let title = format!(" Duplicates: {} groups ", panel.groups.len());
+        let hint = " Enter jump · m keep this, trash the rest · t trash · Esc close ";
+        let inner = self.render_popup(frame, area, title, hint, panel.groups.len() + panel.len());
+
+        let mut lines = Vec::new();
+        let mut positions = Vec::new(); // The note on each line, none for group headers
+        let mut selected_line = 0;
+        let mut position = 0;
+        for group in &panel.groups {
+            let url = app.notes[group[0]].canonical_url().unwrap_or_default();
+            lines.push(Line::styled(format!("{} ({})", url, group.len()), self.theme.title.style()));
+            positions.push(None);
+            for &i in group {
+                let note = &app.notes[i];
+                let mut detail = note.saved_date().map(|date| date.format(&self.card.date_format).to_string()).unwrap_or_default();
+                if note.is_done() {
+                    detail.push_str(if note.archived { " · archived" } else { " · read" });
+                }
+                if position == panel.selected {
+                    selected_line = lines.len();
+                }
+                lines.push(self.popup_row(&note.title, detail, inner.width as usize, position == panel.selected));
+                positions.push(Some(position));
+                position += 1;
+            }
+        }
+
+        // Keep the selected row on screen
+        let scroll = (selected_line + 1).saturating_sub(inner.height as usize);
+        frame.render_widget(Paragraph::new(lines).scroll((scroll as u16, 0)), inner);
+        for (y, position) in (inner.y..inner.bottom()).zip(positions.into_iter().skip(scroll)) {
+            if let Some(position) = position {
+                self.hits.push(Rect::new(inner.x, y, inner.width, 1), Hit::Duplicate(position));
+            }
+        }
+    }
+
+    fn render_bulk_menu(&mut self, app: &App, frame: &mut Frame, area: Rect) {
+        let items = [
+            ("r", "Mark read"),
+            ("u", "Mark unread"),
+            ("+", "Add a tag…"),
+            ("-", "Remove a tag…"),
+            ("a", "Archive"),
+            ("x", "Move to .trash"),
+            ("e", "Export as a list of links…"),
+        ];
+        let title = format!(" {} marked notes ", app.marked.len());
+        let inner = self.render_popup(frame, area, title, " Esc close ", items.len());
+        let lines: Vec<Line> = items.iter()
+            .map(|(key, label)| Line::from(vec![
+                Span::styled(format!(" {} ", key), self.theme.title.style()),
+                Span::raw(format!(" {}", label)),
+            ]))
+            .collect();
+        frame.render_widget(Paragraph::new(lines), inner);
+    }
+
+    fn render_help(&mut self, app: &App, frame: &mut Frame, area: Rect) {
+        let Some(panel) = &app.help else {
+            return;
+        };
+        let inner = self.render_popup(frame, area, " Keys ".to_string(), " ↑↓ scroll · Esc close ", panel.entries.len());
+        let key_width = panel.entries.iter().map(|(keys, _)| keys.width()).max().unwrap_or(0);
+        let lines: Vec<Line> = panel.entries.iter().skip(panel.scroll)
+            .map(|(keys, action)| Line::from(vec![
+                Span::styled(format!(" {:<width$} ", keys, width = key_width), self.theme.title.style()),
+                Span::raw(format!(" {}", action)),
+            ]))
+            .collect();
+        frame.render_widget(Paragraph::new(lines), inner);
+    }
+
+    fn render_related(&mut self, app: &App, frame: &mut Frame, area: Rect) {
+        let Some(panel) = &app.related else {
+            return;
+        };
+        let title = format!(" Related to {} ", app.notes[panel.source].title);
+        let inner = self.render_popup(frame, area, title, " Enter jump · o open · Esc close ", panel.items.len());
+        let lines: Vec<Line> = panel.items.iter().enumerate()
+            .map(|(i, item)| {
+                let note = &app.notes[item.note_index];
+                let detail = match note.domain() {
+                    Some(domain) => format!("{} · {}%", domain, item.percent()),
+                    None => format!("{}%", item.percent()),
+                };
+                self.popup_row(&note.title, detail, inner.width as usize, i == panel.selected)
+            })
+            .collect();
+        frame.render_widget(Paragraph::new(lines), inner);
+        for (i, y) in (0..panel.items.len()).zip(inner.y..inner.bottom()) {
+            self.hits.push(Rect::new(inner.x, y, inner.width, 1), Hit::Related(i));
+        }
+    }
+
+    /// The tag tree with unread/total counts, ● marking checked tags.
+    fn render_tag_sidebar(&mut self, app: &App, frame: &mut Frame, area: Rect) {
+        let sidebar = &app.tags;
+        let title = match sidebar.matching {
+            TagMatch::All => " Tags (all) ",
+            TagMatch::Any => " Tags (any) ",
+        };
+        let border = if sidebar.focused { &self.theme.selected_border } else { &self.theme.border };
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .border_style(border.style());
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let lines: Vec<Line> = sidebar.tree.iter().enumerate()
+            .map(|(i, node)| {
+                let marker = if sidebar.is_checked(&node.tag) { "●" } else { " " };
+                let name = format!("{}{} {}", "  ".repeat(node.depth), marker, node.name);
+                let detail = format!("{}/{}", node.unread, node.total);
+                self.popup_row(&name, detail, inner.width as usize, sidebar.focused && i == sidebar.selected)
+            })
+            .collect();
+        // Keep the selected tag on screen
+        let scroll = (sidebar.selected + 1).saturating_sub(inner.height as usize);
+        frame.render_widget(Paragraph::new(lines).scroll((scroll as u16, 0)), inner);
+        self.hits.push(area, Hit::Sidebar);
+        for (i, y) in (scroll..sidebar.tree.len()).zip(inner.y..inner.bottom()) {
+            self.hits.push(Rect::new(inner.x, y, inner.width, 1), Hit::Tag(i));
+        }
+    }
+
+    fn render_tabs(&mut self, app: &App, frame: &mut Frame, area: Rect) {
+        let mut spans = Vec::new();
+        let mut x = area.x;
+        for (i, view) in app.views.iter().enumerate() {
+            let style = if i == app.active_view { &self.theme.selected_tab } else { &self.theme.tab };
+            // Only the first nine views have a number key
+            let label = if i < 9 { format!(" {} {} ", i + 1, view.name) } else { format!(" {} ", view.name) };
+            let width = (label.width() as u16).min(area.right().saturating_sub(x));
+            self.hits.push(Rect::new(x, area.y, width, 1), Hit::Tab(i));
+            x = x.saturating_add(width + 1);
+            spans.push(Span::styled(label, style.style()));
+            spans.push(Span::raw(" "));
+        }
+        frame.render_widget(Paragraph::new(Line::from(spans)), area);
+    }
+
+    /// Queues the thumbnails of the next and previous pages behind the current one.
+    fn prefetch_adjacent_pages(&self, app: &mut App, card_size: Rect) {
+        if !app.image_cache.is_enabled() {
+            return;
+        }
+        let Some(thumbnail_area) = self.card_layout(card_size, true).thumbnail else {
+            return;
+        };
+
+        let mut pages = vec![app.current_page + 1];
+        if app.current_page > 0 {
+            pages.push(app.current_page - 1);
+        }
+        let keys: Vec<ImageKey> = pages.into_iter()
+            .flat_map(|page| app.notes_on_page(page))
+            .filter_map(|note| self.image_key(note, thumbnail_area))
+            .collect();
+        for key in keys {
+            app.image_cache.request(key);
+        }
+    }
+
+    fn image_key(&self, note: &Note, area: Rect) -> Option<ImageKey> {
+        let path = PathBuf::from(note.thumbnail.as_ref()?);
+        if note.thumbnail_url.is_none() && !path.exists() {
+            return None;
+        }
+        Some(ImageKey {
+            path,
+            url: note.thumbnail_url.clone(),
+            greyscale: note.is_done() && self.theme.greyscale_read_thumbnails.unwrap_or(false),
+            width: area.width,
+            height: area.height,
+        })
+    }
+
+    /// Where the parts of a card go inside its border. Prefetching sizes
+    /// thumbnails with it too, so they match the ones drawn later.
+    fn card_layout(&self, card_area: Rect, with_thumbnail: bool) -> CardLayout {
+        let inner_area = Block::default().borders(Borders::ALL).inner(card_area);
+        let [content_area, footer] = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Min(0),
+                Constraint::Length(if self.card.footer.is_empty() { 0 } else { 1 }),
+            ])
+            .areas(inner_area);
+        if !with_thumbnail {
+            return CardLayout { thumbnail: None, excerpt: content_area, footer };
+        }
+
+        let [thumbnail, excerpt] = Layout::default()
             .direction(Direction::Horizontal)
             .constraints([
                 Constraint::Percentage(50),
                 Constraint::Percentage(50),
             ])
-            .split(area);
-        let card_height = chunks[0].height / num_rows as u16;
-
-        for (i, note) in notes_to_render.iter().enumerate().take(num_cols * num_rows) {
-            let col = i % num_cols;
-            let row = i / num_cols;
+            .areas(content_area);
+        CardLayout { thumbnail: Some(thumbnail), excerpt, footer }
+    }
 
-            let card_area = Rect::new(
-                chunks[col].x,
-                chunks[col].y + (row as u16 * card_height),
-                chunks[col].width,
-                card_height,
-            );
+    fn render_status_bar(&self, app: &App, frame: &mut Frame, area: Rect) {
+        if let Some(prompt) = &app.prompt {
+            let prefix = match prompt.kind {
+                PromptKind::Search(SearchKind::Fuzzy) => "/",
+                PromptKind::Search(SearchKind::FullText) => "full text: ",
+                PromptKind::Filter => "filter: ",
+                PromptKind::SurpriseBudget => "surprise me, at most this many minutes: ",
+                PromptKind::Command => ":",
+                PromptKind::AddTag => "add tag to marked notes: ",
+                PromptKind::RemoveTag => "remove tag from marked notes: ",
+                PromptKind::Export => "export marked notes to file: ",
+            };
+            let mut line = format!("{}{}█", prefix, prompt.input);
+            if prompt.completions.len() > 1 {
+                line.push_str(&format!("  ({}/{})", prompt.completion + 1, prompt.completions.len()));
+            }
+            if let Some(error) = &app.filter_error {
+                line.push_str(&format!("  ({})", error));
+            }
+            frame.render_widget(Paragraph::new(line).style(self.theme.status_bar.style()), area);
+            return;
+        }
 
-            let is_selected = i == app.selected_note_index;
-            self.render_note_card(frame, card_area, note, is_selected, &mut app.image_cache);
+        let mut status = format!(
+            " Page {}/{} · {} notes",
+            app.current_page + 1,
+            app.total_pages().max(1),
+            app.visible.len(),
+        );
+        if app.search.is_none() {
+            status.push_str(&format!(" · {}", app.view().sort.label()));
+        }
+        if let Some(by) = app.grouping() {
+            status.push_str(&format!(" · by {}", by.label()));
+        }
+        if let Some(error) = &app.view().filter_error {
+            status.push_str(&format!(" · view filter error: {}", error));
+        }
+        if let Some(active) = &app.filter {
+            status.push_str(&format!(" · filter: {}", active.query));
         }
+        if !app.tags.checked.is_empty() {
+            status.push_str(&format!(" · tags: {}", app.tags.label()));
+        }
+        if let Some(search) = &app.search {
+            let prefix = match search.kind {
+                SearchKind::Fuzzy => "/",
+                SearchKind::FullText => "full text: ",
+            };
+            let position = app.selected_absolute_index()
+                .and_then(|selected| app.visible.iter().position(|&i| i == selected))
+                .map_or(0, |position| position + 1);
+            status.push_str(&format!(" · {}{} ({}/{})", prefix, search.query, position, app.visible.len()));
+        } else if app.hide_read {
+            status.push_str(" · read hidden");
+        }
+        if !app.marked.is_empty() {
+            status.push_str(&format!(" · {} marked", app.marked.len()));
+        }
+        if let Some(job) = &app.bulk {
+            status.push_str(&format!(" · {} {}/{}", job.action.progress_label(), job.done, job.paths.len()));
+        }
+        if let Some(message) = &app.status_message {
+            status.push_str(&format!(" · {}", message));
+        }
+        if !app.pending_keys.is_empty() {
+            status.push_str(&format!(" · {}", app.pending_keys));
+        }
+        frame.render_widget(Paragraph::new(status).style(self.theme.status_bar.style()), area);
     }
 
     fn render_note_card(
@@ -48,56 +453,196 @@ impl Renderer {
         frame: &mut Frame,
         area: Rect,
         note: &Note,
-        is_selected: bool,
-        image_cache: &mut HashMap<PathBuf, Box<dyn StatefulProtocol>>,
+        highlights: Option<&NoteHighlights>,
+        state: CardState,
+        image_cache: &mut ImageCache,
     ) {
+        let border_style = match (state.selected, state.marked) {
+            (true, _) => &self.theme.selected_border,
+            (false, true) => &self.theme.marked_border,
+            (false, false) => &self.theme.border,
+        };
+        let (state_style, badge) = if note.archived {
+            (&self.theme.archived, self.theme.archived_badge.as_deref())
+        } else if note.read {
+            (&self.theme.read, self.theme.read_badge.as_deref())
+        } else {
+            (&self.theme.unread, None)
+        };
+        let title_style = self.theme.title.merge(state_style).style();
+        let mut title = Vec::new();
+        if state.marked && let Some(marked_badge) = &self.theme.marked_badge {
+            title.push(Span::styled(format!("{} ", marked_badge), self.theme.marked_border.style()));
+        }
+        if let Some(badge) = badge {
+            title.push(Span::styled(format!("{} ", badge), title_style));
+        }
+        let title_highlights = highlights.map(|h| h.title.as_slice()).unwrap_or_default();
+        title.extend(self.highlighted_spans(&note.title, title_highlights, title_style));
         let block = Block::default()
             .borders(Borders::ALL)
-            .title(note.title.as_str())
-            .border_style(if is_selected { Style::default().fg(Color::Yellow) } else { Style::default() });
+            .title(Line::from(title))
+            .border_style(border_style.style());
 
-        let inner_area = block.inner(area);
         frame.render_widget(block, area);
 
-        let chunks = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([
-                Constraint::Percentage(50),
-                Constraint::Percentage(50),
-            ])
-            .split(inner_area);
-
-        // Thumbnail or Placeholder
-        if let Some(thumbnail_path) = &note.thumbnail {
-            let image_path = PathBuf::from(thumbnail_path);
-
-            if image_cache.contains_key(&image_path) {
-                let image_protocol = image_cache.get_mut(&image_path).unwrap();
-                let image_widget = StatefulImage::new(None);
-                frame.render_stateful_widget(image_widget, chunks[0], image_protocol);
-            } else if image_path.exists() {
-                // TODO: the image should be reloaded on screen update, not on every render
-                match image::open(&image_path) {
-                    Ok(dyn_img) => {
-                        let mut picker = Picker::new((8, 12));
-                        let mut image_protocol = picker.new_resize_protocol(dyn_img);
-                        let image_widget = StatefulImage::new(None);
-                        frame.render_stateful_widget(image_widget, chunks[0], &mut image_protocol);
-                        image_cache.insert(image_path, image_protocol);
-                    }
-                    Err(_) => {
-                        frame.render_widget(Paragraph::new("📷 Error loading image"), chunks[0]);
+        let layout = self.card_layout(area, image_cache.is_enabled());
+        if !self.card.footer.is_empty() {
+            frame.render_widget(self.card_footer(note, layout.footer.width as usize), layout.footer);
+        }
+        let excerpt_highlights = highlights.map(|h| h.excerpt.as_slice()).unwrap_or_default();
+        self.render_excerpt(frame, layout.excerpt, note, excerpt_highlights, state_style);
+
+        // Thumbnail or Placeholder, decoding happens on the image cache's loader thread
+        let Some(thumbnail_area) = layout.thumbnail else {
+            return;
+        };
+        if note.thumbnail.is_none() {
+            frame.render_widget(Paragraph::new("📄 No Thumbnail"), thumbnail_area);
+            return;
+        }
+        let Some(key) = self.image_key(note, thumbnail_area) else {
+            frame.render_widget(Paragraph::new("📷 Image not found"), thumbnail_area);
+            return;
+        };
+        match image_cache.get_or_request(&key) {
+            ImageStatus::Ready(image_protocol) => {
+                frame.render_stateful_widget(StatefulImage::new(None), thumbnail_area, image_protocol);
+            }
+            ImageStatus::Loading => {
+                frame.render_widget(Paragraph::new("📷 Loading…"), thumbnail_area);
+            }
+            ImageStatus::Failed => {
+                frame.render_widget(Paragraph::new("📷 Error loading image"), thumbnail_area);
+            }
+        }
+    }
+
+    fn render_excerpt(&self, frame: &mut Frame, area: Rect, note: &Note, highlights: &[usize], state_style: &StyleSpec) {
+        let excerpt_style = if note.is_done() { self.theme.excerpt.merge(state_style) } else { self.theme.excerpt.clone() };
+        let excerpt_style = excerpt_style.style();
+
+        let mut lines = Vec::new();
+        let mut offset = 0;
+        for line in note.excerpt.split('\n') {
+            let line_length = line.chars().count();
+            let line_highlights: Vec<usize> = highlights.iter()
+                .filter(|&&position| position >= offset && position < offset + line_length)
+                .map(|position| position - offset)
+                .collect();
+            lines.push(Line::from(self.highlighted_spans(line, &line_highlights, excerpt_style)));
+            offset += line_length + 1;
+        }
+
+        let excerpt = Paragraph::new(lines)
+            .style(excerpt_style)
+            .wrap(Wrap { trim: true });
+        frame.render_widget(excerpt, area);
+    }
+
+    /// Splits `text` into spans, styling the chars at `positions` as search matches.
+    fn highlighted_spans(&self, text: &str, positions: &[usize], style: Style) -> Vec<Span<'static>> {
+        if positions.is_empty() {
+            return vec![Span::styled(text.to_string(), style)];
+        }
+
+        let match_style = style.patch(self.theme.search_match.style());
+        let mut spans: Vec<Span<'static>> = Vec::new();
+        let mut current = String::new();
+        let mut current_is_match = false;
+        for (i, ch) in text.chars().enumerate() {
+            let is_match = positions.binary_search(&i).is_ok();
+            if is_match != current_is_match && !current.is_empty() {
+                let span_style = if current_is_match { match_style } else { style };
+                spans.push(Span::styled(std::mem::take(&mut current), span_style));
+            }
+            current_is_match = is_match;
+            current.push(ch);
+        }
+        if !current.is_empty() {
+            spans.push(Span::styled(current, if current_is_match { match_style } else { style }));
+        }
+        spans
+    }
+
+    fn card_footer(&self, note: &Note, width: usize) -> Line<'static> {
+        let footer_style = self.theme.footer.style();
+        let mut segments: Vec<Vec<Span<'static>>> = Vec::new();
+
+        for item in &self.card.footer {
+            let segment = match item {
+                FooterItem::Tags => {
+                    let mut chips = Vec::new();
+                    for tag in note.tags.iter().filter(|tag| self.card.is_tag_shown(tag)) {
+                        if !chips.is_empty() {
+                            chips.push(Span::raw(" "));
+                        }
+                        chips.push(Span::styled(format!(" {} ", tag), self.theme.tag_chip_style(tag)));
                     }
+                    chips
+                }
+                FooterItem::Domain => note.domain()
+                    .map(|domain| vec![Span::styled(domain, footer_style)])
+                    .unwrap_or_default(),
+                FooterItem::Date => note.saved_date()
+                    .map(|date| vec![Span::styled(date.format(&self.card.date_format).to_string(), footer_style)])
+                    .unwrap_or_default(),
+                FooterItem::ReadingTime if note.word_count > 0 => {
+                    let minutes = note.reading_minutes(self.card.words_per_minute);
+                    vec![Span::styled(format!("{} min", minutes), footer_style)]
                 }
-            } else {
-                frame.render_widget(Paragraph::new("📷 Image not found"), chunks[0]);
+                FooterItem::ReadingTime => Vec::new(),
+            };
+            if !segment.is_empty() {
+                segments.push(segment);
             }
-        } else {
-            frame.render_widget(Paragraph::new("📄 No Thumbnail"), chunks[0]);
         }
 
-        // Excerpt
-        let excerpt = Paragraph::new(note.excerpt.as_str()).wrap(Wrap { trim: true });
-        frame.render_widget(excerpt, chunks[1]);
+        let mut spans = Vec::new();
+        for (i, segment) in segments.into_iter().enumerate() {
+            if i > 0 {
+                spans.push(Span::styled(FOOTER_SEPARATOR, footer_style));
+            }
+            spans.extend(segment);
+        }
+        Line::from(truncate_spans(spans, width))
+    }
+}
+
+/// Cuts spans to fit `width` columns, ending with an ellipsis when anything was dropped.
+fn truncate_spans(spans: Vec<Span<'static>>, width: usize) -> Vec<Span<'static>> {
+    let total: usize = spans.iter().map(|span| span.content.width()).sum();
+    if total <= width {
+        return spans;
+    }
+
+    let budget = width.saturating_sub(1);
+    let mut used = 0;
+    let mut result = Vec::new();
+    for span in spans {
+        let span_width = span.content.width();
+        if used + span_width <= budget {
+            used += span_width;
+            result.push(span);
+            continue;
+        }
+
+        let mut truncated = String::new();
+        for ch in span.content.chars() {
+            let ch_width = unicode_width::UnicodeWidthChar::width(ch).unwrap_or(0);
+            if used + ch_width > budget {
+                break;
+            }
+            used += ch_width;
+            truncated.push(ch);
+        }
+        if !truncated.is_empty() {
+            result.push(Span::styled(truncated, span.style));
+        }
+        break;
+    }
+    if width > 0 {
+        result.push(Span::styled("…", result.last().map(|span| span.style).unwrap_or_default()));
     }
+    result
 }
@@ -0,0 +1,78 @@
+use url::Url;
+
+/// Host of a note URL without the `www.` prefix, e.g. `youtube.com`.
+pub fn domain(url: &str) -> Option<String> {
+    let parsed = Url::parse(url.trim()).ok()?;
+    let host = parsed.host_str()?.to_lowercase();
+    Some(host.strip_prefix("www.").unwrap_or(&host).to_string())
+}
+
+// Query parameters that only track where a link was shared from
+const TRACKING_PARAMS: [&str; 5] = ["ref_src", "ref_url", "si", "fbclid", "gclid"];
+
+/// A normalized form of `url` for spotting the same page saved twice:
+/// https, lowercased host without `www.`/`m.`/`mobile.`, no tracking
+/// parameters, fragment or trailing slash. YouTube and Twitter links are
+/// reduced to the video or status id.
+pub fn canonical_url(url: &str) -> Option<String> {
+    let mut parsed = Url::parse(url.trim()).ok()?;
+    parsed.set_fragment(None);
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Some(parsed.to_string());
+    }
+    let mut host = parsed.host_str()?.to_lowercase();
+    for prefix in ["www.", "m.", "mobile."] {
+        if let Some(stripped) = host.strip_prefix(prefix) {
+            host = stripped.to_string();
+        }
+    }
+    let segments: Vec<String> = parsed.path_segments()
+        .map(|segments| segments.filter(|segment| !segment.is_empty()).map(str::to_string).collect())
+        .unwrap_or_default();
+
+    match host.as_str() {
+        "youtu.be" | "youtube.com" => {
+            let video_id = match (host.as_str(), segments.as_slice()) {
+                ("youtu.be", [id, ..]) => Some(id.clone()),
+                (_, [kind, id, ..]) if kind == "shorts" || kind == "embed" || kind == "live" => Some(id.clone()),
+                _ => parsed.query_pairs().find(|(key, _)| key == "v").map(|(_, id)| id.to_string()),
+            };
+            if let Some(id) = video_id {
+                return Some(format!("https://youtube.com/watch?v={}", id));
+            }
+        }
+        "twitter.com" | "x.com" => {
+            // The same status is reachable under any user name
+            if let Some(position) = segments.iter().position(|segment| segment == "status")
+                && let Some(id) = segments.get(position + 1)
+            {
+                return Some(format!("https://twitter.com/i/status/{}", id));
+            }
+            host = "twitter.com".to_string();
+        }
+        _ => {}
+    }
+
+    let query: Vec<(String, String)> = parsed.query_pairs()
+        .filter(|(key, _)| !key.starts_with("utm_") && !TRACKING_PARAMS.contains(&key.as_ref()))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+    parsed.set_query(None);
+    if !query.is_empty() {
+        parsed.query_pairs_mut().extend_pairs(query);
+    }
+
+    let mut canonical = format!("https://{}", host);
+    if let Some(port) = parsed.port() {
+        canonical.push_str(&format!(":{}", port));
+    }
+    for segment in &segments {
+        canonical.push('/');
+        canonical.push_str(segment);
+    }
+    if let Some(query) = parsed.query() {
+        canonical.push('?');
+        canonical.push_str(query);
+    }
+    Some(canonical)
+}
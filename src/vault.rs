@@ -1,12 +1,60 @@
-use std::{fs, io, path::{Path, PathBuf}, time::SystemTime};
+use std::{collections::HashMap, fs, io, path::{Path, PathBuf}, time::SystemTime};
 use crate::models::Note;
 use crate::config::Config;
+use crate::journal::Operation;
+use serde::{Deserialize, Serialize};
 use reqwest::blocking::get;
 use image::{ImageOutputFormat,io::Reader};
 
+// Bump when the parsed `Note` changes so stale index caches get rebuilt
+const NOTE_INDEX_VERSION: u32 = 3;
+
+/// Parsed notes keyed by file, reused across runs while the file's mtime is unchanged.
+#[derive(Default, Serialize, Deserialize)]
+struct NoteIndex {
+    version: u32,
+    // Settings that change how notes are parsed, a mismatch invalidates the index
+    excerpt_lines: usize,
+    thumbnail_cache: String,
+    notes: HashMap<PathBuf, Note>,
+}
+
+impl NoteIndex {
+    fn path(config: &Config) -> PathBuf {
+        PathBuf::from(&config.index_cache).join("notes.yaml")
+    }
+
+    fn load(config: &Config) -> Self {
+        fs::read_to_string(Self::path(config)).ok()
+            .and_then(|index_str| serde_yaml::from_str::<NoteIndex>(&index_str).ok())
+            .filter(|index| {
+                index.version == NOTE_INDEX_VERSION
+                    && index.excerpt_lines == config.excerpt_lines
+                    && index.thumbnail_cache == config.thumbnail_cache
+            })
+            .unwrap_or_default()
+    }
+
+    fn save(&self, config: &Config) -> io::Result<()> {
+        fs::create_dir_all(&config.index_cache)?;
+        let index_str = serde_yaml::to_string(self)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(Self::path(config), index_str)
+    }
+}
+
+pub fn thumbnail_cache_path(url: &str, cache_dir: &Path) -> PathBuf {
+    cache_dir.join(format!("{:x}.jpeg", md5::compute(url)))
+}
+
 pub fn download_and_cache_thumbnail(url: &str, cache_dir: &Path) -> io::Result<String> {
-    let response = get(url).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
-    let bytes = response.bytes().map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+    let file_path = thumbnail_cache_path(url, cache_dir);
+    if file_path.exists() {
+        return Ok(file_path.to_string_lossy().to_string());
+    }
+
+    let response = get(url).map_err(io::Error::other)?;
+    let bytes = response.bytes().map_err(io::Error::other)?;
     let img = Reader::new(io::Cursor::new(bytes))
         .with_guessed_format()
         .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
@@ -15,12 +63,9 @@ pub fn download_and_cache_thumbnail(url: &str, cache_dir: &Path) -> io::Result<S
         
     fs::create_dir_all(cache_dir)?;
 
-    let file_name = format!("{:x}.jpeg", md5::compute(url));
-    let file_path = cache_dir.join(file_name);
-    
     let mut file = fs::File::create(&file_path)?;
     img.write_to(&mut file, ImageOutputFormat::Jpeg(80))
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        .map_err(io::Error::other)?;
 
     Ok(file_path.to_string_lossy().to_string())
 }
@@ -32,36 +77,166 @@ pub fn scan_vault(config: &Config) -> io::Result<Vec<Note>> {
         return Err(io::Error::new(io::ErrorKind::NotFound, "Vault path does not exist or is not a directory"));
     }
 
-    let mut files_with_mod_time: Vec<(PathBuf, SystemTime)> = fs::read_dir(&vault_path)?
-        .filter_map(Result::ok)
-        .map(|entry| entry.path())
-        .filter(|path| path.is_file() && path.extension().map_or(false, |ext| ext == "md"))
-        .filter_map(|path| fs::metadata(&path).ok().and_then(|meta| meta.modified().ok()).map(|mod_time| (path, mod_time)))
-        .collect();
+    let mut files_with_mod_time = list_notes(&vault_path)?;
+
+    files_with_mod_time.sort_by_key(|(_, mod_time)| std::cmp::Reverse(*mod_time));
 
-    files_with_mod_time.sort_by(|a, b| b.1.cmp(&a.1));
+    let index = NoteIndex::load(config);
+    let mut index_changed = index.notes.len() != files_with_mod_time.len();
 
     let notes: Vec<Note> = files_with_mod_time.into_iter()
-    // TODO: this is limiting the amount of notes to be loaded
-    // for some reason loading all notes is very slow
-    // we can load only the 4 notes at a time and then load the next 4
-        .take(config.max_notes)
-        .filter_map(|(path, _)| {
-            let content = fs::read_to_string(&path).ok()?;
-            let filename = path.file_stem().and_then(|s| s.to_str()).unwrap_or("Untitled").to_string();
-            Some(Note::from_markdown(&content, &filename, config.excerpt_lines, config))
+        .filter_map(|(path, mod_time)| {
+            if let Some(note) = index.notes.get(&path).filter(|note| note.modified == Some(mod_time)) {
+                return Some(note.clone());
+            }
+            index_changed = true;
+            load_note(&path, mod_time, config).ok()
         })
         .collect();
-    
+
+    if index_changed {
+        // The index is only a cache, failing to write it just means parsing again next time
+        let _ = save_index(&notes, config);
+    }
+
     Ok(notes)
 }
 
+/// Writes the notes to the index `scan_vault` starts from, so notes changed
+/// while browsing aren't parsed again next time.
+pub fn save_index(notes: &[Note], config: &Config) -> io::Result<()> {
+    NoteIndex {
+        version: NOTE_INDEX_VERSION,
+        excerpt_lines: config.excerpt_lines,
+        thumbnail_cache: config.thumbnail_cache.clone(),
+        notes: notes.iter().map(|note| (note.path.clone(), note.clone())).collect(),
+    }.save(config)
+}
+
+fn list_notes(vault_path: &Path) -> io::Result<Vec<(PathBuf, SystemTime)>> {
+    Ok(fs::read_dir(vault_path)?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "md"))
+        .filter_map(|path| fs::metadata(&path).ok().and_then(|meta| meta.modified().ok()).map(|mod_time| (path, mod_time)))
+        .collect())
+}
+
+#[derive(Debug, Clone)]
+pub enum VaultChange {
+    Updated(Box<Note>),
+    Removed(PathBuf),
+}
+
+/// Notices notes added, edited or deleted outside the app by comparing mtimes on every poll.
+pub struct VaultWatcher {
+    known: HashMap<PathBuf, SystemTime>,
+}
+
+impl VaultWatcher {
+    pub fn new(notes: &[Note]) -> Self {
+        VaultWatcher {
+            known: notes.iter()
+                .filter_map(|note| note.modified.map(|modified| (note.path.clone(), modified)))
+                .collect(),
+        }
+    }
+
+    pub fn poll(&mut self, config: &Config) -> Vec<VaultChange> {
+        let Ok(files) = list_notes(Path::new(&config.vault_path)) else {
+            return Vec::new();
+        };
 
+        let mut changes = Vec::new();
+        let mut seen = HashMap::with_capacity(files.len());
+        for (path, mod_time) in files {
+            if self.known.get(&path) != Some(&mod_time)
+                && let Ok(note) = load_note(&path, mod_time, config)
+            {
+                changes.push(VaultChange::Updated(Box::new(note)));
+            }
+            seen.insert(path, mod_time);
+        }
+        for path in self.known.keys() {
+            if !seen.contains_key(path) {
+                changes.push(VaultChange::Removed(path.clone()));
+            }
+        }
+        self.known = seen;
+        changes
+    }
 
+    /// Re-reads every note, changed on disk or not.
+    pub fn reload(&mut self, config: &Config) -> Vec<VaultChange> {
+        self.known.values_mut().for_each(|modified| *modified = SystemTime::UNIX_EPOCH);
+        self.poll(config)
+    }
+}
+
+pub fn load_note(path: &Path, mod_time: SystemTime, config: &Config) -> io::Result<Note> {
+    let content = fs::read_to_string(path)?;
+    let filename = path.file_stem().and_then(|s| s.to_str()).unwrap_or("Untitled").to_string();
+    let mut note = Note::from_markdown(&content, &filename, config.excerpt_lines, config);
+    note.path = path.to_path_buf();
+    note.modified = Some(mod_time);
+    Ok(note)
+}
+
+/// Parses a note again after something else changed its file. A file that
+/// can't be read any more is taken out of the vault.
+pub fn reload_note(path: &Path, config: &Config) -> VaultChange {
+    fs::metadata(path).and_then(|meta| meta.modified())
+        .and_then(|modified| load_note(path, modified, config))
+        .map_or_else(|_| VaultChange::Removed(path.to_path_buf()), |note| VaultChange::Updated(Box::new(note)))
+}
 
-pub fn toggle_read_status(note: &mut Note, config: &Config) -> io::Result<()> {
-    let note_path = PathBuf::from(&config.vault_path).join(format!("{}.md", note.title));
-    let mut content = fs::read_to_string(&note_path)?;
+/// Moves a note into `<vault>/.trash`, numbering it if the name is taken. Returns where it went.
+pub fn trash_note(note: &Note, config: &Config) -> io::Result<PathBuf> {
+    let note_path = note.file_path(config);
+    let trash_dir = PathBuf::from(&config.vault_path).join(".trash");
+    fs::create_dir_all(&trash_dir)?;
+
+    let stem = note_path.file_stem().and_then(|s| s.to_str()).unwrap_or("Untitled");
+    let mut target = trash_dir.join(format!("{}.md", stem));
+    let mut n = 1;
+    while target.exists() {
+        target = trash_dir.join(format!("{} ({}).md", stem, n));
+        n += 1;
+    }
+    fs::rename(&note_path, &target)?;
+    Ok(target)
+}
+
+/// Adds the tags of `others` that `keeper` lacks to its file, read and star state included.
+pub fn merge_tags(keeper: &mut Note, others: &[&Note], config: &Config) -> io::Result<Option<Operation>> {
+    let mut missing: Vec<String> = Vec::new();
+    for tag in others.iter().flat_map(|other| &other.tags) {
+        if !keeper.tags.contains(tag) && !missing.contains(tag) {
+            missing.push(tag.clone());
+        }
+    }
+    if missing.is_empty() {
+        return Ok(None);
+    }
+
+    let note_path = keeper.file_path(config);
+    let before = fs::read_to_string(&note_path)?;
+    let links: Vec<String> = missing.iter().map(|tag| format!("[[{}]]", tag)).collect();
+    let content = format!("{}\n{}", before, links.join(" "));
+    fs::write(&note_path, &content)?;
+
+    keeper.read |= missing.iter().any(|tag| tag == "readitnow/read");
+    keeper.archived |= missing.iter().any(|tag| tag == "readitnow/archived");
+    keeper.starred |= missing.iter().any(|tag| tag == "readitnow/starred");
+    keeper.tags.extend(missing);
+    Ok(Some(Operation::edit(&note_path, &before, &content)))
+}
+
+/// Returns the change made to the file, for the undo journal.
+pub fn toggle_read_status(note: &mut Note, config: &Config) -> io::Result<Operation> {
+    let note_path = note.file_path(config);
+    let before = fs::read_to_string(&note_path)?;
+    let mut content = before.clone();
 
     if note.read {
         // Mark as unread: remove tag
@@ -73,8 +248,60 @@ pub fn toggle_read_status(note: &mut Note, config: &Config) -> io::Result<()> {
         note.read = true;
     }
 
-    fs::write(&note_path, content)?; // Write back the modified content
-    Ok(())
+    fs::write(&note_path, &content)?; // Write back the modified content
+    Ok(Operation::edit(&note_path, &before, &content))
+}
+
+pub fn toggle_archived(note: &mut Note, config: &Config) -> io::Result<Operation> {
+    let note_path = note.file_path(config);
+    let before = fs::read_to_string(&note_path)?;
+    let mut content = before.clone();
+
+    if note.archived {
+        // Also drops the line break archiving added before the tag
+        content = content.replace("\n[[readitnow/archived]]", "").replace("[[readitnow/archived]]", "");
+        note.archived = false;
+    } else {
+        content.push_str("\n[[readitnow/archived]]");
+        note.archived = true;
+    }
+
+    fs::write(&note_path, &content)?;
+    Ok(Operation::edit(&note_path, &before, &content))
 }
 
+/// Appends `[[tag]]` to the note's file, unless the note has the tag already.
+pub fn add_tag(note: &mut Note, tag: &str, config: &Config) -> io::Result<Option<Operation>> {
+    if note.tags.iter().any(|existing| existing == tag) {
+        return Ok(None);
+    }
+    let note_path = note.file_path(config);
+    let before = fs::read_to_string(&note_path)?;
+    let content = format!("{}\n[[{}]]", before, tag);
+    fs::write(&note_path, &content)?;
+
+    note.tags.push(tag.to_string());
+    sync_state_tags(note);
+    Ok(Some(Operation::edit(&note_path, &before, &content)))
+}
+
+/// Removes every `[[tag]]` link from the note's file.
+pub fn remove_tag(note: &mut Note, tag: &str, config: &Config) -> io::Result<Option<Operation>> {
+    if !note.tags.iter().any(|existing| existing == tag) {
+        return Ok(None);
+    }
+    let note_path = note.file_path(config);
+    let before = fs::read_to_string(&note_path)?;
+    let content = before.replace(&format!("[[{}]]", tag), "");
+    fs::write(&note_path, &content)?;
+
+    note.tags.retain(|existing| existing != tag);
+    sync_state_tags(note);
+    Ok(Some(Operation::edit(&note_path, &before, &content)))
+}
 
+// `readitnow/*` tags can be added and removed like any other
+fn sync_state_tags(note: &mut Note) {
+    let has = |tag: &str| note.tags.iter().any(|existing| existing == tag);
+    (note.read, note.archived, note.starred) = (has("readitnow/read"), has("readitnow/archived"), has("readitnow/starred"));
+}
@@ -0,0 +1,120 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use readitnow::app::{App, PromptKind};
+use readitnow::bulk::{self, BulkAction};
+use readitnow::config::Config;
+use readitnow::keybindings::{self, KeyMap};
+use readitnow::models::Note;
+use readitnow::vault;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+fn press(app: &mut App, config: &Config, code: KeyCode, modifiers: KeyModifiers) {
+    let (keymap, _) = KeyMap::new(&config.keybindings);
+    keybindings::handle_key_event(KeyEvent::new(code, modifiers), app, config, &keymap);
+}
+
+fn temp_vault(name: &str, notes: &[&str]) -> (PathBuf, Config) {
+    let vault_dir = std::env::temp_dir().join(name);
+    let _ = fs::remove_dir_all(&vault_dir);
+    fs::create_dir_all(&vault_dir).unwrap();
+    for title in notes {
+        fs::write(vault_dir.join(format!("{}.md", title)), format!("[[ReadItLater]]\n# [{}](https://example.com/{})\n", title, title)).unwrap();
+    }
+    let config = Config { vault_path: vault_dir.to_string_lossy().to_string(), ..Config::default() };
+    (vault_dir, config)
+}
+
+#[test]
+fn test_marking_notes_across_pages() {
+    let config = Config::default();
+    let notes = (0..10).map(|i| Note { title: format!("Note {}", i), path: format!("{}.md", i).into(), ..Default::default() }).collect();
+    let mut app = App::new(notes);
+    let none = KeyModifiers::NONE;
+
+    press(&mut app, &config, KeyCode::Char('m'), none);
+    press(&mut app, &config, KeyCode::PageDown, none);
+    press(&mut app, &config, KeyCode::Char('m'), none);
+    assert_eq!(app.marked.len(), 2);
+    assert!(app.is_marked(app.visible[0]) && app.is_marked(app.visible[4]));
+
+    // Marking a page twice unmarks it, ctrl+a marks everything the view shows
+    press(&mut app, &config, KeyCode::Char('M'), none);
+    assert_eq!(app.marked.len(), 5);
+    press(&mut app, &config, KeyCode::Char('M'), none);
+    assert_eq!(app.marked.len(), 1);
+    press(&mut app, &config, KeyCode::Char('a'), KeyModifiers::CONTROL);
+    assert_eq!(app.marked.len(), 10);
+
+    // Esc drops the marks before anything else
+    app.set_search_query(readitnow::app::SearchKind::Fuzzy, "note");
+    press(&mut app, &config, KeyCode::Esc, none);
+    assert!(app.marked.is_empty());
+    assert!(app.search.is_some());
+
+    press(&mut app, &config, KeyCode::Char('b'), none);
+    assert!(!app.bulk_menu);
+    assert!(app.status_message.is_some());
+}
+
+#[test]
+fn test_bulk_actions_change_the_marked_notes() {
+    let (vault_dir, config) = temp_vault("readitnow_bulk_test", &["A", "B", "C"]);
+    let mut app = App::new(vault::scan_vault(&config).unwrap());
+    let none = KeyModifiers::NONE;
+    for _ in 0..2 {
+        press(&mut app, &config, KeyCode::Char('m'), none);
+        press(&mut app, &config, KeyCode::Right, none);
+    }
+
+    // One note per step with no time to spare, the status bar counts them
+    press(&mut app, &config, KeyCode::Char('b'), none);
+    assert!(app.bulk_menu);
+    press(&mut app, &config, KeyCode::Char('+'), none);
+    assert_eq!(app.prompt.as_ref().unwrap().kind, PromptKind::AddTag);
+    for c in "#later".chars() {
+        press(&mut app, &config, KeyCode::Char(c), none);
+    }
+    press(&mut app, &config, KeyCode::Enter, none);
+    assert_eq!(app.bulk.as_ref().unwrap().action, BulkAction::AddTag("later".to_string()));
+    bulk::step(&mut app, &config, Duration::ZERO);
+    assert_eq!(app.bulk.as_ref().unwrap().done, 1);
+    bulk::step(&mut app, &config, Duration::ZERO);
+    assert!(app.bulk.is_none());
+    assert_eq!(app.status_message.as_deref(), Some("Added [[later]] to 2 notes"));
+    let tagged = app.notes.iter().filter(|note| note.tags.contains(&"later".to_string())).count();
+    assert_eq!(tagged, 2);
+
+    let paths = app.marked_paths();
+    app.start_bulk(BulkAction::Export(vault_dir.join("export.md")), paths.clone());
+    bulk::step(&mut app, &config, Duration::from_secs(60));
+    let export = fs::read_to_string(vault_dir.join("export.md")).unwrap();
+    assert_eq!(export.lines().count(), 2);
+    assert!(export.starts_with("- ["));
+
+    // An existing file is left alone
+    press(&mut app, &config, KeyCode::Char('b'), none);
+    press(&mut app, &config, KeyCode::Char('e'), none);
+    for c in vault_dir.join("export.md").to_string_lossy().chars() {
+        press(&mut app, &config, KeyCode::Char(c), none);
+    }
+    press(&mut app, &config, KeyCode::Enter, none);
+    assert!(app.bulk.is_none());
+    assert!(app.status_message.as_deref().unwrap().ends_with("export.md exists already, export to a new file"));
+    assert_eq!(fs::read_to_string(vault_dir.join("export.md")).unwrap(), export);
+
+    // A note gone from disk is reported, the others still change
+    fs::remove_file(&paths[0]).unwrap();
+    press(&mut app, &config, KeyCode::Char('b'), none);
+    press(&mut app, &config, KeyCode::Char('a'), none);
+    bulk::step(&mut app, &config, Duration::from_secs(60));
+    let summary = app.status_message.clone().unwrap();
+    assert!(summary.starts_with("Archived 1 notes, 1 failed: \""), "{}", summary);
+
+    press(&mut app, &config, KeyCode::Char('b'), none);
+    press(&mut app, &config, KeyCode::Char('x'), none);
+    bulk::step(&mut app, &config, Duration::from_secs(60));
+    assert_eq!(app.notes.len(), 2);
+    assert_eq!(app.marked.len(), 1);
+    assert_eq!(vault_dir.join(".trash").read_dir().unwrap().count(), 1);
+}
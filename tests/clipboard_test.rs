@@ -0,0 +1,43 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use readitnow::app::App;
+use readitnow::clipboard;
+use readitnow::config::Config;
+use readitnow::keybindings::{self, AppAction, KeyMap};
+use readitnow::models::Note;
+
+fn yank(app: &mut App, config: &Config, second: char) -> AppAction {
+    let (keymap, _) = KeyMap::new(&config.keybindings);
+    keybindings::handle_key_event(KeyEvent::new(KeyCode::Char('y'), KeyModifiers::NONE), app, config, &keymap);
+    keybindings::handle_key_event(KeyEvent::new(KeyCode::Char(second), KeyModifiers::NONE), app, config, &keymap)
+}
+
+#[test]
+fn test_osc52_sequence() {
+    assert_eq!(clipboard::osc52("hi"), "\x1b]52;c;aGk=\x07");
+    // tmux's passthrough wants the sequence wrapped, with its escapes doubled
+    assert_eq!(clipboard::tmux_passthrough(&clipboard::osc52("hi")), "\x1bPtmux;\x1b\x1b]52;c;aGk=\x07\x1b\\");
+}
+
+#[test]
+fn test_yank_keys_copy_parts_of_the_note() {
+    let config = Config::default();
+    let note = Note {
+        title: "Rust [2024]".to_string(),
+        url: Some("https://example.com/rust".to_string()),
+        path: "/vault/Rust 2024.md".into(),
+        ..Default::default()
+    };
+    let mut app = App::new(vec![note.clone()]);
+
+    let copied = |what, text: &str| AppAction::Yank { what, text: text.to_string() };
+    assert_eq!(yank(&mut app, &config, 'y'), copied("URL", "https://example.com/rust"));
+    // Only the event loop knows whether copying worked
+    assert_eq!(app.status_message, None);
+    assert_eq!(yank(&mut app, &config, 't'), copied("title", "Rust [2024]"));
+    assert_eq!(yank(&mut app, &config, 'm'), copied("Markdown link", "[Rust \\[2024\\]](https://example.com/rust)"));
+    assert_eq!(yank(&mut app, &config, 'w'), copied("wiki-link", "[[Rust 2024]]"));
+
+    let mut app = App::new(vec![Note { url: None, ..note }]);
+    assert_eq!(yank(&mut app, &config, 'y'), AppAction::Continue);
+    assert_eq!(app.status_message.as_deref(), Some("\"Rust [2024]\" has no URL"));
+}
@@ -0,0 +1,146 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use readitnow::app::App;
+use readitnow::bulk;
+use readitnow::commands::{self, Command, Setting, Target};
+use readitnow::config::{Config, ViewLayout};
+use readitnow::group::GroupBy;
+use readitnow::keybindings::{self, Action, AppAction, KeyMap};
+use readitnow::models::Note;
+use readitnow::sort::{SortDirection, SortKey};
+use readitnow::vault;
+use std::fs;
+use std::time::Duration;
+
+fn key(code: KeyCode) -> KeyEvent {
+    KeyEvent::new(code, KeyModifiers::NONE)
+}
+
+#[test]
+fn test_parse_commands() {
+    let parse = |input: &str| Command::parse(input).unwrap();
+
+    assert_eq!(parse("sort date desc"), Command::Sort(SortKey::Saved, Some(SortDirection::Desc)));
+    assert_eq!(parse(":sort reading-time"), Command::Sort(SortKey::ReadingTime, None));
+    assert_eq!(parse("filter tag:rust -is:read"), Command::Filter("tag:rust -is:read".to_string()));
+    assert_eq!(parse("open 12"), Command::Open(12));
+    assert_eq!(parse("mark-read all-on-page"), Command::MarkRead(true, Some(Target::Page)));
+    assert_eq!(parse("mark-unread"), Command::MarkRead(false, None));
+    assert_eq!(parse("mark-unread selected"), Command::MarkRead(false, Some(Target::Selected)));
+    assert_eq!(parse("set layout list"), Command::Set(Setting::Layout(ViewLayout::list())));
+    assert_eq!(parse("set layout grid 3x2"), Command::Set(Setting::Layout(ViewLayout::Grid { columns: 3, rows: 2 })));
+    assert_eq!(parse("set group-by none"), Command::Set(Setting::GroupBy(None)));
+    assert_eq!(parse("reload"), Command::Reload);
+    // Every keybinding action is a command too
+    assert_eq!(parse("toggle-read"), Command::Action(Action::ToggleRead));
+
+    // Without arguments a command that shares its name with an action runs the action
+    assert_eq!(parse("sort"), Command::Action(Action::Sort));
+
+    assert_eq!(Command::parse("open").unwrap_err(), "Usage: open <number>");
+    assert!(Command::parse("sort date sideways").is_err());
+    assert!(Command::parse("open 0").is_err());
+    assert!(Command::parse("toggle-read now").is_err());
+    assert!(Command::parse("frobnicate").unwrap_err().contains("Unknown command"));
+}
+
+#[test]
+fn test_complete_commands_and_arguments() {
+    assert_eq!(commands::complete("so"), vec!["sort "]);
+    assert!(commands::complete("").contains(&"page-down".to_string()));
+    assert_eq!(commands::complete("sort r"), vec!["sort reading-time", "sort rating", "sort random"]);
+    assert_eq!(commands::complete("sort title "), vec!["sort title asc", "sort title desc"]);
+    assert_eq!(commands::complete("set group-by d"), vec!["set group-by domain", "set group-by date"]);
+    assert!(commands::complete("filter ta").is_empty());
+}
+
+#[test]
+fn test_run_commands() {
+    let config = Config::default();
+    let notes = (0..6).map(|i| Note { title: format!("Note {}", i), ..Default::default() }).collect();
+    let mut app = App::new(notes);
+    app.set_views(&config.views);
+
+    commands::run(Command::Sort(SortKey::Title, Some(SortDirection::Asc)), &mut app, &config);
+    assert_eq!(app.view().sort.by, SortKey::Title);
+    commands::run(Command::Open(3), &mut app, &config);
+    assert_eq!(app.selected_note().unwrap().title, "Note 2");
+
+    commands::run(Command::Set(Setting::Layout(ViewLayout::list())), &mut app, &config);
+    commands::run(Command::Set(Setting::GroupBy(Some(GroupBy::Kind))), &mut app, &config);
+    assert_eq!(app.grouping(), Some(GroupBy::Kind));
+    assert_eq!(app.selected_note().unwrap().title, "Note 2");
+
+    commands::run(Command::Open(9), &mut app, &config);
+    assert_eq!(app.status_message.as_deref(), Some("There is no note 9 in this view"));
+    assert_eq!(commands::run(Command::Reload, &mut app, &config), AppAction::Reload);
+}
+
+#[test]
+fn test_mark_read_changes_notes_on_disk() {
+    let vault_dir = std::env::temp_dir().join("readitnow_commands_test");
+    let _ = fs::remove_dir_all(&vault_dir);
+    fs::create_dir_all(&vault_dir).unwrap();
+    let config = Config { vault_path: vault_dir.to_string_lossy().to_string(), ..Config::default() };
+    for name in ["A", "B", "C"] {
+        fs::write(vault_dir.join(format!("{}.md", name)), format!("[[ReadItLater]]\n# {}\n", name)).unwrap();
+    }
+    let notes = vault::scan_vault(&config).unwrap();
+    let mut app = App::new(notes);
+
+    // Marking runs as a bulk action, a few notes per step
+    commands::run(Command::MarkRead(true, Some(Target::All)), &mut app, &config);
+    assert_eq!(app.bulk.as_ref().unwrap().paths.len(), 3);
+    bulk::step(&mut app, &config, Duration::from_secs(60));
+    assert!(app.bulk.is_none());
+    assert_eq!(app.status_message.as_deref(), Some("Marked 3 notes read"));
+    assert!(app.notes.iter().all(|note| note.read));
+    assert!(fs::read_to_string(vault_dir.join("B.md")).unwrap().contains("[[readitnow/read]]"));
+
+    commands::run(Command::MarkRead(true, Some(Target::Selected)), &mut app, &config);
+    bulk::step(&mut app, &config, Duration::from_secs(60));
+    assert_eq!(app.status_message.as_deref(), Some("Marked 0 notes read"));
+
+    // An explicit `selected` leaves the marked notes alone, no target takes them
+    app.toggle_mark();
+    app.next_note();
+    commands::run(Command::MarkRead(false, Some(Target::Selected)), &mut app, &config);
+    assert_eq!(app.bulk.as_ref().unwrap().paths, vec![app.notes[1].path.clone()]);
+    bulk::step(&mut app, &config, Duration::from_secs(60));
+    commands::run(Command::MarkRead(false, None), &mut app, &config);
+    assert_eq!(app.bulk.as_ref().unwrap().paths, vec![app.notes[0].path.clone()]);
+}
+
+#[test]
+fn test_command_prompt_history_and_completion() {
+    let config = Config::default();
+    let (keymap, _) = KeyMap::new(&config.keybindings);
+    let mut app = App::new(vec![Note::default(), Note::default()]);
+    app.set_views(&config.views);
+    let type_command = |app: &mut App, text: &str| {
+        keybindings::handle_key_event(key(KeyCode::Char(':')), app, &config, &keymap);
+        for c in text.chars() {
+            keybindings::handle_key_event(key(KeyCode::Char(c)), app, &config, &keymap);
+        }
+        keybindings::handle_key_event(key(KeyCode::Enter), app, &config, &keymap)
+    };
+
+    type_command(&mut app, "sort title asc");
+    assert_eq!(app.view().sort.by, SortKey::Title);
+    assert_eq!(type_command(&mut app, "reload"), AppAction::Reload);
+    type_command(&mut app, "bogus");
+    assert_eq!(app.status_message.as_deref(), Some("Unknown command `bogus`"));
+    assert_eq!(app.command_history, vec!["sort title asc", "reload", "bogus"]);
+
+    // Up walks back through history, Tab completes one word at a time
+    keybindings::handle_key_event(key(KeyCode::Char(':')), &mut app, &config, &keymap);
+    keybindings::handle_key_event(key(KeyCode::Up), &mut app, &config, &keymap);
+    keybindings::handle_key_event(key(KeyCode::Up), &mut app, &config, &keymap);
+    assert_eq!(app.prompt.as_ref().unwrap().input, "reload");
+    keybindings::handle_key_event(key(KeyCode::Esc), &mut app, &config, &keymap);
+
+    keybindings::handle_key_event(key(KeyCode::Char(':')), &mut app, &config, &keymap);
+    for code in [KeyCode::Char('s'), KeyCode::Char('e'), KeyCode::Char('t'), KeyCode::Tab, KeyCode::Tab, KeyCode::Tab] {
+        keybindings::handle_key_event(key(code), &mut app, &config, &keymap);
+    }
+    assert_eq!(app.prompt.as_ref().unwrap().input, "set hide-read");
+}
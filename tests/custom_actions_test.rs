@@ -0,0 +1,78 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use readitnow::app::App;
+use readitnow::config::{Config, CustomAction};
+use readitnow::keybindings::{self, Action, AppAction, KeyMap};
+use readitnow::models::Note;
+use std::path::Path;
+
+fn actions() -> Vec<CustomAction> {
+    serde_yaml::from_str("
+- name: mpv
+  key: X
+  command: mpv --title={title} {url}
+- name: say
+  key: [g s, Y]
+  command: say-it '{path}' --tags {tags}
+  suspend: true
+  reload: true
+- name: clash
+  key: r
+  command: echo
+").unwrap()
+}
+
+fn note(url: Option<&str>) -> Note {
+    Note {
+        title: "A talk".to_string(),
+        url: url.map(str::to_string),
+        path: "/vault/A talk.md".into(),
+        tags: vec!["video".to_string(), "rust".to_string()],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_custom_keys_fill_in_the_selected_note() {
+    let config = Config { actions: actions(), ..Config::default() };
+    let (keymap, problems) = KeyMap::with_actions(&config.keybindings, &config.actions);
+    assert_eq!(problems, ["`r` is bound to both toggle_read and clash"]);
+    assert_eq!(keymap.keys(Action::Custom(1)), ["Y", "gs"]);
+
+    let mut app = App::new(vec![note(Some("https://example.com/talk"))]);
+    let action = keybindings::handle_key_event(KeyEvent::new(KeyCode::Char('X'), KeyModifiers::SHIFT), &mut app, &config, &keymap);
+    let AppAction::Run(external) = action else { panic!("expected a command, got {:?}", action) };
+    assert_eq!((external.program.as_str(), external.args.as_slice()), ("mpv", ["--title=A talk".to_string(), "https://example.com/talk".to_string()].as_slice()));
+    assert!(!external.suspend && external.reload.is_none());
+    assert_eq!(app.status_message.as_deref(), Some("Ran mpv on \"A talk\""));
+
+    let action = keybindings::handle_key_event(KeyEvent::new(KeyCode::Char('Y'), KeyModifiers::SHIFT), &mut app, &config, &keymap);
+    let AppAction::Run(external) = action else { panic!("expected a command, got {:?}", action) };
+    assert_eq!(external.args, ["/vault/A talk.md", "--tags", "video,rust"]);
+    assert!(external.suspend);
+    assert_eq!(external.reload.as_deref(), Some(Path::new("/vault/A talk.md")));
+
+    // No URL to fill in, nothing runs
+    let mut app = App::new(vec![note(None)]);
+    let action = keybindings::handle_key_event(KeyEvent::new(KeyCode::Char('X'), KeyModifiers::SHIFT), &mut app, &config, &keymap);
+    assert_eq!(action, AppAction::Continue);
+    assert_eq!(app.status_message.as_deref(), Some("\"A talk\" has no URL for mpv"));
+}
+
+#[test]
+fn test_help_lists_custom_actions() {
+    let config = Config { actions: actions(), ..Config::default() };
+    let (keymap, _) = KeyMap::with_actions(&config.keybindings, &config.actions);
+    let mut app = App::new(vec![note(None)]);
+    keybindings::handle_key_event(KeyEvent::new(KeyCode::Char('?'), KeyModifiers::NONE), &mut app, &config, &keymap);
+    let panel = app.help.as_ref().unwrap();
+    assert!(panel.entries.contains(&("ctrl+r".to_string(), "redo".to_string())));
+    assert_eq!(panel.entries.last().unwrap(), &("r".to_string(), "clash: echo".to_string()));
+
+    // Other keys only scroll while it is open
+    keybindings::handle_key_event(KeyEvent::new(KeyCode::PageDown, KeyModifiers::NONE), &mut app, &config, &keymap);
+    keybindings::handle_key_event(KeyEvent::new(KeyCode::Char('r'), KeyModifiers::NONE), &mut app, &config, &keymap);
+    assert_eq!(app.help.as_ref().unwrap().scroll, 10);
+    assert!(!app.notes[0].read);
+    keybindings::handle_key_event(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE), &mut app, &config, &keymap);
+    assert!(app.help.is_none());
+}
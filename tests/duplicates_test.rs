@@ -0,0 +1,111 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use readitnow::app::App;
+use readitnow::config::Config;
+use readitnow::duplicates::find_duplicates;
+use readitnow::keybindings::{self, KeyMap};
+use readitnow::models::Note;
+use readitnow::urls::canonical_url;
+use readitnow::vault::{self, VaultChange};
+use std::fs;
+
+#[test]
+fn test_canonical_url_normalization() {
+    let canonical = |url: &str| canonical_url(url).unwrap();
+
+    assert_eq!(canonical("http://WWW.Example.com/post/?utm_source=x&id=3&ref_src=twsrc#top"), "https://example.com/post?id=3");
+    assert_eq!(canonical("https://m.example.com"), "https://example.com");
+    assert_eq!(canonical("https://youtu.be/abc123?si=share"), "https://youtube.com/watch?v=abc123");
+    assert_eq!(canonical("https://m.youtube.com/watch?v=abc123&t=42s"), "https://youtube.com/watch?v=abc123");
+    assert_eq!(canonical("https://www.youtube.com/shorts/abc123"), "https://youtube.com/watch?v=abc123");
+    assert_eq!(canonical("https://mobile.twitter.com/someone/status/42?s=20"), "https://twitter.com/i/status/42");
+    assert_eq!(canonical("https://x.com/renamed/status/42"), "https://twitter.com/i/status/42");
+    assert!(canonical_url("not a url").is_none());
+}
+
+#[test]
+fn test_find_duplicates_groups_oldest_first() {
+    let note = |title: &str, url: &str, created: Option<&str>| Note {
+        title: title.to_string(),
+        url: Some(url.to_string()),
+        created: created.and_then(readitnow::models::parse_date),
+        ..Default::default()
+    };
+    let notes = vec![
+        note("newer", "https://youtu.be/abc", Some("2024-05-01")),
+        note("unique", "https://example.com/a", None),
+        note("undated", "https://www.youtube.com/watch?v=abc", None),
+        note("older", "https://youtube.com/watch?v=abc&utm_medium=social", Some("2024-01-01")),
+    ];
+
+    assert_eq!(find_duplicates(&notes), vec![vec![3, 0, 2]]);
+}
+
+#[test]
+fn test_merge_and_trash_duplicates() {
+    let vault_dir = std::env::temp_dir().join("readitnow_duplicates_test");
+    let _ = fs::remove_dir_all(&vault_dir);
+    fs::create_dir_all(&vault_dir).unwrap();
+    let config = Config { vault_path: vault_dir.to_string_lossy().to_string(), ..Config::default() };
+
+    fs::write(vault_dir.join("Keep.md"), "[[ReadItLater]]\n# [Post](https://example.com/post)\n").unwrap();
+    fs::write(vault_dir.join("Extra.md"), "[[ReadItLater]] [[infra]]\n# [Post](https://example.com/post?utm_source=x)\n[[readitnow/read]]").unwrap();
+    let load = |name: &str| vault::load_note(&vault_dir.join(name), std::time::SystemTime::now(), &config).unwrap();
+    let mut keeper = load("Keep.md");
+    let extra = load("Extra.md");
+
+    vault::merge_tags(&mut keeper, &[&extra], &config).unwrap();
+    assert!(keeper.read);
+    assert!(fs::read_to_string(vault_dir.join("Keep.md")).unwrap().contains("[[infra]] [[readitnow/read]]"));
+
+    let trashed = vault::trash_note(&extra, &config).unwrap();
+    assert_eq!(trashed, vault_dir.join(".trash").join("Extra.md"));
+    assert!(!vault_dir.join("Extra.md").exists());
+
+    // A second note with the same name doesn't overwrite the first in the trash
+    fs::write(vault_dir.join("Extra.md"), "again").unwrap();
+    assert_eq!(vault::trash_note(&extra, &config).unwrap(), vault_dir.join(".trash").join("Extra (1).md"));
+}
+
+#[test]
+fn test_duplicates_panel_follows_vault_changes() {
+    let note = |path: &str| Note { url: Some("https://example.com/post".to_string()), path: path.into(), ..Default::default() };
+    let mut app = App::new(vec![note("a.md"), note("b.md"), note("c.md")]);
+
+    app.open_duplicates();
+    app.next_duplicate();
+    app.next_duplicate();
+    assert_eq!(app.duplicates.as_ref().unwrap().selected_note().unwrap().1, 2);
+
+    app.apply_vault_changes(vec![VaultChange::Removed("c.md".into())]);
+    assert_eq!(app.duplicates.as_ref().unwrap().selected, 1);
+
+    app.apply_vault_changes(vec![VaultChange::Removed("b.md".into())]);
+    assert!(app.duplicates.is_none());
+}
+
+#[test]
+fn test_merge_reports_duplicates_that_couldnt_be_trashed() {
+    let vault_dir = std::env::temp_dir().join("readitnow_merge_failure_test");
+    let _ = fs::remove_dir_all(&vault_dir);
+    fs::create_dir_all(&vault_dir).unwrap();
+    for title in ["A", "B", "C"] {
+        fs::write(vault_dir.join(format!("{}.md", title)), "# [Post](https://example.com/post)\n").unwrap();
+    }
+    let config = Config { vault_path: vault_dir.to_string_lossy().to_string(), ..Config::default() };
+    let (keymap, _) = KeyMap::new(&config.keybindings);
+    let mut app = App::new(vault::scan_vault(&config).unwrap());
+    app.open_duplicates();
+
+    // One of the notes to trash is gone from disk by the time they are merged
+    let (group, selected) = app.duplicates.as_ref().unwrap().selected_note().map(|(group, selected)| (group.to_vec(), selected)).unwrap();
+    let gone = group.into_iter().find(|&i| i != selected).unwrap();
+    let gone_title = app.notes[gone].title.clone();
+    fs::remove_file(&app.notes[gone].path).unwrap();
+    keybindings::handle_key_event(KeyEvent::new(KeyCode::Char('m'), KeyModifiers::NONE), &mut app, &config, &keymap);
+
+    let message = app.status_message.clone().unwrap();
+    let expected = format!("Merged 2 duplicates, 1 couldn't be trashed: \"{}\" (", gone_title);
+    assert!(message.starts_with(&expected), "{}", message);
+    assert_eq!(vault_dir.join(".trash").read_dir().unwrap().count(), 1);
+    fs::remove_dir_all(&vault_dir).unwrap();
+}
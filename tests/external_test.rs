@@ -0,0 +1,43 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use readitnow::app::App;
+use readitnow::config::Config;
+use readitnow::external::{self, External};
+use readitnow::keybindings::{self, AppAction, KeyMap};
+use readitnow::models::Note;
+use std::path::Path;
+
+#[test]
+fn test_templates_keep_values_in_one_argument() {
+    let external = External::from_template("nvim '+call cursor({line}, 1)' {path}", &[("path", "/notes/My Note.md"), ("line", "3")]).unwrap();
+    assert_eq!(external.program, "nvim");
+    assert_eq!(external.args, ["+call cursor(3, 1)", "/notes/My Note.md"]);
+    assert!(External::from_template("  ", &[]).is_none());
+
+    // Values are filled in once, placeholders in them stay as typed
+    let external = External::from_template("say {title} {tags} {other}", &[("title", "On {tags} {"), ("tags", "rust")]).unwrap();
+    assert_eq!(external.args, ["On {tags} {", "rust", "{other}"]);
+
+    // A template without `{path}` gets it at the end
+    let config = Config { editor: Some("code --wait".to_string()), ..Config::default() };
+    let editor = external::editor_command(&config, Path::new("/notes/a.md"), 1).unwrap();
+    assert_eq!(editor.args, ["--wait", "/notes/a.md"]);
+    assert!(editor.suspend);
+    assert_eq!(editor.reload.as_deref(), Some(Path::new("/notes/a.md")));
+}
+
+#[test]
+fn test_editor_opens_at_the_search_match() {
+    let content = "# Title\n\nSome intro\nThe Rust borrow checker\n";
+    assert_eq!(external::match_line(content, Some("rust")), 4);
+    assert_eq!(external::match_line(content, Some("nowhere")), 1);
+    assert_eq!(external::match_line(content, None), 1);
+
+    let config = Config { editor: Some("hx {path}:{line}".to_string()), ..Config::default() };
+    let note = Note { title: "Note".to_string(), path: "/nonexistent/Note.md".into(), ..Default::default() };
+    let mut app = App::new(vec![note]);
+    let (keymap, _) = KeyMap::new(&config.keybindings);
+    let action = keybindings::handle_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::SHIFT), &mut app, &config, &keymap);
+    let AppAction::Run(editor) = action else { panic!("expected the editor, got {:?}", action) };
+    assert_eq!(editor.program, "hx");
+    assert_eq!(editor.args, ["/nonexistent/Note.md:1"]);
+}
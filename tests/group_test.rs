@@ -0,0 +1,135 @@
+use chrono::NaiveDate;
+use ratatui::{backend::TestBackend, Terminal};
+use readitnow::app::{App, Row};
+use readitnow::config::{CardConfig, ViewConfig};
+use readitnow::group::{group_notes, GroupBy};
+use readitnow::models::Note;
+
+fn note(title: &str, url: Option<&str>, tags: &[&str]) -> Note {
+    Note {
+        title: title.to_string(),
+        url: url.map(str::to_string),
+        tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        ..Default::default()
+    }
+}
+
+fn notes() -> Vec<Note> {
+    vec![
+        note("b1", Some("https://b.example.com/1"), &["ReadItLater", "rust"]),
+        note("plain", None, &[]),
+        note("a1", Some("https://a.example.com/1"), &["go"]),
+        note("b2", Some("https://www.b.example.com/2"), &["rust"]),
+        note("video", Some("https://youtu.be/abc"), &[]),
+    ]
+}
+
+fn grouped_app() -> App {
+    let mut app = App::new(notes());
+    app.card.hidden_tags = vec!["ReadItLater".to_string()];
+    let views: Vec<ViewConfig> = serde_yaml::from_str("
+- name: By domain
+  sort: { by: title, order: asc }
+  layout: { type: list, rows: 4 }
+  group_by: domain
+").unwrap();
+    app.set_views(&views);
+    app
+}
+
+#[test]
+fn test_group_notes_by_domain_kind_and_tag() {
+    let notes = notes();
+    let card = CardConfig { hidden_tags: vec!["ReadItLater".to_string()], ..CardConfig::default() };
+    let today = NaiveDate::from_ymd_opt(2024, 6, 15).unwrap();
+    let labels = |by| -> Vec<(String, Vec<usize>)> {
+        group_notes(&notes, &[0, 1, 2, 3, 4], by, &card, today).into_iter().map(|group| (group.label, group.notes)).collect()
+    };
+
+    // First appearance order, notes without a domain last
+    assert_eq!(labels(GroupBy::Domain), vec![
+        ("b.example.com".to_string(), vec![0, 3]),
+        ("a.example.com".to_string(), vec![2]),
+        ("youtu.be".to_string(), vec![4]),
+        ("No domain".to_string(), vec![1]),
+    ]);
+    assert_eq!(labels(GroupBy::Kind)[0], ("Articles".to_string(), vec![0, 2, 3]));
+    assert_eq!(labels(GroupBy::Tag)[0], ("rust".to_string(), vec![0, 3]));
+    assert_eq!(labels(GroupBy::Tag).last().unwrap().0, "Untagged");
+}
+
+#[test]
+fn test_group_notes_by_date_bucket() {
+    // A Saturday
+    let today = NaiveDate::from_ymd_opt(2024, 6, 15).unwrap();
+    let saved = |date: Option<&str>| Note { created: date.and_then(readitnow::models::parse_date), ..Default::default() };
+    let notes = vec![
+        saved(Some("2024-05-30")),
+        saved(Some("2024-06-10")),
+        saved(None),
+        saved(Some("2024-06-15")),
+        saved(Some("2024-06-09")),
+    ];
+    let groups = group_notes(&notes, &[0, 1, 2, 3, 4], GroupBy::Date, &CardConfig::default(), today);
+    let labels: Vec<&str> = groups.iter().map(|group| group.label.as_str()).collect();
+    assert_eq!(labels, vec!["Today", "This week", "This month", "Older", "Undated"]);
+    assert_eq!(groups[2].notes, vec![4]);
+}
+
+#[test]
+fn test_collapsing_groups_and_navigating_rows() {
+    let mut app = grouped_app();
+    assert_eq!(app.grouping(), Some(GroupBy::Domain));
+    assert_eq!(app.rows[0], Row::Header { label: "a.example.com".to_string(), count: 1, collapsed: false });
+    assert_eq!(app.rows.len(), 9);
+    assert_eq!(app.total_pages(), 3);
+    assert!(app.selected_note().is_none());
+
+    app.next_row();
+    assert_eq!(app.selected_note().unwrap().title, "a1");
+
+    // Folding from a note folds its group and selects the header
+    app.next_row();
+    app.next_row();
+    app.toggle_group();
+    assert_eq!(app.selected_position(), 2);
+    assert_eq!(app.rows[2], Row::Header { label: "b.example.com".to_string(), count: 2, collapsed: true });
+    assert_eq!(app.rows.len(), 7);
+    assert_eq!(app.visible.len(), 5);
+
+    // Selecting a folded note unfolds its group
+    assert!(app.select_note(3));
+    assert_eq!(app.selected_note().unwrap().title, "b2");
+    assert_eq!(app.rows.len(), 9);
+
+    app.set_group_by(None);
+    assert_eq!(app.selected_note().unwrap().title, "b2");
+    assert_eq!(app.rows.len(), 5);
+}
+
+#[test]
+fn test_grids_are_never_grouped() {
+    let mut app = App::new(notes());
+    app.cycle_group_by();
+    assert!(app.status_message.is_some());
+    assert_eq!(app.grouping(), None);
+    assert!(app.rows.iter().all(|row| matches!(row, Row::Note(_))));
+}
+
+#[test]
+fn test_group_headers_are_rendered() {
+    let mut app = grouped_app();
+    app.next_row();
+    app.toggle_group();
+    let mut terminal = Terminal::new(TestBackend::new(60, 30)).unwrap();
+
+    terminal.draw(|frame| readitnow::ui::Renderer::new().render_app(&mut app, frame)).unwrap();
+
+    let buffer = terminal.backend().buffer();
+    let rows: Vec<String> = (0..buffer.area.height)
+        .map(|y| (0..buffer.area.width).map(|x| buffer[(x, y)].symbol()).collect())
+        .collect();
+    assert!(rows.iter().any(|row| row.contains("▸ a.example.com (1)")));
+    assert!(rows.iter().any(|row| row.contains("▾ b.example.com (2)")));
+    assert!(rows.iter().any(|row| row.contains("by domain")));
+}
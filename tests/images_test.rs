@@ -0,0 +1,120 @@
+use ratatui_image::picker::ProtocolType;
+use image::DynamicImage;
+use ratatui::{backend::TestBackend, Terminal};
+use readitnow::app::App;
+use readitnow::models::Note;
+use readitnow::ui::Renderer;
+use ratatui_image::picker::Picker;
+use readitnow::images::{detect_picker, ImageCache, ImageConfig, ImageKey, ImageProtocol, ImageStatus};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+fn key(name: &str) -> ImageKey {
+    ImageKey { path: PathBuf::from(name), url: None, greyscale: false, width: 10, height: 5 }
+}
+
+fn cache_with_limits(cache_max_entries: usize, cache_max_bytes: usize) -> ImageCache {
+    let config = ImageConfig { cache_max_entries, cache_max_bytes, ..ImageConfig::default() };
+    ImageCache::new(Some(Picker::new((1, 1))), &config)
+}
+
+fn insert(cache: &mut ImageCache, name: &str, bytes: usize) {
+    let protocol = Picker::new((1, 1)).new_resize_protocol(DynamicImage::new_rgb8(1, 1));
+    cache.insert(key(name), protocol, bytes);
+}
+
+#[test]
+fn test_images_can_be_disabled() {
+    let config = ImageConfig { protocol: ImageProtocol::None, ..ImageConfig::default() };
+    assert!(detect_picker(&config).is_none());
+}
+
+#[test]
+fn test_forced_protocol_and_font_size_skip_detection() {
+    let config = ImageConfig { protocol: ImageProtocol::Sixel, font_size: Some((9, 18)), ..ImageConfig::default() };
+    let picker = detect_picker(&config).unwrap();
+
+    assert_eq!(picker.protocol_type, ProtocolType::Sixel);
+    assert_eq!(picker.font_size, (9, 18));
+}
+
+#[test]
+fn test_image_config_parses_from_yaml() {
+    let config: ImageConfig = serde_yaml::from_str("protocol: halfblocks\nfont_size: [7, 14]\n").unwrap();
+    assert_eq!(config.protocol, ImageProtocol::Halfblocks);
+    assert_eq!(config.font_size, Some((7, 14)));
+}
+
+#[test]
+fn test_image_cache_evicts_least_recently_used_entry() {
+    let mut cache = cache_with_limits(2, 0);
+    insert(&mut cache, "a.jpeg", 10);
+    insert(&mut cache, "b.jpeg", 10);
+    assert!(matches!(cache.get_or_request(&key("a.jpeg")), ImageStatus::Ready(_)));
+
+    insert(&mut cache, "c.jpeg", 10);
+    assert_eq!(cache.len(), 2);
+    assert_eq!(cache.bytes(), 20);
+    assert!(matches!(cache.get_or_request(&key("a.jpeg")), ImageStatus::Ready(_)));
+    assert!(matches!(cache.get_or_request(&key("c.jpeg")), ImageStatus::Ready(_)));
+    assert!(matches!(cache.get_or_request(&key("b.jpeg")), ImageStatus::Loading));
+}
+
+#[test]
+fn test_image_cache_respects_byte_limit() {
+    let mut cache = cache_with_limits(0, 100);
+    insert(&mut cache, "a.jpeg", 60);
+    insert(&mut cache, "b.jpeg", 30);
+    insert(&mut cache, "c.jpeg", 30);
+
+    assert_eq!(cache.len(), 2);
+    assert_eq!(cache.bytes(), 60);
+
+    cache.invalidate(&PathBuf::from("c.jpeg"));
+    assert_eq!(cache.len(), 1);
+    assert_eq!(cache.bytes(), 30);
+}
+
+#[test]
+fn test_missing_image_is_reported_as_failed() {
+    let mut cache = cache_with_limits(4, 0);
+    assert!(matches!(cache.get_or_request(&key("/nonexistent/thumb.jpeg")), ImageStatus::Loading));
+
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+    while !cache.receive() && std::time::Instant::now() < deadline {
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    assert!(matches!(cache.get_or_request(&key("/nonexistent/thumb.jpeg")), ImageStatus::Failed));
+}
+
+#[test]
+fn test_prefetched_thumbnails_are_hits_on_the_next_page() {
+    let dir = std::env::temp_dir().join("readitnow_prefetch_test");
+    std::fs::create_dir_all(&dir).unwrap();
+    let notes = (0..8)
+        .map(|i| {
+            let path = dir.join(format!("{}.png", i));
+            DynamicImage::new_rgb8(8, 8).save(&path).unwrap();
+            Note { title: format!("Note {}", i), thumbnail: Some(path.to_string_lossy().to_string()), ..Default::default() }
+        })
+        .collect();
+    let mut app = App::new(notes);
+    app.image_cache = cache_with_limits(0, 0);
+    let mut renderer = Renderer::new();
+    let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+
+    // The first page and the prefetched second one, 4 thumbnails each
+    terminal.draw(|frame| renderer.render_app(&mut app, frame)).unwrap();
+    let started = Instant::now();
+    while app.image_cache.len() < 8 && started.elapsed() < Duration::from_secs(10) {
+        app.image_cache.receive();
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(app.image_cache.len(), 8);
+
+    app.next_page();
+    terminal.draw(|frame| renderer.render_app(&mut app, frame)).unwrap();
+    let screen: String = terminal.backend().buffer().content().iter().map(|cell| cell.symbol()).collect();
+    assert!(!screen.contains("Loading"), "{}", screen);
+    assert_eq!(app.image_cache.len(), 8);
+}
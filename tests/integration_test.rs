@@ -1,6 +1,9 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::{backend::TestBackend, Terminal};
 
 use readitnow::app::App;
+use readitnow::config::{Config, ViewConfig};
+use readitnow::keybindings::{self, KeyMap};
 use readitnow::models::Note;
 
 #[test]
@@ -20,20 +23,22 @@ fn test_app_navigation() {
     app.next_note();
     assert_eq!(app.selected_note_index, 2);
 
+    // The last note stays selected, there is no page after it
     app.next_note();
-    assert_eq!(app.selected_note_index, 0);
-
-    app.previous_note();
     assert_eq!(app.selected_note_index, 2);
 
     app.previous_note();
     assert_eq!(app.selected_note_index, 1);
 
+    app.previous_note();
+    app.previous_note();
+    assert_eq!(app.selected_note_index, 0);
+
     app.next_page();
-    assert_eq!(app.selected_note_index, 1);
+    assert_eq!(app.selected_note_index, 0);
 
     app.previous_page();
-    assert_eq!(app.selected_note_index, 1);
+    assert_eq!(app.selected_note_index, 0);
 }
 
 #[test]
@@ -61,3 +66,156 @@ fn test_ui_rendering() {
     }).unwrap();
 }
 
+
+#[test]
+fn test_grid_navigation_crosses_pages() {
+    let notes = (0..10).map(|i| Note { title: format!("Note {}", i), ..Default::default() }).collect();
+    let mut app = App::new(notes);
+    let views: Vec<ViewConfig> = serde_yaml::from_str("
+- name: Wide
+  layout: { type: grid, columns: 3, rows: 2 }
+").unwrap();
+    app.set_views(&views);
+    let at = |app: &App| (app.current_page, app.selected_cell().row, app.selected_cell().col);
+
+    // Right reads on to the next row, down from the bottom row turns the page
+    (0..3).for_each(|_| app.next_note());
+    assert_eq!(at(&app), (0, 1, 0));
+    app.next_row();
+    assert_eq!(at(&app), (1, 0, 0));
+
+    // The last page has 4 cards, the cell below the third one is past them
+    app.next_note();
+    app.next_note();
+    app.next_row();
+    assert_eq!(at(&app), (1, 1, 0));
+    app.next_note();
+    assert_eq!(at(&app), (1, 1, 0));
+
+    app.previous_row();
+    app.previous_row();
+    assert_eq!(at(&app), (0, 1, 0));
+    app.next_row();
+    app.previous_note();
+    assert_eq!(at(&app), (0, 1, 2));
+    app.next_page();
+    assert_eq!(at(&app), (1, 1, 0));
+
+    let config = Config::default();
+    let (keymap, _) = KeyMap::new(&config.keybindings);
+    keybindings::handle_key_event(KeyEvent::new(KeyCode::Home, KeyModifiers::NONE), &mut app, &config, &keymap);
+    assert_eq!(at(&app), (0, 0, 0));
+    keybindings::handle_key_event(KeyEvent::new(KeyCode::End, KeyModifiers::NONE), &mut app, &config, &keymap);
+    assert_eq!(app.selected_note().unwrap().title, "Note 9");
+}
+
+#[test]
+fn test_hide_read_removes_notes_from_pagination() {
+    let mut notes: Vec<Note> = (0..6)
+        .map(|i| Note { title: format!("Note {}", i), ..Default::default() })
+        .collect();
+    notes[1].read = true;
+    notes[4].archived = true;
+    let mut app = App::new(notes);
+    assert_eq!(app.total_pages(), 2);
+
+    app.next_page();
+    app.next_note();
+    assert_eq!(app.selected_note().unwrap().title, "Note 5");
+
+    app.toggle_hide_read();
+    assert_eq!(app.visible, vec![0, 2, 3, 5]);
+    assert_eq!(app.total_pages(), 1);
+    assert_eq!(app.current_page, 0);
+    assert!(app.notes_on_current_page().iter().all(|note| !note.is_done()));
+
+    app.toggle_hide_read();
+    assert_eq!(app.visible.len(), 6);
+}
+
+#[test]
+fn test_card_footer_hides_noise_tags_and_truncates() {
+    let backend = TestBackend::new(60, 20);
+    let mut terminal = Terminal::new(backend).unwrap();
+
+    let mock_notes = vec![Note {
+        title: "Footer".to_string(),
+        tags: vec!["ReadItLater".to_string(), "infra".to_string(), "readitnow/read".to_string()],
+        url: Some("https://www.example.com/a-very-long-path".to_string()),
+        word_count: 2300,
+        ..Default::default()
+    }];
+    let mut app = App::new(mock_notes);
+
+    terminal.draw(|frame| {
+        let mut renderer = readitnow::ui::Renderer::new();
+        renderer.render_app(&mut app, frame);
+    }).unwrap();
+
+    let buffer = terminal.backend().buffer();
+    let rows: Vec<String> = (0..buffer.area.height)
+        .map(|y| (0..buffer.area.width).map(|x| buffer[(x, y)].symbol()).collect())
+        .collect();
+    let footer = rows.iter().find(|row| row.contains("infra")).expect("footer row");
+
+    assert!(!footer.contains("ReadItLater"));
+    assert!(!footer.contains("readitnow/read"));
+    assert!(footer.contains("…"));
+}
+
+#[test]
+fn test_views_keep_their_own_page_and_selection() {
+    let mut notes: Vec<Note> = (0..10)
+        .map(|i| Note { title: format!("Note {}", i), ..Default::default() })
+        .collect();
+    notes[3].url = Some("https://youtu.be/abc".to_string());
+    notes[7].url = Some("https://www.youtube.com/watch?v=def".to_string());
+    let mut app = App::new(notes);
+
+    let views: Vec<ViewConfig> = serde_yaml::from_str("
+- name: All
+- name: Videos
+  filter: kind:video
+  sort: { by: title, order: asc }
+- name: List
+  layout: { type: list, rows: 5 }
+").unwrap();
+    app.set_views(&views);
+    assert_eq!(app.views.len(), 3);
+
+    app.next_page();
+    app.next_note();
+    assert_eq!(app.selected_note().unwrap().title, "Note 5");
+
+    app.switch_view(1);
+    assert_eq!(app.visible, vec![3, 7]);
+    app.next_note();
+    assert_eq!(app.selected_note().unwrap().title, "Note 7");
+
+    app.switch_view(2);
+    assert_eq!(app.total_pages(), 2);
+    assert_eq!(app.selected_position(), 0);
+
+    app.switch_view(0);
+    assert_eq!(app.selected_note().unwrap().title, "Note 5");
+    app.switch_view(1);
+    assert_eq!(app.selected_note().unwrap().title, "Note 7");
+}
+
+#[test]
+fn test_view_tabs_are_rendered() {
+    let backend = TestBackend::new(80, 20);
+    let mut terminal = Terminal::new(backend).unwrap();
+    let mut app = App::new(vec![Note::default()]);
+    app.set_views(&Config::default().views);
+
+    terminal.draw(|frame| {
+        let mut renderer = readitnow::ui::Renderer::new();
+        renderer.render_app(&mut app, frame);
+    }).unwrap();
+
+    let buffer = terminal.backend().buffer();
+    let tabs: String = (0..buffer.area.width).map(|x| buffer[(x, 0)].symbol()).collect();
+    assert!(tabs.contains("1 All"));
+    assert!(tabs.contains("2 Inbox"));
+}
@@ -0,0 +1,137 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use readitnow::app::App;
+use readitnow::bulk::{self, BulkAction};
+use readitnow::config::Config;
+use readitnow::journal::{self, Journal, Operation, UndoConfig};
+use readitnow::keybindings::{self, KeyMap};
+use readitnow::vault;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+fn press(app: &mut App, config: &Config, code: KeyCode, modifiers: KeyModifiers) {
+    let (keymap, _) = KeyMap::new(&config.keybindings);
+    keybindings::handle_key_event(KeyEvent::new(code, modifiers), app, config, &keymap);
+}
+
+fn temp_vault(name: &str, notes: &[&str]) -> (PathBuf, Config) {
+    let vault_dir = std::env::temp_dir().join(name);
+    let _ = fs::remove_dir_all(&vault_dir);
+    fs::create_dir_all(&vault_dir).unwrap();
+    for title in notes {
+        fs::write(vault_dir.join(format!("{}.md", title)), format!("[[ReadItLater]]\n# [{}](https://example.com/{})\n", title, title)).unwrap();
+    }
+    let config = Config {
+        vault_path: vault_dir.to_string_lossy().to_string(),
+        index_cache: vault_dir.join(".cache").to_string_lossy().to_string(),
+        ..Config::default()
+    };
+    (vault_dir, config)
+}
+
+#[test]
+fn test_edit_keeps_only_the_changed_part() {
+    let operation = Operation::edit("a.md".as_ref(), "# Café\n[[x]] tail", "# Cafë\n tail");
+    let Operation::Edit { start, removed, inserted, .. } = operation else { panic!("not an edit") };
+    assert_eq!(start, 5);
+    assert_eq!(removed, "é\n[[x]]");
+    assert_eq!(inserted, "ë\n");
+
+    let mut journal = Journal::new(2);
+    for label in ["a", "b", "c"] {
+        journal.record(label, vec![Operation::Move { from: "x".into(), to: "y".into() }]);
+    }
+    journal.record("nothing", Vec::new());
+    assert_eq!(journal.undo.iter().map(|entry| entry.label.as_str()).collect::<Vec<_>>(), ["b", "c"]);
+}
+
+#[test]
+fn test_undo_and_redo_toggling_read() {
+    let (vault_dir, config) = temp_vault("readitnow_journal_test", &["A"]);
+    let note_path = vault_dir.join("A.md");
+    let original = fs::read_to_string(&note_path).unwrap();
+    let mut app = App::new(vault::scan_vault(&config).unwrap());
+    let none = KeyModifiers::NONE;
+
+    press(&mut app, &config, KeyCode::Char('r'), none);
+    assert!(app.notes[0].read);
+    press(&mut app, &config, KeyCode::Char('u'), none);
+    assert_eq!(app.status_message.as_deref(), Some("Undid marking \"A\" read"));
+    assert_eq!(fs::read_to_string(&note_path).unwrap(), original);
+    assert!(!app.notes[0].read);
+
+    press(&mut app, &config, KeyCode::Char('r'), KeyModifiers::CONTROL);
+    assert!(app.notes[0].read);
+    press(&mut app, &config, KeyCode::Char('r'), KeyModifiers::CONTROL);
+    assert_eq!(app.status_message.as_deref(), Some("Nothing to redo"));
+
+    // A file changed outside the app since is left alone
+    fs::write(&note_path, "edited elsewhere").unwrap();
+    press(&mut app, &config, KeyCode::Char('u'), none);
+    assert_eq!(app.status_message.as_deref(), Some("Can't undo marking \"A\" read: \"A.md\" was changed since"));
+    assert_eq!(fs::read_to_string(&note_path).unwrap(), "edited elsewhere");
+    assert_eq!(app.journal.undo.len(), 1);
+}
+
+#[test]
+fn test_undo_puts_back_what_it_applied_when_a_file_fails() {
+    let (vault_dir, config) = temp_vault("readitnow_partial_undo_test", &[]);
+    let (a, b) = (vault_dir.join("A.md"), vault_dir.join("B.md"));
+    fs::write(&a, "a after").unwrap();
+    fs::write(&b, "b after").unwrap();
+    // Both files are as recorded, but A's change no longer lines up with its content
+    let mut broken = Operation::edit(&a, "a before", "a after");
+    if let Operation::Edit { start, .. } = &mut broken {
+        *start = 100;
+    }
+    let mut app = App::new(Vec::new());
+    app.journal.record("editing A and B", vec![broken, Operation::edit(&b, "b before", "b after")]);
+
+    // Undo goes newest first, so B is changed before A fails
+    journal::undo(&mut app, &config);
+    assert_eq!(app.status_message.as_deref(), Some("Can't undo editing A and B: the change doesn't apply to \"A.md\""));
+    assert_eq!(fs::read_to_string(&a).unwrap(), "a after");
+    assert_eq!(fs::read_to_string(&b).unwrap(), "b after");
+    assert_eq!(app.journal.undo.len(), 1);
+    assert!(app.journal.redo.is_empty());
+    fs::remove_dir_all(&vault_dir).unwrap();
+}
+
+#[test]
+fn test_unarchiving_leaves_the_file_as_it_was() {
+    let (vault_dir, config) = temp_vault("readitnow_unarchive_test", &["A"]);
+    let note_path = vault_dir.join("A.md");
+    let original = fs::read_to_string(&note_path).unwrap();
+    let mut app = App::new(vault::scan_vault(&config).unwrap());
+
+    for _ in 0..2 {
+        press(&mut app, &config, KeyCode::Char('d'), KeyModifiers::NONE);
+        press(&mut app, &config, KeyCode::Char('d'), KeyModifiers::NONE);
+    }
+    assert!(!app.notes[0].archived);
+    assert_eq!(fs::read_to_string(&note_path).unwrap(), original);
+    fs::remove_dir_all(&vault_dir).unwrap();
+}
+
+#[test]
+fn test_undo_bulk_trash_and_persist() {
+    let (vault_dir, config) = temp_vault("readitnow_journal_trash_test", &["A", "B", "C"]);
+    let mut app = App::new(vault::scan_vault(&config).unwrap());
+    let paths: Vec<PathBuf> = app.notes.iter().take(2).map(|note| note.path.clone()).collect();
+    app.start_bulk(BulkAction::Trash, paths.clone());
+    bulk::step(&mut app, &config, Duration::from_secs(60));
+    assert_eq!(app.notes.len(), 1);
+
+    // The whole bulk action comes back at once
+    press(&mut app, &config, KeyCode::Char('u'), KeyModifiers::NONE);
+    assert_eq!(app.status_message.as_deref(), Some("Undid trashing 2 notes"));
+    assert_eq!(app.notes.len(), 3);
+    assert!(paths.iter().all(|path| path.exists()));
+    assert_eq!(vault_dir.join(".trash").read_dir().unwrap().count(), 0);
+
+    let config = Config { undo: UndoConfig { persist: true, ..UndoConfig::default() }, ..config };
+    app.journal.save(&config).unwrap();
+    let journal = Journal::load(&config);
+    assert_eq!(journal.redo, app.journal.redo);
+    assert_eq!(journal.limit, 100);
+}
@@ -0,0 +1,206 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use readitnow::app::{App, SearchKind};
+use readitnow::config::{Config, Keybindings};
+use readitnow::keybindings::{self, Action, AppAction, KeyMap};
+use readitnow::keys::{KeyList, KeySpec};
+use readitnow::models::Note;
+
+fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+    KeyEvent::new(code, modifiers)
+}
+
+#[test]
+fn test_parse_key_specs() {
+    let parse = |spec: &str| KeySpec::parse(spec).unwrap();
+
+    assert_eq!(parse("ctrl+d"), KeySpec::new(KeyCode::Char('d'), KeyModifiers::CONTROL));
+    assert_eq!(parse("Shift+Enter"), KeySpec::new(KeyCode::Enter, KeyModifiers::SHIFT));
+    assert_eq!(parse("alt+x"), KeySpec::new(KeyCode::Char('x'), KeyModifiers::ALT));
+    assert_eq!(parse("f5"), KeySpec::new(KeyCode::F(5), KeyModifiers::NONE));
+    assert_eq!(parse("space"), KeySpec::new(KeyCode::Char(' '), KeyModifiers::NONE));
+    assert_eq!(parse("shift+g"), parse("G"));
+    assert_eq!(parse("shift+tab"), parse("backtab"));
+    assert_eq!(parse("ctrl++"), KeySpec::new(KeyCode::Char('+'), KeyModifiers::CONTROL));
+    assert_eq!(parse("ctrl+shift+pagedown").to_string(), "ctrl+shift+pagedown");
+
+    assert!(KeySpec::parse("hyper+x").is_err());
+    assert!(KeySpec::parse("f25").is_err());
+    assert!(KeySpec::parse("ctrl+").is_err());
+}
+
+#[test]
+fn test_key_specs_match_terminal_events() {
+    let upper_g = KeySpec::parse("G").unwrap();
+    assert!(upper_g.matches(&key(KeyCode::Char('G'), KeyModifiers::SHIFT)));
+    assert!(upper_g.matches(&key(KeyCode::Char('G'), KeyModifiers::NONE)));
+    assert!(!upper_g.matches(&key(KeyCode::Char('g'), KeyModifiers::NONE)));
+    assert!(KeySpec::parse("shift+tab").unwrap().matches(&key(KeyCode::BackTab, KeyModifiers::SHIFT)));
+    assert!(!KeySpec::parse("enter").unwrap().matches(&key(KeyCode::Enter, KeyModifiers::SHIFT)));
+}
+
+#[test]
+fn test_keymap_reports_conflicts_and_bad_specs() {
+    let (keymap, problems) = KeyMap::new(&Keybindings::default());
+    assert!(problems.is_empty(), "{:?}", problems);
+    assert_eq!(keymap.action(&key(KeyCode::Char('j'), KeyModifiers::NONE)), Some(Action::Down));
+    assert_eq!(keymap.keys(Action::Up).len(), 2);
+
+    let keybindings: Keybindings = serde_yaml::from_str("
+quit: [q, ctrl+c]
+sort: j
+reverse_sort: hyper+s
+").unwrap();
+    let (keymap, problems) = KeyMap::new(&keybindings);
+    assert_eq!(problems.len(), 2);
+    assert!(problems[0].contains("`j` is bound to both down and sort"));
+    assert!(problems[1].starts_with("reverse_sort:"));
+    assert_eq!(keymap.action(&key(KeyCode::Char('c'), KeyModifiers::CONTROL)), Some(Action::Quit));
+    // Actions left out keep their defaults
+    assert_eq!(keymap.action(&key(KeyCode::Char('/'), KeyModifiers::NONE)), Some(Action::Search));
+}
+
+#[test]
+fn test_key_lists_round_trip_through_config() {
+    let mut config = Config::default();
+    config.keybindings.quit = KeyList::new(&["q", "ctrl+c"]);
+    let yaml = serde_yaml::to_string(&config).unwrap();
+    assert!(yaml.contains("page_down: pagedown"));
+    let loaded: Config = serde_yaml::from_str(&yaml).unwrap();
+    assert_eq!(loaded.keybindings, config.keybindings);
+}
+
+#[test]
+fn test_legacy_single_key_defaults_are_upgraded() {
+    // As written by versions before key lists, with one key changed by hand
+    let path = std::env::temp_dir().join("readitnow_legacy_keys_config.yaml");
+    std::fs::write(&path, "
+vault_path: /tmp/vault
+max_notes: 20
+excerpt_lines: 5
+thumbnail_cache: /tmp/thumbnails
+keybindings:
+  open_link: enter
+  open_file: shift+enter
+  up: up
+  down: down
+  left: a
+  right: right
+  page_up: pageup
+  page_down: pagedown
+  quit: q
+").unwrap();
+    let config = Config::load(&path).unwrap();
+    let defaults = Keybindings::default();
+    assert_eq!(config.keybindings.up, defaults.up);
+    assert_eq!(config.keybindings.right, KeyList::new(&["right", "l"]));
+    assert_eq!(config.keybindings.left, KeyList::new(&["a"]));
+    assert_eq!(config.keybindings.first, defaults.first);
+}
+
+#[test]
+fn test_handle_key_event_uses_configured_keys() {
+    let mut config = Config::default();
+    config.keybindings.right = KeyList::new(&["ctrl+n"]);
+    config.keybindings.quit = KeyList::new(&["ctrl+q"]);
+    let (keymap, _) = KeyMap::new(&config.keybindings);
+    let mut app = App::new(vec![Note::default(), Note::default()]);
+
+    keybindings::handle_key_event(key(KeyCode::Char('l'), KeyModifiers::NONE), &mut app, &config, &keymap);
+    assert_eq!(app.selected_note_index, 0);
+    keybindings::handle_key_event(key(KeyCode::Char('n'), KeyModifiers::CONTROL), &mut app, &config, &keymap);
+    assert_eq!(app.selected_note_index, 1);
+
+    let quit = |key_event| keybindings::handle_key_event(key_event, &mut App::new(Vec::new()), &config, &keymap) == AppAction::Quit;
+    assert!(!quit(key(KeyCode::Char('q'), KeyModifiers::NONE)));
+    assert!(quit(key(KeyCode::Char('q'), KeyModifiers::CONTROL)));
+}
+
+#[test]
+fn test_key_sequences_and_counts() {
+    let config = Config::default();
+    let (keymap, problems) = KeyMap::new(&config.keybindings);
+    assert!(problems.is_empty(), "{:?}", problems);
+    assert_eq!(keymap.keys(Action::First), vec!["gg", "home"]);
+    let notes: Vec<Note> = (0..10).map(|i| Note { title: format!("Note {}", i), ..Default::default() }).collect();
+    let mut app = App::new(notes);
+    let press = |app: &mut App, c: char| {
+        keybindings::handle_key_event(key(KeyCode::Char(c), KeyModifiers::NONE), app, &config, &keymap);
+    };
+
+    press(&mut app, 'G');
+    assert_eq!(app.selected_note().unwrap().title, "Note 9");
+    press(&mut app, 'g');
+    assert_eq!(app.pending_keys.to_string(), "g");
+    press(&mut app, 'g');
+    assert!(app.pending_keys.is_empty());
+    assert_eq!(app.selected_note().unwrap().title, "Note 0");
+
+    // 3l moves three notes within the 2 × 2 page, 7gg goes to the seventh note
+    press(&mut app, '3');
+    press(&mut app, 'l');
+    assert_eq!(app.selected_note_index, 3);
+    press(&mut app, '7');
+    press(&mut app, 'g');
+    assert_eq!(app.pending_keys.to_string(), "7g");
+    press(&mut app, 'g');
+    assert_eq!(app.selected_note().unwrap().title, "Note 6");
+
+    // A key that continues no sequence drops what was typed
+    press(&mut app, 'g');
+    press(&mut app, 'x');
+    assert!(app.pending_keys.is_empty());
+}
+
+#[test]
+fn test_digit_switches_view_unless_a_move_takes_the_count() {
+    let config = Config::default();
+    let (keymap, _) = KeyMap::new(&config.keybindings);
+    let notes: Vec<Note> = (0..10).map(|i| Note { title: format!("Note {}", i), ..Default::default() }).collect();
+    let mut app = App::new(notes);
+    app.set_views(&config.views);
+    let press = |app: &mut App, c: char| {
+        keybindings::handle_key_event(key(KeyCode::Char(c), KeyModifiers::NONE), app, &config, &keymap);
+    };
+
+    press(&mut app, '2');
+    assert_eq!(app.active_view, 1);
+    let now = std::time::Instant::now();
+    keybindings::handle_pending_timeout(&mut app, &config, &keymap, now + std::time::Duration::from_secs(2));
+    assert_eq!(app.active_view, 1);
+    assert!(app.pending_keys.is_empty());
+
+    press(&mut app, 'g');
+    press(&mut app, 't');
+    assert_eq!(app.active_view, 2);
+
+    // A second digit or a move takes the digit as a count, back in the view it left
+    press(&mut app, '1');
+    assert_eq!(app.active_view, 0);
+    press(&mut app, '2');
+    assert_eq!(app.active_view, 2);
+    assert_eq!(app.pending_keys.to_string(), "12");
+    keybindings::handle_key_event(key(KeyCode::Esc, KeyModifiers::NONE), &mut app, &config, &keymap);
+    press(&mut app, '1');
+    keybindings::handle_pending_timeout(&mut app, &config, &keymap, now + std::time::Duration::from_secs(4));
+    press(&mut app, '3');
+    assert_eq!(app.active_view, 2);
+    press(&mut app, 'l');
+    assert_eq!(app.active_view, 0);
+    assert_eq!(app.selected_note_index, 3);
+
+    // View switches and match steps count from the view the digit left too
+    press(&mut app, '2');
+    press(&mut app, 'g');
+    press(&mut app, 't');
+    assert_eq!(app.active_view, 2);
+    press(&mut app, '2');
+    press(&mut app, 'g');
+    press(&mut app, 't');
+    assert_eq!(app.active_view, 0);
+
+    app.set_search_query(SearchKind::Fuzzy, "note");
+    press(&mut app, '3');
+    press(&mut app, 'n');
+    assert_eq!(app.active_view, 0);
+    assert_eq!(app.selected_position(), 3);
+}
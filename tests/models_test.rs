@@ -40,3 +40,33 @@ fn test_config_load_save() {
 }
 
 
+
+#[test]
+fn test_note_metadata_for_card_footer() {
+    let markdown_content = "---\ncreated: 2025-03-14\n---\n[[ReadItLater]] [[Article]]\n\n# [Some post](https://www.example.com/posts/1?utm_source=x)\n\none two three four five six\n";
+    let note = Note::from_markdown(markdown_content, "Some post.md", 5, &Config::default());
+
+    assert_eq!(note.domain().as_deref(), Some("example.com"));
+    assert_eq!(note.saved_date(), chrono::NaiveDate::from_ymd_opt(2025, 3, 14));
+    assert_eq!(note.word_count, 8);
+    assert_eq!(note.reading_minutes(230), 1);
+}
+
+#[test]
+fn test_note_rating_from_frontmatter() {
+    let rating = |value: &str| Note::from_markdown(&format!("---\nrating: {}\n---\nbody\n", value), "Rated.md", 5, &Config::default()).rating;
+
+    assert_eq!(rating("4"), Some(4));
+    assert_eq!(rating("3.6"), Some(4));
+    assert_eq!(rating("\"2/5\""), Some(2));
+    assert_eq!(rating("★★★☆☆"), Some(3));
+    assert_eq!(rating("9"), Some(5));
+    assert_eq!(rating("great"), None);
+}
+
+#[test]
+fn test_markdown_link_escapes_brackets_in_the_title() {
+    let note = Note { title: "Rust [2024]".to_string(), url: Some("https://example.com".to_string()), ..Default::default() };
+    assert_eq!(note.markdown_link().as_deref(), Some("[Rust \\[2024\\]](https://example.com)"));
+    assert_eq!(Note { url: None, ..note }.markdown_link(), None);
+}
@@ -0,0 +1,110 @@
+use crossterm::event::{KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::{backend::TestBackend, Terminal};
+use readitnow::app::{App, Row};
+use readitnow::config::{Config, ViewConfig};
+use readitnow::models::Note;
+use readitnow::mouse::{self, Hit};
+use readitnow::ui::Renderer;
+use std::time::{Duration, Instant};
+
+fn mouse_event(kind: MouseEventKind, column: u16, row: u16) -> MouseEvent {
+    MouseEvent { kind, column, row, modifiers: KeyModifiers::NONE }
+}
+
+fn notes(count: usize) -> Vec<Note> {
+    (0..count)
+        .map(|i| Note { title: format!("Note {}", i), tags: vec![if i % 2 == 0 { "even" } else { "odd" }.to_string()], ..Default::default() })
+        .collect()
+}
+
+fn draw(renderer: &mut Renderer, app: &mut App) {
+    let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+    terminal.draw(|frame| renderer.render_app(app, frame)).unwrap();
+}
+
+#[test]
+fn test_hit_map_follows_the_layout() {
+    let config = Config::default();
+    let mut app = App::new(notes(10));
+    app.set_views(&config.views);
+    let mut renderer = Renderer::new();
+    draw(&mut renderer, &mut app);
+
+    // Tabs on the first line, a 2 × 2 grid of cards below, the status bar last
+    assert_eq!(renderer.hits.at(1, 0), Some(Hit::Tab(0)));
+    assert_eq!(renderer.hits.at(2, 1), Some(Hit::Row(0)));
+    assert_eq!(renderer.hits.at(60, 1), Some(Hit::Row(1)));
+    assert_eq!(renderer.hits.at(60, 20), Some(Hit::Row(3)));
+    assert_eq!(renderer.hits.at(2, 23), None);
+
+    // The popup covers the cards under it
+    app.open_related();
+    assert!(app.related.is_some());
+    draw(&mut renderer, &mut app);
+    assert!(matches!(renderer.hits.at(40, 12), Some(Hit::Popup | Hit::Related(_))));
+}
+
+#[test]
+fn test_clicks_select_switch_tabs_and_double_click_opens() {
+    let config = Config::default();
+    let mut app = App::new(notes(10));
+    app.set_views(&config.views);
+    let mut renderer = Renderer::new();
+    draw(&mut renderer, &mut app);
+    let now = Instant::now();
+    let left_click = MouseEventKind::Down(MouseButton::Left);
+
+    mouse::handle_mouse_event(mouse_event(left_click, 60, 20), &mut app, &config, &renderer.hits, now);
+    assert_eq!(app.selected_note_index, 3);
+
+    let views: Vec<ViewConfig> = serde_yaml::from_str("
+- name: Grouped
+  layout: { type: list, rows: 4 }
+  group_by: tag
+- name: Other
+").unwrap();
+    app.set_views(&views);
+    draw(&mut renderer, &mut app);
+    let tab_x = (0..40).find(|&x| renderer.hits.at(x, 0) == Some(Hit::Tab(1))).unwrap();
+    mouse::handle_mouse_event(mouse_event(left_click, tab_x, 0), &mut app, &config, &renderer.hits, now);
+    assert_eq!(app.active_view, 1);
+    mouse::handle_mouse_event(mouse_event(left_click, 1, 0), &mut app, &config, &renderer.hits, now);
+    assert_eq!(app.active_view, 0);
+
+    // Double clicking a group header folds it, a slow second click doesn't
+    draw(&mut renderer, &mut app);
+    assert!(matches!(app.rows[0], Row::Header { collapsed: false, .. }));
+    mouse::handle_mouse_event(mouse_event(left_click, 2, 1), &mut app, &config, &renderer.hits, now);
+    mouse::handle_mouse_event(mouse_event(left_click, 2, 1), &mut app, &config, &renderer.hits, now + Duration::from_secs(2));
+    assert!(matches!(app.rows[0], Row::Header { collapsed: false, .. }));
+    mouse::handle_mouse_event(mouse_event(left_click, 2, 1), &mut app, &config, &renderer.hits, now + Duration::from_millis(2200));
+    assert!(matches!(app.rows[0], Row::Header { collapsed: true, .. }));
+}
+
+#[test]
+fn test_wheel_pages_and_sidebar_clicks_check_tags() {
+    let config = Config::default();
+    let mut app = App::new(notes(10));
+    app.set_views(&config.views);
+    let mut renderer = Renderer::new();
+    draw(&mut renderer, &mut app);
+    let now = Instant::now();
+
+    mouse::handle_mouse_event(mouse_event(MouseEventKind::ScrollDown, 40, 10), &mut app, &config, &renderer.hits, now);
+    assert_eq!(app.current_page, 1);
+    mouse::handle_mouse_event(mouse_event(MouseEventKind::ScrollUp, 40, 10), &mut app, &config, &renderer.hits, now);
+    assert_eq!(app.current_page, 0);
+
+    // The tree is `even` then `odd`, one line each inside the border
+    app.toggle_tag_sidebar();
+    app.unfocus_tag_sidebar();
+    draw(&mut renderer, &mut app);
+    assert_eq!(renderer.hits.at(5, 3), Some(Hit::Tag(1)));
+    mouse::handle_mouse_event(mouse_event(MouseEventKind::Down(MouseButton::Left), 5, 3), &mut app, &config, &renderer.hits, now);
+    assert!(app.tags.focused);
+    assert_eq!(app.tags.checked, vec!["odd"]);
+    assert_eq!(app.visible.len(), 5);
+
+    mouse::handle_mouse_event(mouse_event(MouseEventKind::ScrollUp, 5, 10), &mut app, &config, &renderer.hits, now);
+    assert_eq!(app.tags.selected, 0);
+}
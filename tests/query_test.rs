@@ -0,0 +1,98 @@
+use chrono::NaiveDate;
+use readitnow::app::App;
+use readitnow::cli::{self, Command};
+use readitnow::models::{Note, NoteKind};
+use readitnow::search::query::{Comparison, Filter, NoteState};
+
+fn today() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2024, 6, 15).unwrap()
+}
+
+fn note(title: &str, url: &str, tags: &[&str], created: &str) -> Note {
+    Note {
+        title: title.to_string(),
+        url: Some(url.to_string()),
+        tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        created: NaiveDate::parse_from_str(created, "%Y-%m-%d").ok(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_filter_parses_fields_and_boolean_operators() {
+    let filter = Filter::parse_at("kind:video is:unread (tag:infra OR -domain:www.example.com) after:1m words>=100", today()).unwrap();
+    assert_eq!(filter, Filter::And(vec![
+        Filter::Kind(NoteKind::Video),
+        Filter::Is(NoteState::Unread),
+        Filter::Or(vec![
+            Filter::Tag("infra".to_string()),
+            Filter::Not(Box::new(Filter::Domain("example.com".to_string()))),
+        ]),
+        Filter::After(NaiveDate::from_ymd_opt(2024, 5, 15).unwrap()),
+        Filter::Words(Comparison::GreaterOrEqual, 100),
+    ]));
+
+    assert_eq!(Filter::parse_at("  ", today()).unwrap(), Filter::All);
+    assert_eq!(
+        Filter::parse_at(r#"tag:"to read" NOT "Rust Async""#, today()).unwrap(),
+        Filter::And(vec![Filter::Tag("to read".to_string()), Filter::Not(Box::new(Filter::Text("rust async".to_string())))]),
+    );
+}
+
+#[test]
+fn test_filter_errors_point_at_the_problem() {
+    let error = Filter::parse_at("tag:infra kind:podcast", today()).unwrap_err();
+    assert_eq!(error.position, 10);
+
+    assert_eq!(Filter::parse_at("(tag:infra", today()).unwrap_err().position, 10);
+    assert_eq!(Filter::parse_at("tag:infra)", today()).unwrap_err().position, 9);
+    assert!(Filter::parse_at("before:soon", today()).is_err());
+    assert!(Filter::parse_at("words>many", today()).is_err());
+    assert!(Filter::parse_at("rust OR", today()).is_err());
+}
+
+#[test]
+fn test_filter_matches_notes() {
+    let video = Note { read: false, ..note("Kubernetes talk", "https://www.youtube.com/watch?v=abc", &["infra/k8s"], "2024-06-01") };
+    let article = Note { read: true, starred: true, word_count: 3000, ..note("Borrowing", "https://blog.rust-lang.org/post", &["rust"], "2024-01-10") };
+    let matches = |query: &str, note: &Note| Filter::parse_at(query, today()).unwrap().matches(note);
+
+    assert!(matches("kind:video is:unread tag:infra after:30d", &video));
+    assert!(!matches("kind:video is:unread tag:infra after:30d", &article));
+    assert!(matches("domain:rust-lang.org is:starred words>2000 before:2024-02", &article));
+    assert!(matches("kind:tweet OR borrow", &article));
+    assert!(!matches("-is:read borrow", &article));
+    assert!(!matches("tag:infr", &video));
+}
+
+#[test]
+fn test_app_filter_combines_with_browsing() {
+    let notes = vec![
+        note("Video", "https://youtu.be/abc", &[], "2024-06-01"),
+        note("Article", "https://example.com/post", &[], "2024-06-01"),
+    ];
+    let mut app = App::new(notes);
+
+    app.set_filter_query("kind:video");
+    assert_eq!(app.visible, vec![0]);
+
+    // A query that doesn't parse keeps the last working filter
+    app.set_filter_query("kind:video (");
+    assert_eq!(app.visible, vec![0]);
+    assert!(app.filter_error.is_some());
+
+    app.clear_filter();
+    assert_eq!(app.visible, vec![0, 1]);
+}
+
+#[test]
+fn test_cli_arguments() {
+    let args = |args: &[&str]| cli::parse_args(args.iter().map(|arg| arg.to_string()));
+
+    assert_eq!(args(&[]), Ok(Command::Browse));
+    assert_eq!(args(&["--filter", "is:unread"]), Ok(Command::List { filter: "is:unread".to_string() }));
+    assert_eq!(args(&["--filter=kind:video"]), Ok(Command::List { filter: "kind:video".to_string() }));
+    assert_eq!(args(&["-h"]), Ok(Command::Help));
+    assert!(args(&["--filter"]).is_err());
+    assert!(args(&["--bogus"]).is_err());
+}
@@ -0,0 +1,246 @@
+use readitnow::app::{App, SearchKind};
+use readitnow::models::Note;
+use readitnow::search::fulltext::{parse_query, FullTextIndex, QueryClause};
+use readitnow::search::fuzzy::fuzzy_match;
+use readitnow::search::related::related_notes;
+use readitnow::config::{CardConfig, Config};
+use readitnow::vault::{self, VaultChange, VaultWatcher};
+use std::path::PathBuf;
+use readitnow::search::fuzzy_search;
+
+fn note(title: &str, excerpt: &str, tags: &[&str]) -> Note {
+    Note {
+        title: title.to_string(),
+        excerpt: excerpt.to_string(),
+        tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_fuzzy_match_positions_and_case() {
+    let found = fuzzy_match("rst", "Rust tips").unwrap();
+    assert_eq!(found.positions, vec![0, 2, 3]);
+
+    assert!(fuzzy_match("RUST", "rust tips").is_none());
+    assert!(fuzzy_match("xyz", "rust tips").is_none());
+}
+
+#[test]
+fn test_fuzzy_match_prefers_consecutive_and_word_starts() {
+    let consecutive = fuzzy_match("tip", "rust tips").unwrap();
+    let scattered = fuzzy_match("tip", "the big plan").unwrap();
+    assert!(consecutive.score > scattered.score);
+
+    let word_start = fuzzy_match("sp", "shell patterns").unwrap();
+    let mid_word = fuzzy_match("sp", "wasp").unwrap();
+    assert!(word_start.score > mid_word.score);
+}
+
+#[test]
+fn test_fuzzy_search_requires_every_term_and_ranks_titles_first() {
+    let notes = vec![
+        note("Kubernetes networking", "pods and services", &["infra"]),
+        note("Cooking", "a recipe mentioning kubernetes", &["food"]),
+        note("Rust async", "tokio internals", &["rust"]),
+    ];
+
+    let results = fuzzy_search(&notes, "kubernetes");
+    let order: Vec<usize> = results.iter().map(|result| result.note_index).collect();
+    assert_eq!(order, vec![0, 1]);
+    assert_eq!(results[0].highlights.title.len(), "kubernetes".len());
+    assert!(results[1].highlights.excerpt.len() == "kubernetes".len());
+
+    let results = fuzzy_search(&notes, "kube infra");
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].note_index, 0);
+}
+
+#[test]
+fn test_app_search_covers_notes_outside_browse_limit() {
+    let notes = vec![
+        note("First", "", &[]),
+        note("Second", "", &[]),
+        note("Third", "", &[]),
+        note("Fourth", "", &[]),
+        note("Fifth", "", &[]),
+        note("Sixth match", "", &[]),
+    ];
+    let mut app = App::new(notes);
+    app.browse_limit = 2;
+    app.refresh_visible();
+    assert_eq!(app.visible, vec![0, 1]);
+
+    app.set_search_query(SearchKind::Fuzzy, "th");
+    assert!(app.visible.contains(&5));
+    assert_eq!(app.selected_position(), 0);
+
+    app.next_match();
+    assert_eq!(app.selected_position(), 1);
+    app.previous_match();
+    app.previous_match();
+    assert_eq!(app.selected_position(), app.visible.len() - 1);
+
+    app.clear_search();
+    assert_eq!(app.visible, vec![0, 1]);
+}
+
+#[test]
+fn test_filters_and_related_jumps_reach_notes_outside_browse_limit() {
+    let mut notes = vec![
+        note("Tokio", "", &["rust"]),
+        note("Second", "", &[]),
+        note("Third", "", &[]),
+        note("Old async", "", &["rust"]),
+    ];
+    for (i, note) in notes.iter_mut().enumerate() {
+        note.path = PathBuf::from(format!("{}.md", i));
+    }
+    let mut app = App::new(notes);
+    app.browse_limit = 2;
+    app.refresh_visible();
+    assert_eq!(app.visible, vec![0, 1]);
+
+    app.set_filter_query("tag:rust");
+    assert_eq!(app.visible, vec![0, 3]);
+    app.clear_filter();
+    assert_eq!(app.visible, vec![0, 1]);
+
+    app.open_related();
+    assert_eq!(app.selected_related().unwrap().title, "Old async");
+    app.jump_to_related();
+    assert!(app.status_message.is_none());
+    assert_eq!(app.selected_note().unwrap().title, "Old async");
+}
+
+#[test]
+fn test_fulltext_query_parsing() {
+    assert_eq!(parse_query(r#"rust "borrow checker" lif*"#), vec![
+        QueryClause::Term("rust".to_string()),
+        QueryClause::Phrase(vec!["borrow".to_string(), "checker".to_string()]),
+        QueryClause::Prefix("lif".to_string()),
+    ]);
+}
+
+#[test]
+fn test_fulltext_search_phrases_prefixes_and_ranking() {
+    let mut index = FullTextIndex::default();
+    index.insert_text(PathBuf::from("a.md"), None, "The borrow checker rejects this. Lifetimes help the borrow checker.".to_string());
+    index.insert_text(PathBuf::from("b.md"), None, "A checker for borrowed books at the library, plus a long tail of unrelated words about shelves and catalogues.".to_string());
+    index.insert_text(PathBuf::from("c.md"), None, "Nothing relevant here.".to_string());
+
+    let phrase = index.search(r#""borrow checker""#);
+    assert_eq!(phrase.len(), 1);
+    assert_eq!(phrase[0].path, PathBuf::from("a.md"));
+    // Phrases are highlighted whole, the space between their words too
+    let highlighted: String = phrase[0].highlights.iter().map(|&i| phrase[0].snippet.chars().nth(i).unwrap()).collect();
+    assert_eq!(highlighted, "borrow checkerborrow checker");
+
+    let prefix = index.search("borrow* checker");
+    assert_eq!(prefix.iter().map(|r| r.path.clone()).collect::<Vec<_>>(), vec![PathBuf::from("a.md"), PathBuf::from("b.md")]);
+    assert!(prefix[0].score > prefix[1].score);
+
+    let snippet = &prefix[1];
+    let highlighted: String = snippet.highlights.iter().map(|&i| snippet.snippet.chars().nth(i).unwrap()).collect();
+    assert_eq!(highlighted, "checkerborrowed");
+
+    assert!(index.search("borrow missing").is_empty());
+}
+
+#[test]
+fn test_vault_changes_update_fulltext_search() {
+    let vault = std::env::temp_dir().join("readitnow_fulltext_test");
+    std::fs::create_dir_all(&vault).unwrap();
+    let path = vault.join("note.md");
+    std::fs::write(&path, "# Note\n\nAll about tokio runtimes.").unwrap();
+
+    let mut app = App::new(vec![note("Other", "", &[])]);
+    let changed = Note { title: "Note".to_string(), path: path.clone(), ..Default::default() };
+    app.apply_vault_changes(vec![VaultChange::Updated(Box::new(changed))]);
+    app.set_search_query(SearchKind::FullText, "tokio");
+    assert_eq!(app.visible.len(), 1);
+    assert!(app.excerpt(app.visible[0]).contains("tokio"));
+
+    app.apply_vault_changes(vec![VaultChange::Removed(path)]);
+    assert!(app.visible.is_empty());
+    assert_eq!(app.notes.len(), 1);
+}
+
+#[test]
+fn test_note_index_keeps_notes_changed_while_browsing() {
+    let vault = std::env::temp_dir().join("readitnow_note_index_test");
+    let _ = std::fs::remove_dir_all(&vault);
+    std::fs::create_dir_all(&vault).unwrap();
+    let config = Config {
+        vault_path: vault.to_string_lossy().to_string(),
+        index_cache: vault.join(".cache").to_string_lossy().to_string(),
+        ..Config::default()
+    };
+    let path = vault.join("note.md");
+    std::fs::write(&path, "# Note\n\nFirst version.").unwrap();
+    let notes = vault::scan_vault(&config).unwrap();
+    let mut watcher = VaultWatcher::new(&notes);
+    let mut app = App::new(notes);
+
+    std::fs::write(&path, "# Note\n\nSecond version.").unwrap();
+    let modified = std::fs::metadata(&path).unwrap().modified().unwrap() + std::time::Duration::from_secs(5);
+    std::fs::File::options().write(true).open(&path).unwrap().set_modified(modified).unwrap();
+    app.apply_vault_changes(watcher.poll(&config));
+    vault::save_index(&app.notes, &config).unwrap();
+
+    // The file changes again behind the index's back, keeping its mtime, so the next scan reads the index
+    std::fs::write(&path, "# Note\n\nThird version.").unwrap();
+    std::fs::File::options().write(true).open(&path).unwrap().set_modified(modified).unwrap();
+    let notes = vault::scan_vault(&config).unwrap();
+    assert!(notes[0].excerpt.contains("Second version"), "{}", notes[0].excerpt);
+    std::fs::remove_dir_all(&vault).unwrap();
+}
+
+#[test]
+fn test_related_notes_combine_text_tags_and_domain() {
+    let mut notes = vec![
+        note("Async Rust", "", &["ReadItLater", "rust"]),
+        note("Tokio internals", "", &["ReadItLater"]),
+        note("Rust macros", "", &["ReadItLater", "rust"]),
+        note("Gardening", "", &["ReadItLater"]),
+        note("Same site", "", &["ReadItLater"]),
+    ];
+    for (i, note) in notes.iter_mut().enumerate() {
+        note.path = PathBuf::from(format!("{}.md", i));
+    }
+    notes[0].url = Some("https://blog.example.com/async".to_string());
+    notes[4].url = Some("https://blog.example.com/other".to_string());
+
+    let mut index = FullTextIndex::default();
+    index.insert_text(notes[0].path.clone(), None, "tokio futures executor wakers polling runtime".to_string());
+    index.insert_text(notes[1].path.clone(), None, "the tokio runtime polls futures with wakers".to_string());
+    index.insert_text(notes[2].path.clone(), None, "declarative macros expand at compile time".to_string());
+    index.insert_text(notes[3].path.clone(), None, "tomatoes need sun and water".to_string());
+    index.insert_text(notes[4].path.clone(), None, "unrelated words entirely".to_string());
+
+    let similar = index.similar(&notes[0].path);
+    assert_eq!(similar[0].0, notes[1].path);
+    assert!(similar[0].1 > 0.0 && similar[0].1 <= 1.0);
+
+    let related = related_notes(&notes, 0, &index, &CardConfig::default(), 5);
+    let order: Vec<usize> = related.iter().map(|r| r.note_index).collect();
+    // The hidden ReadItLater tag doesn't relate the gardening note
+    assert_eq!(order, vec![1, 2, 4]);
+}
+
+#[test]
+fn test_jump_to_related_note_clears_the_search_hiding_it() {
+    let mut notes = vec![note("Tokio", "", &["rust"]), note("Async", "", &["rust"]), note("Other", "", &[])];
+    for (i, note) in notes.iter_mut().enumerate() {
+        note.path = PathBuf::from(format!("{}.md", i));
+    }
+    let mut app = App::new(notes);
+    app.set_search_query(SearchKind::Fuzzy, "tokio");
+    app.open_related();
+    assert_eq!(app.selected_related().unwrap().title, "Async");
+
+    app.jump_to_related();
+    assert!(app.related.is_none());
+    assert!(app.search.is_none());
+    assert_eq!(app.selected_note().unwrap().title, "Async");
+}
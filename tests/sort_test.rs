@@ -0,0 +1,94 @@
+use readitnow::app::App;
+use readitnow::config::{Config, SavedView, ViewState};
+use readitnow::models::Note;
+use readitnow::sort::{Sort, SortDirection, SortKey};
+use std::path::PathBuf;
+
+fn notes() -> Vec<Note> {
+    vec![
+        Note { title: "banana".to_string(), url: Some("https://b.example.com/x".to_string()), word_count: 300, rating: Some(2), ..Default::default() },
+        Note { title: "Apple".to_string(), word_count: 100, rating: None, ..Default::default() },
+        Note { title: "cherry".to_string(), url: Some("https://a.example.com/y".to_string()), word_count: 200, rating: Some(5), ..Default::default() },
+    ]
+    .into_iter()
+    .enumerate()
+    .map(|(i, note)| Note { path: PathBuf::from(format!("{}.md", i)), ..note })
+    .collect()
+}
+
+fn sorted(sort: Sort) -> Vec<usize> {
+    let mut indexes = vec![0, 1, 2];
+    sort.apply(&notes(), &mut indexes);
+    indexes
+}
+
+#[test]
+fn test_sort_keys_and_directions() {
+    assert_eq!(sorted(Sort::new(SortKey::Title, SortDirection::Asc)), vec![1, 0, 2]);
+    assert_eq!(sorted(Sort::new(SortKey::ReadingTime, SortDirection::Desc)), vec![0, 2, 1]);
+
+    // Notes without a rating or domain stay last both ways
+    assert_eq!(sorted(Sort::new(SortKey::Rating, SortDirection::Desc)), vec![2, 0, 1]);
+    assert_eq!(sorted(Sort::new(SortKey::Rating, SortDirection::Asc)), vec![0, 2, 1]);
+    assert_eq!(sorted(Sort::new(SortKey::Domain, SortDirection::Asc)), vec![2, 0, 1]);
+}
+
+#[test]
+fn test_random_sort_is_stable_for_a_seed() {
+    let random = |seed| sorted(Sort { seed, ..Sort::new(SortKey::Random, SortDirection::Asc) });
+    assert_eq!(random(7), random(7));
+    assert!((0..20).map(random).any(|order| order != random(7)));
+
+    let next = Sort::new(SortKey::Rating, SortDirection::Desc).next_key();
+    assert_eq!(next.by, SortKey::Random);
+    assert_eq!(next.next_key().by, SortKey::Modified);
+}
+
+#[test]
+fn test_resorting_keeps_the_selected_note() {
+    let mut app = App::new(notes());
+    app.next_note();
+    assert_eq!(app.selected_note().unwrap().title, "Apple");
+
+    app.set_sort(Sort::new(SortKey::Title, SortDirection::Desc));
+    assert_eq!(app.visible, vec![2, 0, 1]);
+    assert_eq!(app.selected_note().unwrap().title, "Apple");
+
+    app.reverse_sort();
+    assert_eq!(app.visible, vec![1, 0, 2]);
+    assert_eq!(app.selected_position(), 0);
+}
+
+#[test]
+fn test_view_sort_round_trips_through_config() {
+    let mut config = Config::default();
+    config.views[0].sort = Sort { seed: 42, ..Sort::new(SortKey::Random, SortDirection::Asc) };
+    let yaml = serde_yaml::to_string(&config).unwrap();
+    let loaded: Config = serde_yaml::from_str(&yaml).unwrap();
+    assert_eq!(loaded.views[0].sort, config.views[0].sort);
+}
+
+#[test]
+fn test_view_state_keeps_changes_out_of_the_config() {
+    let cache_dir = std::env::temp_dir().join("readitnow_view_state_test");
+    let _ = std::fs::remove_dir_all(&cache_dir);
+    let config = Config { index_cache: cache_dir.to_string_lossy().to_string(), ..Config::default() };
+    let inbox = &config.views[1];
+    let by_title = Sort::new(SortKey::Title, SortDirection::Asc);
+
+    let mut state = ViewState::load(&config);
+    assert!(!state.update(inbox, SavedView::of(inbox)));
+    assert!(state.update(inbox, SavedView { sort: by_title, ..SavedView::of(inbox) }));
+    assert!(!state.update(inbox, SavedView { sort: by_title, ..SavedView::of(inbox) }));
+    state.save(&config).unwrap();
+
+    let views = ViewState::load(&config).apply(&config.views);
+    assert_eq!(views[1].sort, by_title);
+    assert_eq!(views[1].filter, inbox.filter);
+    assert_eq!(views[0], config.views[0]);
+
+    // Going back to the configured sort forgets the saved one
+    assert!(state.update(inbox, SavedView::of(inbox)));
+    assert_eq!(state, ViewState::default());
+    let _ = std::fs::remove_dir_all(&cache_dir);
+}
@@ -0,0 +1,108 @@
+use chrono::NaiveDate;
+use readitnow::app::App;
+use readitnow::cli::{self, Command};
+use readitnow::models::Note;
+use readitnow::surprise::{Surprise, SurpriseConfig};
+
+fn today() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2024, 6, 15).unwrap()
+}
+
+fn note(title: &str, saved: &str, words: usize, rating: Option<u8>) -> Note {
+    Note {
+        title: title.to_string(),
+        created: NaiveDate::parse_from_str(saved, "%Y-%m-%d").ok(),
+        word_count: words,
+        rating,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_surprise_weights_favour_old_rated_and_short_notes() {
+    let config = SurpriseConfig::default();
+    let surprise = Surprise { config: &config, words_per_minute: 200, today: today() };
+
+    let fresh = surprise.weight(&note("fresh", "2024-06-14", 200, None));
+    let old = surprise.weight(&note("old", "2023-01-01", 200, None));
+    let loved = surprise.weight(&note("loved", "2024-06-14", 200, Some(5)));
+    let long = surprise.weight(&note("long", "2024-06-14", 20000, None));
+    assert!(old > fresh);
+    assert!(loved > fresh);
+    assert!(fresh > long);
+    assert!(long >= 1.0);
+}
+
+#[test]
+fn test_surprise_picks_unread_notes_within_budget() {
+    let config = SurpriseConfig { age_weight: 10.0, ..SurpriseConfig::default() };
+    let surprise = Surprise { config: &config, words_per_minute: 200, today: today() };
+    let notes = vec![
+        Note { read: true, ..note("read", "2020-01-01", 200, None) },
+        note("new", "2024-06-14", 200, None),
+        note("old", "2023-01-01", 200, None),
+        note("old and long", "2023-01-01", 10000, None),
+    ];
+    let all = [0, 1, 2, 3];
+
+    let mut picks = [0; 4];
+    for seed in 0..1000 {
+        picks[surprise.pick(&notes, &all, Some(5), seed).unwrap()] += 1;
+    }
+    assert_eq!(picks[0], 0);
+    assert_eq!(picks[3], 0);
+    assert!(picks[2] > picks[1] * 3);
+
+    assert_eq!(surprise.pick(&notes, &[0, 3], Some(5), 1), None);
+    assert_eq!(surprise.pick(&notes, &[0, 3], None, 1), Some(3));
+}
+
+#[test]
+fn test_app_surprise_selects_a_visible_note() {
+    let config = SurpriseConfig::default();
+    let surprise = Surprise { config: &config, words_per_minute: 200, today: today() };
+    let mut notes: Vec<Note> = (0..9).map(|i| Note { read: true, ..note(&format!("Note {}", i), "2024-01-01", 100, None) }).collect();
+    notes[6].read = false;
+    let mut app = App::new(notes);
+
+    app.surprise_me(&surprise, None);
+    assert_eq!(app.selected_note().unwrap().title, "Note 6");
+    assert_eq!(app.current_page, 1);
+
+    app.notes[6].read = true;
+    app.surprise_me(&surprise, None);
+    assert!(app.status_message.is_some());
+}
+
+#[test]
+fn test_app_surprise_reaches_notes_past_the_browse_limit() {
+    let config = SurpriseConfig::default();
+    let surprise = Surprise { config: &config, words_per_minute: 200, today: today() };
+    let mut notes: Vec<Note> = (0..5).map(|i| Note { read: true, ..note(&format!("Note {}", i), "2024-01-01", 100, None) }).collect();
+    notes[4].read = false;
+    notes[4].tags = vec!["backlog".to_string()];
+    let mut app = App::new(notes);
+    app.browse_limit = 2;
+    app.refresh_visible();
+    assert!(!app.visible.contains(&4));
+
+    app.surprise_me(&surprise, None);
+    assert_eq!(app.selected_note().unwrap().title, "Note 4");
+
+    app.set_filter_query("tag:reading");
+    app.surprise_me(&surprise, None);
+    assert!(app.status_message.is_some());
+}
+
+#[test]
+fn test_surprise_cli_arguments() {
+    let args = |args: &[&str]| cli::parse_args(args.iter().map(|arg| arg.to_string()));
+
+    assert_eq!(args(&["--surprise"]), Ok(Command::Surprise { filter: None, max_minutes: None }));
+    assert_eq!(
+        args(&["-s", "--max-minutes=10", "-f", "kind:video"]),
+        Ok(Command::Surprise { filter: Some("kind:video".to_string()), max_minutes: Some(10) }),
+    );
+    assert!(args(&["--max-minutes", "10"]).is_err());
+    assert!(args(&["-s", "-m", "soon"]).is_err());
+}
@@ -0,0 +1,85 @@
+use readitnow::app::{App, TagMatch};
+use readitnow::config::CardConfig;
+use readitnow::models::Note;
+use readitnow::tags::tag_tree;
+use readitnow::vault::VaultChange;
+
+fn note(path: &str, tags: &[&str], read: bool) -> Note {
+    Note {
+        title: path.to_string(),
+        path: path.into(),
+        tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        read,
+        ..Default::default()
+    }
+}
+
+fn notes() -> Vec<Note> {
+    vec![
+        note("a.md", &["ReadItLater", "infra/k8s", "rust"], false),
+        note("b.md", &["infra", "Infra/k8s"], true),
+        note("c.md", &["rust", "readitnow/read"], true),
+        note("d.md", &["infra/terraform"], false),
+    ]
+}
+
+#[test]
+fn test_tag_tree_nests_and_counts() {
+    let card = CardConfig { hidden_tags: vec!["ReadItLater".to_string()], ..CardConfig::default() };
+    let tree: Vec<(String, usize, usize, usize)> = tag_tree(&notes(), &card).into_iter()
+        .map(|node| (node.tag, node.depth, node.unread, node.total))
+        .collect();
+
+    assert_eq!(tree, vec![
+        ("infra".to_string(), 0, 2, 3),
+        ("infra/k8s".to_string(), 1, 1, 2),
+        ("infra/terraform".to_string(), 1, 1, 1),
+        ("rust".to_string(), 0, 1, 2),
+    ]);
+}
+
+#[test]
+fn test_checked_tags_filter_with_and_or() {
+    let mut app = App::new(notes());
+    app.toggle_tag_sidebar();
+    assert!(app.tags.open && app.tags.focused);
+    assert_eq!(app.tags.selected_tag().unwrap().tag, "infra");
+
+    app.only_tag();
+    assert_eq!(app.visible.len(), 3);
+
+    app.next_tag();
+    app.only_tag();
+    app.next_tag();
+    app.next_tag();
+    app.toggle_tag_checked();
+    assert_eq!(app.tags.matching, TagMatch::All);
+    assert_eq!(app.visible.len(), 1);
+
+    app.toggle_tag_match();
+    assert_eq!(app.visible.len(), 3);
+    assert_eq!(app.tags.label(), "infra/k8s | rust");
+
+    // Closing keeps the filter, jumping to a hidden note drops it
+    app.toggle_tag_sidebar();
+    assert!(!app.tags.open);
+    assert_eq!(app.visible.len(), 3);
+    app.jump_to_note(3);
+    assert!(app.tags.checked.is_empty());
+    assert_eq!(app.selected_note().unwrap().title, "d.md");
+}
+
+#[test]
+fn test_tag_tree_follows_vault_changes() {
+    let mut app = App::new(notes());
+    app.toggle_tag_sidebar();
+    app.next_tag();
+    assert_eq!(app.tags.selected_tag().unwrap().tag, "infra/k8s");
+
+    app.apply_vault_changes(vec![VaultChange::Updated(Box::new(note("e.md", &["go"], false)))]);
+    assert_eq!(app.tags.tree[0].tag, "go");
+    assert_eq!(app.tags.selected_tag().unwrap().tag, "infra/k8s");
+
+    app.apply_vault_changes(vec![VaultChange::Removed("d.md".into())]);
+    assert!(app.tags.tree.iter().all(|node| node.tag != "infra/terraform"));
+}
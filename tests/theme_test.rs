@@ -0,0 +1,52 @@
+use ratatui::style::{Color, Modifier};
+use readitnow::config::ThemeConfig;
+use readitnow::theme::{StyleSpec, Theme};
+
+#[test]
+fn test_style_spec_to_style() {
+    let spec = StyleSpec::fg("yellow").with_bg("#102030").with_modifiers(&["bold", "dim", "unknown"]);
+    let style = spec.style();
+
+    assert_eq!(style.fg, Some(Color::Yellow));
+    assert_eq!(style.bg, Some(Color::Rgb(0x10, 0x20, 0x30)));
+    assert!(style.add_modifier.contains(Modifier::BOLD | Modifier::DIM));
+}
+
+#[test]
+fn test_theme_merge_keeps_unset_fields() {
+    let overrides = Theme {
+        selected_border: StyleSpec::fg("magenta"),
+        ..Theme::default()
+    };
+    let theme = Theme::dark().merge(&overrides);
+
+    assert_eq!(theme.selected_border.fg.as_deref(), Some("magenta"));
+    assert_eq!(theme.selected_border.modifiers, Theme::dark().selected_border.modifiers);
+    assert_eq!(theme.border, Theme::dark().border);
+}
+
+#[test]
+fn test_theme_file_extends_builtin() {
+    let themes_dir = std::env::temp_dir().join("readitnow_theme_test");
+    std::fs::create_dir_all(&themes_dir).unwrap();
+    std::fs::write(
+        themes_dir.join("solarized.yaml"),
+        "extends: light\ntitle:\n  fg: \"#268bd2\"\n",
+    ).unwrap();
+
+    let theme = Theme::load("solarized", Some(&themes_dir)).unwrap();
+    assert_eq!(theme.title.fg.as_deref(), Some("#268bd2"));
+    assert_eq!(theme.border, Theme::light().border);
+
+    assert!(Theme::load("missing", Some(&themes_dir)).is_err());
+    assert_eq!(Theme::load("high-contrast", None).unwrap(), Theme::high_contrast());
+
+    std::fs::remove_dir_all(&themes_dir).unwrap();
+}
+
+#[test]
+fn test_theme_config_without_name_uses_dark() {
+    let theme: ThemeConfig = serde_yaml::from_str("title: { fg: red }").unwrap();
+    assert_eq!(theme.name, "dark");
+    assert_eq!(theme.overrides.title.fg.as_deref(), Some("red"));
+}